@@ -1,38 +1,71 @@
-use super_permutations_attempt::{bruteforce, bruteforce_optimise};
-use super_permutations_attempt::base::SuperPermHandling;
-
-use criterion::{
-    black_box,
-    criterion_group,
-    criterion_main,
-    Criterion
-};
-
-const N: usize = 5;
-
-fn common_bench(c: &mut Criterion, superperm_h: impl SuperPermHandling, group_name: &String) {
-    let n: usize = black_box(N);
-    let superperm = black_box(superperm_h.create_superperm(n));
-    let mut bruteforce_group = c.benchmark_group(group_name);
-
-    bruteforce_group.bench_function(
-        "creation",
-        |b| b.iter(|| superperm_h.create_superperm(n))
-    );
-    bruteforce_group.bench_function(
-        "checking",
-        |b| b.iter(|| superperm_h.check_superperm(&superperm, n))
-    );    
-    bruteforce_group.finish();
-}
+// Benchmarks creation and checking for every implementation in the registry
+// across a range of n, so new implementations are benchmarked without
+// changes here. Set SUPERPERM_BENCH_N to choose n (e.g "3,4,5", the default).
+// Alongside criterion's own output a summary table is printed and the mean
+// times are written to target/bench_all.csv.
+use std::collections::BTreeMap;
+use std::fs;
+use std::time::{Duration, Instant};
+
+use super_permutations_attempt::base::all_handles;
+
+use criterion::{black_box, Criterion};
+
+const DEFAULT_NS: [usize; 3] = [3, 4, 5];
+const CSV_PATH: &str = "target/bench_all.csv";
 
-fn bruteforce_bench(c: &mut Criterion) {
-    common_bench(c, bruteforce::Handle{}, &String::from("bruteforce"));
+/// Total time and iterations measured for each (operation, n, algorithm)
+type Totals = BTreeMap<(&'static str, usize, &'static str), (Duration, u64)>;
+
+fn bench_ns() -> Vec<usize> {
+    match std::env::var("SUPERPERM_BENCH_N") {
+        Ok(list) => list.split(',').map(|n| n.trim().parse().expect("SUPERPERM_BENCH_N should list numbers")).collect(),
+        Err(_) => DEFAULT_NS.to_vec(),
+    }
 }
 
-fn bruteforce_optimise_bench(c: &mut Criterion) {
-    common_bench(c, bruteforce_optimise::Handle{}, &String::from("bruteforce_optimise"));
+/// Runs the closure iters times, adding the time taken to the totals
+fn timed<F: FnMut()>(totals: &mut Totals, key: (&'static str, usize, &'static str), iters: u64, mut f: F) -> Duration {
+    let start = Instant::now();
+    for _ in 0..iters {
+        f();
+    }
+    let elapsed = start.elapsed();
+    let total = totals.entry(key).or_insert((Duration::ZERO, 0));
+    total.0 += elapsed;
+    total.1 += iters;
+    return elapsed;
 }
 
-criterion_group!(benches, bruteforce_bench, bruteforce_optimise_bench);
-criterion_main!(benches);
\ No newline at end of file
+fn main() {
+    let mut criterion = Criterion::default().configure_from_args();
+    let mut totals = Totals::new();
+    for n in bench_ns() {
+        for operation in ["creation", "checking"] {
+            let mut group = criterion.benchmark_group(format!("{}/n={}", operation, n));
+            for (name, handle) in all_handles() {
+                let superperm = handle.create_superperm(n);
+                group.bench_function(name, |b| b.iter_custom(|iters| {
+                    timed(&mut totals, (operation, n, name), iters, || match operation {
+                        "creation" => drop(black_box(handle.create_superperm(black_box(n)))),
+                        _ => drop(black_box(handle.check_superperm(black_box(&superperm), n))),
+                    })
+                }));
+            }
+            group.finish();
+        }
+    }
+    criterion.final_summary();
+
+    let mut csv = String::from("operation,n,algorithm,mean_ns\n");
+    println!("{:<10} {:<4} {:<20} {:>14}", "operation", "n", "algorithm", "mean");
+    for ((operation, n, algorithm), (total, iters)) in &totals {
+        let mean_ns = total.as_nanos() as f64 / (*iters).max(1) as f64;
+        csv.push_str(&format!("{},{},{},{:.1}\n", operation, n, algorithm, mean_ns));
+        println!("{:<10} {:<4} {:<20} {:>12.1}us", operation, n, algorithm, mean_ns / 1000.0);
+    }
+    match fs::write(CSV_PATH, csv) {
+        Ok(()) => println!("written to {}", CSV_PATH),
+        Err(err) => eprintln!("couldn't write {}: {}", CSV_PATH, err),
+    }
+}
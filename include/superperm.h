@@ -0,0 +1,38 @@
+#ifndef SUPERPERM_H
+#define SUPERPERM_H
+
+/* Generated with cbindgen from src/ffi.rs, do not edit by hand */
+
+#include <stddef.h>
+#include <stdint.h>
+
+/**
+ * Creates a superpermutation of the tokens 1..=n, writing its length to
+ * out_len. Returns null if out_len is null or n is above the largest n
+ * supported (MAX_SUPPORTED_N, 20 on 64 bit platforms).
+ *
+ * # Safety
+ * out_len must be null or valid to write a size_t to.
+ */
+size_t *superperm_create(size_t n, size_t *out_len);
+
+/**
+ * Checks whether the len tokens at sequence make up a superpermutation of
+ * the tokens 1..=n. Returns 1 if so, 0 if not and -1 if sequence is null
+ * with a non-zero len or n is above the largest n supported.
+ *
+ * # Safety
+ * sequence must be valid to read len tokens from, or may be null if len is 0.
+ */
+int32_t superperm_check(const size_t *sequence, size_t len, size_t n);
+
+/**
+ * Frees a sequence returned by superperm_create. Does nothing if sequence is null.
+ *
+ * # Safety
+ * sequence must be null or a pointer returned by superperm_create along with
+ * the length it gave, and not freed already.
+ */
+void superperm_free(size_t *sequence, size_t len);
+
+#endif  /* SUPERPERM_H */
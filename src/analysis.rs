@@ -0,0 +1,942 @@
+// Analysis of the structure of a sequence as a superpermutation: how much of
+// the permutation space it covers and how efficiently it does so.
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::permutation::{checked_factorial, is_permutation, lex_rank, lex_unrank};
+use crate::records;
+
+/// Summary statistics of a sequence for the tokens (1,2,3, ...,n)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SuperPermStats {
+    /// Length of the sequence
+    pub length: usize,
+    /// Amount of unique tokens
+    pub n: usize,
+    /// Amount of distinct permutations appearing within the sequence
+    pub distinct_perms: usize,
+    /// Amount of times a permutation appears again after its first appearance
+    pub duplicate_visits: usize,
+    /// Length beyond the minimum possible to visit distinct_perms permutations
+    pub waste: usize,
+    /// Index of the token that completes the coverage of every permutation.
+    /// None if not every permutation is covered.
+    pub completion_index: Option<usize>,
+}
+
+impl SuperPermStats {
+    /// Calculates the statistics of a sequence for the tokens (1,2,3, ...,n)
+    ///
+    /// E.g 123121321 for n=3 covers all 6 permutations with 1 token of waste,
+    /// as the shortest possible sequence covering 6 permutations has length 8.
+    pub fn compute(sequence: &[usize], n: usize) -> SuperPermStats {
+        let total_perms = checked_factorial(n);
+        let mut seen: HashSet<&[usize]> = HashSet::new();
+        let mut duplicate_visits = 0;
+        let mut completion_index = None;
+        if n > 0 {
+            for (i, window) in sequence.windows(n).enumerate() {
+                if !is_permutation(window) {
+                    continue;
+                }
+                if !seen.insert(window) {
+                    duplicate_visits += 1;
+                } else if completion_index.is_none() && Some(seen.len()) == total_perms {
+                    completion_index = Some(i + n - 1);
+                }
+            }
+        }
+        let distinct_perms = seen.len();
+        // Each distinct permutation after the first adds at least one token
+        let minimum_length = if distinct_perms == 0 { 0 } else { n + distinct_perms - 1 };
+        return SuperPermStats {
+            length: sequence.len(),
+            n,
+            distinct_perms,
+            duplicate_visits,
+            waste: sequence.len().saturating_sub(minimum_length),
+            completion_index,
+        };
+    }
+
+    /// Whether every permutation is covered
+    pub fn is_complete(&self) -> bool {
+        return self.completion_index.is_some();
+    }
+}
+
+impl fmt::Display for SuperPermStats {
+    /// One statistic per line, e.g "waste:            1"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "n:                {}", self.n)?;
+        writeln!(f, "length:           {}", self.length)?;
+        writeln!(f, "distinct perms:   {}", self.distinct_perms)?;
+        writeln!(f, "duplicate visits: {}", self.duplicate_visits)?;
+        writeln!(f, "waste:            {}", self.waste)?;
+        match self.completion_index {
+            Some(index) => write!(f, "completion index: {}", index),
+            None => write!(f, "completion index: incomplete"),
+        }
+    }
+}
+
+/// Iterator over the first appearance of each permutation in a sequence. See
+/// covered_in_order
+pub struct CoveredInOrder<'a> {
+    windows: std::iter::Enumerate<std::slice::Windows<'a, usize>>,
+    /// Whether each permutation has been seen, indexed by lexicographic rank
+    covered: Vec<bool>,
+}
+
+impl<'a> Iterator for CoveredInOrder<'a> {
+    type Item = (usize, &'a [usize]);
+
+    fn next(&mut self) -> Option<(usize, &'a [usize])> {
+        for (i, window) in self.windows.by_ref() {
+            if is_permutation(window) {
+                let rank = lex_rank(window).unwrap();
+                if self.covered[rank] == false {
+                    self.covered[rank] = true;
+                    return Some((i, window));
+                }
+            }
+        }
+        return None;
+    }
+}
+
+/// The permutations of (1,2,3, ...,n) appearing in a sequence in the order
+/// they first appear, along with the start index of that first appearance.
+/// This is the visit order that diff and the other visit based tools work
+/// with. Gives nothing for n = 0.
+///
+/// E.g 1213121 for n=2 gives (0, [1,2]) and (1, [2,1]).
+/// Panics if n! overflows.
+pub fn covered_in_order(sequence: &[usize], n: usize) -> CoveredInOrder<'_> {
+    let windows = match n {
+        0 => sequence[..0].windows(1),
+        n => sequence.windows(n),
+    };
+    let total = checked_factorial(n).expect("n! overflows");
+    return CoveredInOrder { windows: windows.enumerate(), covered: vec![false; total] };
+}
+
+/// Start index of each window that is the first appearance of a permutation,
+/// in the order they appear.
+pub(crate) fn first_visits(sequence: &[usize], n: usize) -> Vec<usize> {
+    return covered_in_order(sequence, n).map(|(start, _)| start).collect();
+}
+
+/// How often each token appears in a sequence and which characters are waste
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenStats {
+    /// Amount of times each token appears. Element t-1 is for the token t
+    pub frequencies: Vec<usize>,
+    /// Amount of times each token would appear if every token was equally common
+    pub expected_frequency: f64,
+    /// Indices of the characters that don't complete a newly visited
+    /// permutation, besides the characters leading up to the first one.
+    /// There are SuperPermStats::waste of them
+    pub wasted_positions: Vec<usize>,
+}
+
+/// Calculates the token frequencies and wasted positions of a sequence for the
+/// tokens (1,2,3, ...,n). Tokens outside of 1..=n are not counted.
+///
+/// E.g 123121321 for n=3 has the frequencies [4, 3, 2] and the 1 at index 5 is waste
+pub fn token_stats(sequence: &[usize], n: usize) -> TokenStats {
+    let mut frequencies = vec![0; n];
+    for token in sequence {
+        if (1..n + 1).contains(token) {
+            frequencies[token - 1] += 1;
+        }
+    }
+    let expected_frequency = if n == 0 { 0.0 } else { sequence.len() as f64 / n as f64 };
+
+    let mut useful = vec![false; sequence.len()];
+    let starts = first_visits(sequence, n);
+    if let Some(first) = starts.first() {
+        useful[*first..*first + n - 1].fill(true);
+    }
+    for start in starts {
+        useful[start + n - 1] = true;
+    }
+    let wasted_positions = (0..sequence.len()).filter(|i| useful[*i] == false).collect();
+    return TokenStats { frequencies, expected_frequency, wasted_positions };
+}
+
+/// Histogram of how many tokens each newly visited permutation shares with
+/// the previously visited new permutation, indexed by overlap size 0..n-1.
+/// Repeat visits are skipped over, so they count against the overlap.
+///
+/// E.g 123121321 for n=3 gives [0, 1, 4]: four permutations are reached by
+/// appending one token and 213 is reached by appending two.
+pub fn overlap_histogram(sequence: &[usize], n: usize) -> Vec<usize> {
+    let mut histogram = vec![0; n];
+    let starts = first_visits(sequence, n);
+    for pair in starts.windows(2) {
+        let overlap = n.saturating_sub(pair[1] - pair[0]);
+        histogram[overlap] += 1;
+    }
+    return histogram;
+}
+
+/// Counts the transitions between consecutive newly visited permutations by
+/// their weight k, the amount of tokens appended (n minus the overlap). In the
+/// superpermutation literature a weight k transition moves between k-cycles,
+/// e.g weight 1 transitions stay within a 1-cycle (rotating the permutation)
+/// and weight 2 transitions move on to the next 1-cycle of a 2-cycle.
+///
+/// Element k-1 is the count of weight k transitions for k in 1..=n. E.g the
+/// recursive construction for n=3 (123121321) gives [4, 1, 0].
+pub fn k_cycle_counts(sequence: &[usize], n: usize) -> Vec<usize> {
+    // Overlap n-k is weight k, so the histogram read backwards
+    let mut counts = overlap_histogram(sequence, n);
+    counts.reverse();
+    return counts;
+}
+
+/// Which symmetries a sequence has, for the tokens (1,2,3, ...,n). The
+/// complement of a token i is n+1-i.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Symmetries {
+    /// The sequence reads the same backwards
+    pub reversal: bool,
+    /// Complementing every token gives the same sequence
+    pub complement: bool,
+    /// Reversing and complementing gives the same sequence
+    pub reverse_complement: bool,
+    /// The reverse of every covered permutation is also covered
+    pub covered_reversal: bool,
+    /// The complement of every covered permutation is also covered
+    pub covered_complement: bool,
+}
+
+/// Checks which symmetries a sequence has. Minimal superpermutations for small
+/// n are palindromes for example. Symmetries of the covered permutations are
+/// only of interest for incomplete sequences, as every permutation of a
+/// complete one is covered.
+///
+/// E.g 123121321 for n=3 is a palindrome but not its own complement (321323123)
+pub fn symmetries(sequence: &[usize], n: usize) -> Symmetries {
+    let complement = |token: &usize| (n + 1).wrapping_sub(*token);
+    let reversed: Vec<usize> = sequence.iter().rev().copied().collect();
+    let complemented: Vec<usize> = sequence.iter().map(complement).collect();
+    let reverse_complemented: Vec<usize> = reversed.iter().map(complement).collect();
+
+    let mut covered: HashSet<&[usize]> = HashSet::new();
+    if n > 0 {
+        covered = sequence.windows(n).filter(|window| is_permutation(window)).collect();
+    }
+    let covered_reversal = covered.iter().all(|perm| {
+        let reversed: Vec<usize> = perm.iter().rev().copied().collect();
+        covered.contains(reversed.as_slice())
+    });
+    let covered_complement = covered.iter().all(|perm| {
+        let complemented: Vec<usize> = perm.iter().map(complement).collect();
+        covered.contains(complemented.as_slice())
+    });
+    return Symmetries {
+        reversal: reversed == sequence,
+        complement: complemented == sequence,
+        reverse_complement: reverse_complemented == sequence,
+        covered_reversal,
+        covered_complement,
+    };
+}
+
+/// Part of two sequences where their visit orders diverge
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DivergentSegment {
+    /// Range of indices into the visit order of the first sequence
+    pub a_visits: (usize, usize),
+    /// Range of indices into the visit order of the second sequence
+    pub b_visits: (usize, usize),
+    /// Amount of characters the first sequence spends on the segment
+    pub a_length: usize,
+    /// Amount of characters the second sequence spends on the segment
+    pub b_length: usize,
+}
+
+/// Comparison of the visit orders of two sequences. See diff
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VisitDiff {
+    /// Amount of leading newly visited permutations both sequences visit in the same order
+    pub common_prefix: usize,
+    /// Parts where the sequences diverge, in order. Everything else is common
+    /// to both sequences and costs the same amount of characters
+    pub segments: Vec<DivergentSegment>,
+    /// Length of the second sequence minus the length of the first. This is
+    /// the sum of b_length - a_length over the segments
+    pub length_difference: isize,
+}
+
+/// A longest common subsequence of two visit orders, as pairs of indices into
+/// each. Visit orders never repeat a permutation, so this is the longest run
+/// of shared visits whose positions in b increase along a, found by patience
+/// sorting in O(m log m) time and linear space. A dense LCS table would be
+/// (n!)² entries, e.g 6.5GB for n=8.
+fn common_visits(a_order: &[usize], b_order: &[usize]) -> Vec<(usize, usize)> {
+    let b_index: HashMap<usize, usize> = b_order.iter().enumerate().map(|(j, rank)| (*rank, j)).collect();
+    let pairs: Vec<(usize, usize)> =
+        a_order.iter().enumerate().filter_map(|(i, rank)| Some((i, *b_index.get(rank)?))).collect();
+    // tails[k] is the pair ending the increasing run of length k + 1 with the
+    // smallest index into b found so far, and previous links each pair to
+    // the one before it in its run
+    let mut tails: Vec<usize> = Vec::new();
+    let mut previous = vec![None; pairs.len()];
+    for (p, (_, j)) in pairs.iter().enumerate() {
+        let k = tails.partition_point(|t| pairs[*t].1 < *j);
+        if k > 0 {
+            previous[p] = Some(tails[k - 1]);
+        }
+        if k == tails.len() {
+            tails.push(p);
+        } else {
+            tails[k] = p;
+        }
+    }
+    let mut anchors = Vec::with_capacity(tails.len());
+    let mut next = tails.last().copied();
+    while let Some(p) = next {
+        anchors.push(pairs[p]);
+        next = previous[p];
+    }
+    anchors.reverse();
+    return anchors;
+}
+
+/// Aligns the visit orders (the newly visited permutations in order) of two
+/// sequences for the tokens (1,2,3, ...,n) and reports where they diverge.
+///
+/// The longest common subsequence of the visit orders splits both sequences
+/// into common visits and divergent segments. A segment covers the characters
+/// from the end of the common visit before it up to the end of the common
+/// visit after it, so the characters of each sequence are attributed to
+/// exactly one segment or common visit.
+pub fn diff(a: &[usize], b: &[usize], n: usize) -> VisitDiff {
+    let (a_starts, a_order): (Vec<usize>, Vec<usize>) =
+        covered_in_order(a, n).map(|(start, perm)| (start, lex_rank(perm).unwrap())).unzip();
+    let (b_starts, b_order): (Vec<usize>, Vec<usize>) =
+        covered_in_order(b, n).map(|(start, perm)| (start, lex_rank(perm).unwrap())).unzip();
+    let common_prefix = a_order.iter().zip(&b_order).take_while(|(x, y)| x == y).count();
+
+    let anchors = common_visits(&a_order, &b_order);
+
+    // Walk between consecutive common visits, with the end of the sequences
+    // acting as a common visit of its own
+    let mut segments = Vec::new();
+    let (mut next_visits, mut prev_ends) = ((0, 0), (0, 0));
+    let end = ((a_order.len(), b_order.len()), (a.len(), b.len()));
+    let bounds = anchors.iter().map(|(i, j)| ((*i, *j), (a_starts[*i] + n, b_starts[*j] + n)));
+    for (visits, ends) in bounds.chain([end]) {
+        let segment = DivergentSegment {
+            a_visits: (next_visits.0, visits.0),
+            b_visits: (next_visits.1, visits.1),
+            a_length: ends.0 - prev_ends.0,
+            b_length: ends.1 - prev_ends.1,
+        };
+        if segment.a_visits.0 != segment.a_visits.1
+            || segment.b_visits.0 != segment.b_visits.1
+            || segment.a_length != segment.b_length
+        {
+            segments.push(segment);
+        }
+        next_visits = (visits.0 + 1, visits.1 + 1);
+        prev_ends = ends;
+    }
+    return VisitDiff {
+        common_prefix,
+        segments,
+        length_difference: b.len() as isize - a.len() as isize,
+    };
+}
+
+/// For each position of the sequence, the amount of distinct permutations
+/// covered by windows ending at or before it.
+///
+/// E.g 1231 for n=3 gives [0, 0, 1, 2]
+pub fn coverage_timeline(sequence: &[usize], n: usize) -> Vec<usize> {
+    let mut timeline = vec![0; sequence.len()];
+    if n == 0 {
+        return timeline;
+    }
+    let mut seen: HashSet<&[usize]> = HashSet::new();
+    for (i, covered) in timeline.iter_mut().enumerate() {
+        if i + 1 >= n {
+            let window = &sequence[i + 1 - n..i + 1];
+            if is_permutation(window) {
+                seen.insert(window);
+            }
+        }
+        *covered = seen.len();
+    }
+    return timeline;
+}
+
+/// Count of how many windows cover each permutation of (1,2,3, ...,n), which
+/// can be updated a window at a time as a sequence is edited
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coverage {
+    n: usize,
+    /// Amount of windows equal to each permutation, indexed by lexicographic rank
+    counts: Vec<usize>,
+    /// Amount of permutations with a count of 0
+    missing: usize,
+}
+
+impl Coverage {
+    /// Creates a coverage with nothing covered. Panics if n! overflows.
+    pub fn new(n: usize) -> Coverage {
+        let total_perms = checked_factorial(n).expect("n! overflows a usize");
+        return Coverage { n, counts: vec![0; total_perms], missing: total_perms };
+    }
+
+    /// Creates a coverage counting every window of the sequence
+    pub fn from_sequence(sequence: &[usize], n: usize) -> Coverage {
+        let mut coverage = Coverage::new(n);
+        if n > 0 {
+            for window in sequence.windows(n) {
+                coverage.add(window);
+            }
+        }
+        return coverage;
+    }
+
+    fn rank(&self, window: &[usize]) -> Option<usize> {
+        if window.len() != self.n || !is_permutation(window) {
+            return None;
+        }
+        return lex_rank(window);
+    }
+
+    /// Amount of unique tokens
+    pub fn n(&self) -> usize {
+        return self.n;
+    }
+
+    /// Counts a window. Windows that aren't permutations are ignored.
+    pub fn add(&mut self, window: &[usize]) {
+        if let Some(rank) = self.rank(window) {
+            if self.counts[rank] == 0 {
+                self.missing -= 1;
+            }
+            self.counts[rank] += 1;
+        }
+    }
+
+    /// Uncounts a window previously counted with add
+    pub fn remove(&mut self, window: &[usize]) {
+        if let Some(rank) = self.rank(window) {
+            self.counts[rank] -= 1;
+            if self.counts[rank] == 0 {
+                self.missing += 1;
+            }
+        }
+    }
+
+    /// Amount of windows equal to the permutation
+    pub fn count(&self, perm: &[usize]) -> usize {
+        return self.rank(perm).map_or(0, |rank| self.counts[rank]);
+    }
+
+    /// Amount of distinct permutations covered
+    pub fn covered(&self) -> usize {
+        return self.counts.len() - self.missing;
+    }
+
+    /// Amount of permutations not covered
+    pub fn missing(&self) -> usize {
+        return self.missing;
+    }
+
+    /// Whether every permutation is covered
+    pub fn is_complete(&self) -> bool {
+        return self.missing == 0;
+    }
+}
+
+/// Amount of missing permutations given as examples by explain
+const EXPLAIN_EXAMPLES: usize = 5;
+
+/// A permutation missing from a sequence
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MissingPerm {
+    pub perm: Vec<usize>,
+    /// Kendall tau distance to the nearest covered permutation, i.e the
+    /// fewest swaps of neighbouring tokens turning a covered permutation into
+    /// it. None if nothing is covered
+    pub distance: Option<usize>,
+}
+
+/// Diagnostics for why a sequence isn't (or is) a superpermutation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Explanation {
+    pub n: usize,
+    /// Amount of distinct permutations covered
+    pub covered: usize,
+    /// Amount of permutations of n tokens
+    pub total: usize,
+    /// Length of the longest prefix that might still be extended into a
+    /// superpermutation no longer than the budget. Each token appended
+    /// covers at most one new permutation, so any longer prefix certainly
+    /// can't be. Equal to the sequence length if the whole sequence might be
+    pub extendable_prefix: usize,
+    /// A few of the missing permutations, those nearest to covered ones first
+    pub nearest_missing: Vec<MissingPerm>,
+}
+
+impl fmt::Display for Explanation {
+    /// The coverage, extendable prefix and nearest missing permutations, one
+    /// per line. E.g "missing:          3 2 1 (distance 1)"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "covered:          {}/{}", self.covered, self.total)?;
+        write!(f, "extendable:       first {} tokens", self.extendable_prefix)?;
+        for missing in &self.nearest_missing {
+            let perm: Vec<String> = missing.perm.iter().map(|token| token.to_string()).collect();
+            match missing.distance {
+                Some(distance) => write!(f, "\nmissing:          {} (distance {})", perm.join(" "), distance)?,
+                None => write!(f, "\nmissing:          {}", perm.join(" "))?,
+            }
+        }
+        return Ok(());
+    }
+}
+
+/// Explains what a sequence is missing to be a superpermutation of
+/// (1,2,3, ...,n), to help track down where a heuristic went wrong. budget is
+/// the length a superpermutation is wanted within, e.g the best known length.
+///
+/// E.g 12312132 for n=3 covers 5 of 6 permutations, missing 321 which is
+/// one swap away from 231 and 312. With a budget of 9 the whole sequence
+/// might still be extended, by appending a 1.
+/// Panics if n! overflows.
+pub fn explain(sequence: &[usize], n: usize, budget: usize) -> Explanation {
+    let total = checked_factorial(n).expect("n! overflows a usize");
+    // The empty permutation of n = 0 is in every sequence
+    let mut covered = vec![n == 0; total];
+    let mut covered_count = if n == 0 { 1 } else { 0 };
+    // Longest prefix whose length plus the least amount of tokens left to
+    // append fits in the budget. Until the first window each token only
+    // brings it closer, after that each covers at most one permutation
+    let mut extendable_prefix = 0;
+    for prefix in 1..sequence.len() + 1 {
+        if n > 0 && prefix >= n {
+            let window = &sequence[prefix - n..prefix];
+            if is_permutation(window) {
+                let rank = lex_rank(window).unwrap();
+                if covered[rank] == false {
+                    covered[rank] = true;
+                    covered_count += 1;
+                }
+            }
+        }
+        let least_remaining = (total - covered_count) + n.saturating_sub(1).saturating_sub(prefix);
+        if prefix + least_remaining <= budget {
+            extendable_prefix = prefix;
+        }
+    }
+
+    // Breadth first search out from every covered permutation, one swap of
+    // neighbouring tokens at a time, finds each one's distance to the nearest
+    let tokens: Vec<usize> = (1..n + 1).collect();
+    let mut distances: Vec<Option<usize>> = covered.iter().map(|c| if *c { Some(0) } else { None }).collect();
+    let mut queue: VecDeque<usize> = (0..total).filter(|rank| covered[*rank]).collect();
+    while let Some(rank) = queue.pop_front() {
+        let perm = lex_unrank(rank, &tokens).unwrap();
+        for i in 1..n {
+            let mut neighbour = perm.clone();
+            neighbour.swap(i - 1, i);
+            let neighbour_rank = lex_rank(&neighbour).unwrap();
+            if distances[neighbour_rank].is_none() {
+                distances[neighbour_rank] = distances[rank].map(|d| d + 1);
+                queue.push_back(neighbour_rank);
+            }
+        }
+    }
+    let mut missing: Vec<usize> = (0..total).filter(|rank| covered[*rank] == false).collect();
+    // Stable sort keeps lexicographic order amongst equal distances
+    missing.sort_by_key(|rank| distances[*rank]);
+    let nearest_missing = missing.iter().take(EXPLAIN_EXAMPLES)
+        .map(|rank| MissingPerm { perm: lex_unrank(*rank, &tokens).unwrap(), distance: distances[*rank] })
+        .collect();
+
+    return Explanation { n, covered: covered_count, total, extendable_prefix, nearest_missing };
+}
+
+/// Segments of a sequence that could each be deleted with the sequence still
+/// being a valid superpermutation afterwards
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redundancy {
+    /// Start (inclusive) and end (exclusive) positions of each segment. No
+    /// segment lies within another
+    pub segments: Vec<(usize, usize)>,
+    /// Amount of positions lying in at least one segment. Segments can't
+    /// necessarily all be deleted together so this is an upper bound on the
+    /// length that can be saved
+    pub total_removable: usize,
+}
+
+/// Finds the maximal segments of a sequence whose deletion leaves a valid
+/// superpermutation of (1,2,3, ...,n).
+///
+/// E.g 11231213213 for n=3 has the segments (0, 1), (1, 2) and (10, 11) as
+/// either of the leading 1s and the trailing 3 can be deleted.
+pub fn removable_segments(sequence: &[usize], n: usize) -> Redundancy {
+    let mut candidates: Vec<(usize, usize)> = Vec::new();
+    if n > 0 {
+        let full = Coverage::from_sequence(sequence, n);
+        for start in 0..sequence.len() {
+            let mut coverage = full.clone();
+            // Deleting start..end removes the windows starting from first to end-1
+            // and adds the windows across the join
+            let first = start.saturating_sub(n - 1);
+            let mut next_removal = first;
+            let mut longest = None;
+            for end in start + 1..sequence.len() + 1 {
+                while next_removal < end && next_removal + n <= sequence.len() {
+                    coverage.remove(&sequence[next_removal..next_removal + n]);
+                    next_removal += 1;
+                }
+                // The join has at most n-1 windows, so can't make up for more
+                // missing permutations. Deleting more only removes more windows
+                if coverage.missing() > n - 1 {
+                    break;
+                }
+                let join: Vec<usize> = sequence[first..start]
+                    .iter()
+                    .chain(&sequence[end..(end + n - 1).min(sequence.len())])
+                    .copied()
+                    .collect();
+                for window in join.windows(n) {
+                    coverage.add(window);
+                }
+                if coverage.is_complete() {
+                    longest = Some(end);
+                }
+                for window in join.windows(n) {
+                    coverage.remove(window);
+                }
+            }
+            if let Some(end) = longest {
+                candidates.push((start, end));
+            }
+        }
+    }
+
+    // Candidates are ordered by start, so one lies within an earlier one
+    // exactly when it doesn't reach past the furthest end so far
+    let mut segments: Vec<(usize, usize)> = Vec::new();
+    let mut total_removable = 0;
+    let mut furthest = 0;
+    for (start, end) in candidates {
+        if end > furthest {
+            total_removable += end - start.max(furthest);
+            furthest = end;
+            segments.push((start, end));
+        }
+    }
+    return Redundancy { segments, total_removable };
+}
+
+/// Finds the shortest contiguous part of a sequence that is itself a valid
+/// superpermutation of (1,2,3, ...,n), as start (inclusive) and end
+/// (exclusive) positions. The earliest is given if there are several. None if
+/// the whole sequence isn't valid.
+///
+/// E.g 31231213213 for n=3 gives (1, 10), trimming the junk at either end
+pub fn minimal_covering_window(sequence: &[usize], n: usize) -> Option<(usize, usize)> {
+    if n == 0 {
+        return Some((0, 0));
+    }
+    let mut coverage = Coverage::new(n);
+    let mut best: Option<(usize, usize)> = None;
+    let mut start = 0;
+    for end in n..sequence.len() + 1 {
+        coverage.add(&sequence[end - n..end]);
+        // Shrink from the front for as long as everything stays covered
+        while coverage.is_complete() {
+            if best.is_none_or(|(s, e)| end - start < e - s) {
+                best = Some((start, end));
+            }
+            coverage.remove(&sequence[start..start + n]);
+            start += 1;
+        }
+    }
+    return best;
+}
+
+/// Renders a strip chart of which regions of a sequence contribute new
+/// permutations, squeezed into at most width columns. Each column stands for a
+/// run of positions and shows the fraction of them completing a window that
+/// is the first visit of a permutation:
+/// `#` at least 3/4, `+` at least 1/2, `-` some, `.` none.
+///
+/// A second line marks the positions at either end. E.g 123121321 for n=3 at
+/// width 9 gives
+/// ```text
+/// |..###.###|
+///  0        9
+/// ```
+pub fn render_coverage_ascii(sequence: &[usize], n: usize, width: usize) -> String {
+    let mut new_at = vec![false; sequence.len()];
+    for start in first_visits(sequence, n) {
+        new_at[start + n - 1] = true;
+    }
+    let columns = width.min(sequence.len()).max(1);
+    let mut strip = String::with_capacity(columns + 2);
+    strip.push('|');
+    for column in 0..columns {
+        let bucket = &new_at[column * sequence.len() / columns..(column + 1) * sequence.len() / columns];
+        let new = bucket.iter().filter(|b| **b).count();
+        strip.push(match new {
+            _ if bucket.is_empty() || new == 0 => '.',
+            _ if new * 4 >= bucket.len() * 3 => '#',
+            _ if new * 2 >= bucket.len() => '+',
+            _ => '-',
+        });
+    }
+    strip.push('|');
+    let end = sequence.len().to_string();
+    // Line the end position up with the closing bar
+    let gap = columns.saturating_sub(end.len()).max(1);
+    return format!("{}\n 0{}{}", strip, " ".repeat(gap), end);
+}
+
+/// Where a length falls relative to the known bounds for its n
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LengthClass {
+    /// Shorter than the lower bound, so can't be a superpermutation
+    BelowLowerBound,
+    /// Shorter than the best known superpermutation, which would be a new record
+    BeatsBestKnown,
+    MatchesBestKnown,
+    /// Longer than the best known but shorter than the recursive construction
+    BetweenBestAndRecursive,
+    MatchesRecursive,
+    /// Longer than the recursive construction
+    AboveRecursive,
+}
+
+/// Comparison of a length against the known bounds for superpermutations of n
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoundsReport {
+    pub n: usize,
+    pub length: usize,
+    /// No superpermutation can be shorter than this
+    pub lower_bound: usize,
+    /// Length of the shortest superpermutation found so far
+    pub best_known: usize,
+    /// Length of the superpermutation built by the standard recursive
+    /// construction, 1! + 2! + ... + n!
+    pub recursive_length: usize,
+    pub class: LengthClass,
+}
+
+/// Compares a length against the known bounds for superpermutations of n,
+/// taken from the records module. None if the bounds are too large for a usize.
+pub fn bounds_report(n: usize, length: usize) -> Option<BoundsReport> {
+    let lower_bound = records::lower_bound(n)?;
+    let best_known = records::best_known_length(n)?;
+    let recursive_length = records::recursive_length(n)?;
+    let class = if length < lower_bound {
+        LengthClass::BelowLowerBound
+    } else if length < best_known {
+        LengthClass::BeatsBestKnown
+    } else if length == best_known {
+        LengthClass::MatchesBestKnown
+    } else if length < recursive_length {
+        LengthClass::BetweenBestAndRecursive
+    } else if length == recursive_length {
+        LengthClass::MatchesRecursive
+    } else {
+        LengthClass::AboveRecursive
+    };
+    return Some(BoundsReport { n, length, lower_bound, best_known, recursive_length, class });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_of_known_sequences() {
+        let stats = SuperPermStats::compute(&[1,2,3,1,2,1,3,2,1], 3);
+        assert_eq!(stats, SuperPermStats {
+            length: 9,
+            n: 3,
+            distinct_perms: 6,
+            duplicate_visits: 0,
+            waste: 1,
+            completion_index: Some(8),
+        });
+        let stats = SuperPermStats::compute(&[1,2,1,2,1,3], 2);
+        assert_eq!(stats.distinct_perms, 2);
+        assert_eq!(stats.duplicate_visits, 2);
+        assert_eq!(stats.completion_index, Some(2));
+        let stats = SuperPermStats::compute(&[1,2], 3);
+        assert_eq!((stats.distinct_perms, stats.waste, stats.is_complete()), (0, 2, false));
+        assert!(stats.to_string().ends_with("waste:            2\ncompletion index: incomplete"));
+    }
+
+    #[test]
+    fn visit_order() {
+        let visits: Vec<(usize, &[usize])> = covered_in_order(&[1,2,1,3,1,2,1], 2).collect();
+        assert_eq!(visits, vec![(0, &[1,2][..]), (1, &[2,1][..])]);
+        let starts: Vec<usize> = covered_in_order(&[1,2,3,1,2,1,3,2,1,2,3], 3).map(|(start, _)| start).collect();
+        assert_eq!(starts, vec![0, 1, 2, 4, 5, 6]);
+        assert_eq!(covered_in_order(&[1,2], 0).count(), 0);
+    }
+
+    #[test]
+    fn explanations() {
+        let explanation = explain(&[1,2,3,1,2,1,3,2], 3, 9);
+        assert_eq!((explanation.covered, explanation.total, explanation.extendable_prefix), (5, 6, 8));
+        assert_eq!(explanation.nearest_missing, vec![MissingPerm { perm: vec![3,2,1], distance: Some(1) }]);
+        assert_eq!(
+            explanation.to_string(),
+            "covered:          5/6\nextendable:       first 8 tokens\nmissing:          3 2 1 (distance 1)"
+        );
+        // 121 isn't a permutation, so after it the 6 permutations can no
+        // longer fit within 8 tokens
+        assert_eq!(explain(&[1,2,1,3,1,2,3], 3, 8).extendable_prefix, 2);
+        let explanation = explain(&[], 3, 9);
+        assert_eq!(explanation.nearest_missing.len(), 5);
+        assert_eq!(explanation.nearest_missing[0], MissingPerm { perm: vec![1,2,3], distance: None });
+        assert_eq!(explain(&[4], 0, 0).covered, 1);
+    }
+
+    #[test]
+    fn timeline() {
+        assert_eq!(coverage_timeline(&[1,2,3,1], 3), vec![0,0,1,2]);
+        let sequence = [1,2,3,1,2,1,3,2,1,2,3];
+        let timeline = coverage_timeline(&sequence, 3);
+        assert_eq!(timeline.len(), sequence.len());
+        assert_eq!(timeline[8], 6);
+        assert_eq!(timeline[10], 6);
+    }
+
+    #[test]
+    fn redundant_segments() {
+        let sequence = [1,1,2,3,1,2,1,3,2,1,3];
+        let redundancy = removable_segments(&sequence, 3);
+        assert_eq!(redundancy.segments, vec![(0,1), (1,2), (10,11)]);
+        assert_eq!(redundancy.total_removable, 3);
+        assert!(removable_segments(&[1,2,3,1,2,1,3,2,1], 3).segments.is_empty());
+
+        // Every segment found really can be deleted
+        let sequence = [1,2,3,4,1,2,3,1,4,2,3,1,2,4,3,1,2,1,3,4,2,1,3,2,4,1,3,2,1,4,3,2,1,2,3,4,1];
+        let redundancy = removable_segments(&sequence, 4);
+        assert!(!redundancy.segments.is_empty());
+        for (start, end) in redundancy.segments {
+            let deleted = [&sequence[..start], &sequence[end..]].concat();
+            assert!(Coverage::from_sequence(&deleted, 4).is_complete());
+        }
+    }
+
+    #[test]
+    fn minimal_window() {
+        assert_eq!(minimal_covering_window(&[3,1,2,3,1,2,1,3,2,1,3], 3), Some((1, 10)));
+        assert_eq!(minimal_covering_window(&[1,2,3,1,2,1,3,2,1], 3), Some((0, 9)));
+        assert_eq!(minimal_covering_window(&[1,2,3,1,2], 3), None);
+        // Of two complete copies the first is given
+        assert_eq!(minimal_covering_window(&[1,2,1,1,2,1], 2), Some((0, 3)));
+    }
+
+    #[test]
+    fn bounds() {
+        let report = bounds_report(5, 153).unwrap();
+        assert_eq!((report.lower_bound, report.best_known, report.recursive_length), (152, 153, 153));
+        assert_eq!(report.class, LengthClass::MatchesBestKnown);
+        let report = bounds_report(6, 873).unwrap();
+        assert_eq!((report.lower_bound, report.best_known), (867, 872));
+        assert_eq!(report.class, LengthClass::MatchesRecursive);
+        assert_eq!(bounds_report(8, 46205).unwrap().class, LengthClass::MatchesBestKnown);
+        assert_eq!(bounds_report(3, 8).unwrap().class, LengthClass::BelowLowerBound);
+        assert_eq!(bounds_report(3, 10).unwrap().class, LengthClass::AboveRecursive);
+        assert_eq!(bounds_report(7, 5907).unwrap().class, LengthClass::BetweenBestAndRecursive);
+        assert_eq!(bounds_report(30, 0), None);
+    }
+
+    #[test]
+    fn ascii_coverage() {
+        assert_eq!(render_coverage_ascii(&[1,2,3,1,2,1,3,2,1], 3, 9), "|..###.###|\n 0        9");
+        let strip = render_coverage_ascii(&[1,2,3,1,2,1,3,2,1], 3, 3);
+        assert_eq!(strip.lines().next(), Some("|-+#|"));
+        assert_eq!(render_coverage_ascii(&[], 3, 10), "|.|\n 0 0");
+    }
+
+    #[test]
+    fn k_cycles() {
+        assert_eq!(k_cycle_counts(&[1,2,3,1,2,1,3,2,1], 3), vec![4,1,0]);
+        // The recursive construction for n=4 has 18 weight 1, 4 weight 2 and
+        // 1 weight 3 transitions
+        let sequence = [1,2,3,4,1,2,3,1,4,2,3,1,2,4,3,1,2,1,3,4,2,1,3,2,4,1,3,2,1,4,3,2,1];
+        assert_eq!(k_cycle_counts(&sequence, 4), vec![18,4,1,0]);
+    }
+
+    #[test]
+    fn sequence_symmetries() {
+        assert_eq!(symmetries(&[1,2,3,1,2,1,3,2,1], 3), Symmetries {
+            reversal: true,
+            complement: false,
+            reverse_complement: false,
+            covered_reversal: true,
+            covered_complement: true,
+        });
+        let partial = symmetries(&[1,2,3,2,1], 3);
+        assert!(partial.reversal && partial.covered_reversal && partial.covered_complement);
+        let partial = symmetries(&[1,2,3,1], 3);
+        assert!(!partial.reversal && !partial.covered_reversal && !partial.covered_complement);
+        assert!(symmetries(&[1,2], 2).reverse_complement);
+    }
+
+    #[test]
+    fn visit_diff() {
+        let a = [1,2,3,1,2,1,3,2,1];
+        assert_eq!(diff(&a, &a, 3), VisitDiff { common_prefix: 6, segments: vec![], length_difference: 0 });
+        // Same visit order but wasted characters before the last visit
+        let b = [1,2,3,1,2,1,3,2,3,2,1];
+        let result = diff(&a, &b, 3);
+        assert_eq!(result.common_prefix, 6);
+        assert_eq!(result.length_difference, 2);
+        assert_eq!(result.segments, vec![DivergentSegment {
+            a_visits: (5, 5),
+            b_visits: (5, 5),
+            a_length: 1,
+            b_length: 3,
+        }]);
+        // Different routes through the permutations
+        let b = [1,2,3,2,1,3,1,2,1,3,2,1];
+        let result = diff(&a, &b, 3);
+        assert_eq!(result.common_prefix, 1);
+        let attributed: isize = result.segments.iter().map(|s| s.b_length as isize - s.a_length as isize).sum();
+        assert_eq!(attributed, result.length_difference);
+        assert_eq!(result.length_difference, 3);
+        assert_eq!(common_visits(&[5, 1, 2, 3], &[1, 2, 5, 3]), vec![(1, 0), (2, 1), (3, 3)]);
+    }
+
+    #[test]
+    fn token_frequencies() {
+        let stats = token_stats(&[1,2,3,1,2,1,3,2,1], 3);
+        assert_eq!(stats.frequencies, vec![4,3,2]);
+        assert_eq!(stats.expected_frequency, 3.0);
+        assert_eq!(stats.wasted_positions, vec![5]);
+        let sequence = [2,2,1,2,3,2,3,2,1,3];
+        let stats = token_stats(&sequence, 3);
+        assert_eq!(stats.wasted_positions, vec![0,1,5,6,7]);
+        assert_eq!(stats.wasted_positions.len(), SuperPermStats::compute(&sequence, 3).waste);
+    }
+
+    #[test]
+    fn overlaps() {
+        assert_eq!(overlap_histogram(&[1,2,3,1,2,1,3,2,1], 3), vec![0,1,4]);
+        assert_eq!(overlap_histogram(&[1,2,2,1], 2), vec![1,0]);
+        // Revisiting 12 in between doesn't shrink the overlap of 12 to 21
+        assert_eq!(overlap_histogram(&[1,2,1,2,1], 2), vec![0,1]);
+        assert_eq!(overlap_histogram(&[1,2,3,3,2,1], 3), vec![1,0,0]);
+        assert_eq!(overlap_histogram(&[], 3), vec![0,0,0]);
+    }
+}
@@ -0,0 +1,196 @@
+// Futures for running the blocking create/check work of a SuperPermHandling on
+// a spawned thread, so the crate can be awaited from async runtimes such as
+// tokio without spawn_blocking boilerplate. No runtime is depended upon: the
+// thread wakes the future itself once the work is done.
+use std::fmt;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+use crate::base::{check_n_supported, NTooLarge, SuperPermHandling};
+
+/// Errors a Task can resolve to instead of the result of its work
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskError {
+    /// The task was cancelled before the work finished
+    Cancelled,
+    /// The work panicked, so there's no result
+    Panicked,
+    /// The work was never started as n is too large
+    NTooLarge(NTooLarge),
+}
+
+impl fmt::Display for TaskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskError::Cancelled => write!(f, "task was cancelled"),
+            TaskError::Panicked => write!(f, "task panicked"),
+            TaskError::NTooLarge(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for TaskError {}
+
+/// State shared between a Task, its CancelHandle and the thread doing the work
+struct Shared<T> {
+    result: Option<Result<T, TaskError>>,
+    cancelled: bool,
+    waker: Option<Waker>,
+}
+
+/// Future resolving to the result of work running on another thread
+pub struct Task<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+}
+
+/// Cancels the Task it was created alongside. Can be cloned and sent to other threads.
+#[derive(Clone)]
+pub struct CancelHandle {
+    cancel: Arc<dyn Fn() + Send + Sync>,
+}
+
+impl CancelHandle {
+    /// Makes the task resolve to Err(TaskError::Cancelled) if it hasn't finished yet. The
+    /// algorithms can't be interrupted part way, so the thread runs on to the
+    /// end in the background and its result is dropped.
+    pub fn cancel(&self) {
+        (self.cancel)();
+    }
+}
+
+impl<T> Future for Task<T> {
+    type Output = Result<T, TaskError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut shared = self.shared.lock().unwrap();
+        if shared.cancelled {
+            return Poll::Ready(Err(TaskError::Cancelled));
+        }
+        if let Some(result) = shared.result.take() {
+            return Poll::Ready(result);
+        }
+        shared.waker = Some(cx.waker().clone());
+        return Poll::Pending;
+    }
+}
+
+/// Runs the work on a new thread, giving a future of its result and a handle
+/// to cancel it. The future resolves to Err(TaskError::Panicked) if the work
+/// panics.
+pub fn spawn<T, F>(work: F) -> (Task<T>, CancelHandle)
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let shared = Arc::new(Mutex::new(Shared { result: None, cancelled: false, waker: None }));
+    let worker_shared = Arc::clone(&shared);
+    thread::spawn(move || {
+        // Without catching, a panic would leave the task pending forever
+        let result = panic::catch_unwind(AssertUnwindSafe(work)).map_err(|_| TaskError::Panicked);
+        let mut shared = worker_shared.lock().unwrap();
+        if shared.cancelled == false {
+            shared.result = Some(result);
+        }
+        if let Some(waker) = shared.waker.take() {
+            waker.wake();
+        }
+    });
+
+    return with_cancel(shared);
+}
+
+/// A task that has already resolved to the error given
+fn failed<T: Send + 'static>(error: TaskError) -> (Task<T>, CancelHandle) {
+    return with_cancel(Arc::new(Mutex::new(Shared { result: Some(Err(error)), cancelled: false, waker: None })));
+}
+
+fn with_cancel<T: Send + 'static>(shared: Arc<Mutex<Shared<T>>>) -> (Task<T>, CancelHandle) {
+    let cancel_shared = Arc::clone(&shared);
+    let cancel = move || {
+        let mut shared = cancel_shared.lock().unwrap();
+        shared.cancelled = true;
+        if let Some(waker) = shared.waker.take() {
+            waker.wake();
+        }
+    };
+    return (Task { shared }, CancelHandle { cancel: Arc::new(cancel) });
+}
+
+/// Creates a superpermutation of the tokens (1,2,3, ...,n_tokens) with the
+/// handle given on a new thread. See SuperPermHandling::create_superperm.
+/// Resolves to Err(TaskError::NTooLarge) without spawning if n_tokens is
+/// above MAX_SUPPORTED_N.
+pub fn create_superperm_async<H>(handle: H, n_tokens: usize) -> (Task<Vec<usize>>, CancelHandle)
+where
+    H: SuperPermHandling + Send + 'static,
+{
+    if let Err(error) = check_n_supported(n_tokens) {
+        return failed(TaskError::NTooLarge(error));
+    }
+    return spawn(move || handle.create_superperm(n_tokens));
+}
+
+/// Checks the sequence with the handle given on a new thread. See
+/// SuperPermHandling::check_superperm. Resolves to Err(TaskError::NTooLarge)
+/// without spawning if n_tokens is above MAX_SUPPORTED_N.
+pub fn check_superperm_async<H>(handle: H, sequence: Vec<usize>, n_tokens: usize) -> (Task<bool>, CancelHandle)
+where
+    H: SuperPermHandling + Send + 'static,
+{
+    if let Err(error) = check_n_supported(n_tokens) {
+        return failed(TaskError::NTooLarge(error));
+    }
+    return spawn(move || handle.check_superperm(&sequence, n_tokens));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bruteforce_optimise;
+    use std::sync::mpsc;
+    use std::task::Wake;
+
+    /// Polls a future on the current thread until it's ready
+    fn block_on<F: Future>(future: F) -> F::Output {
+        struct ThreadWaker(thread::Thread);
+        impl Wake for ThreadWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut context = Context::from_waker(&waker);
+        let mut future = Box::pin(future);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+            thread::park();
+        }
+    }
+
+    #[test]
+    fn async_tasks() {
+        let (task, _) = create_superperm_async(bruteforce_optimise::Handle, 4);
+        let sequence = block_on(task).unwrap();
+        assert_eq!(sequence.len(), 33);
+        let (task, _) = check_superperm_async(bruteforce_optimise::Handle, sequence, 4);
+        assert_eq!(block_on(task), Ok(true));
+
+        // Cancelling resolves the task straight away even though the work is blocked
+        let (sender, receiver) = mpsc::channel::<()>();
+        let (task, cancel) = spawn(move || receiver.recv().is_ok());
+        cancel.cancel();
+        assert_eq!(block_on(task), Err(TaskError::Cancelled));
+        drop(sender);
+
+        let (task, _) = spawn(|| -> bool { panic!("work failed") });
+        assert_eq!(block_on(task), Err(TaskError::Panicked));
+        let (task, _) = create_superperm_async(bruteforce_optimise::Handle, 25);
+        assert!(matches!(block_on(task), Err(TaskError::NTooLarge(NTooLarge { n: 25, .. }))));
+    }
+}
@@ -1,13 +1,274 @@
+use crate::permutation::{checked_factorial, is_permutation};
+
 /// Declare the common functionality for each implementation
 pub trait SuperPermHandling {
     /// Creates a superpmutation. 
     /// 
-    /// Tokens used are the numbers (1,2,3,4, ...,n_tokens).
+    /// Tokens used are the numbers (1,2,3,4, ...,n_tokens). For n_tokens = 0
+    /// this is the empty sequence and for n_tokens = 1 it's [1].
     fn create_superperm(&self, n_tokens: usize) -> Vec<usize>;
 
     /// Checks if sequence passed in is a valid superpermutation.
     /// 
     /// n_tokens specifies the amount of unique tokens within the sequence. See
     /// documentation for create_superperm for what the tokens should be.
+    ///
+    /// Sequences shorter than n_tokens can't contain a permutation, so give
+    /// false rather than panicking. The empty permutation of n_tokens = 0 is
+    /// in every sequence, so any sequence is valid for n_tokens = 0.
     fn check_superperm(&self, sequence: &Vec<usize>, n_tokens: usize) -> bool;
+
+    /// Largest n_tokens try_create_superperm creates for. MAX_CREATE_N unless
+    /// the implementation needs more memory than the sequence it creates.
+    fn max_create_n(&self) -> usize {
+        return MAX_CREATE_N;
+    }
+
+    /// Same as create_superperm, giving an error instead of running out of
+    /// memory, panicking or producing garbage when n_tokens is above
+    /// max_create_n.
+    fn try_create_superperm(&self, n_tokens: usize) -> Result<Vec<usize>, NTooLarge> {
+        check_create_n(self, n_tokens)?;
+        return Ok(self.create_superperm(n_tokens));
+    }
+
+    /// Same as check_superperm, giving an error instead of panicking or
+    /// producing garbage when n_tokens! doesn't fit in a usize. Sequences too
+    /// short to be a superpermutation are false without being checked, so
+    /// nothing larger than the sequence itself is allocated.
+    fn try_check_superperm(&self, sequence: &Vec<usize>, n_tokens: usize) -> Result<bool, NTooLarge> {
+        check_n_supported(n_tokens)?;
+        if long_enough(sequence.len(), n_tokens) == false {
+            return Ok(false);
+        }
+        return Ok(self.check_superperm(sequence, n_tokens));
+    }
+
+    /// Same as check_superperm, with the mode deciding whether tokens outside
+    /// of (1,2,3, ...,n_tokens) and padding around the permutations are
+    /// errors rather than tolerated.
+    fn check_superperm_with(&self, sequence: &Vec<usize>, n_tokens: usize, mode: CheckMode) -> Result<bool, CheckError> {
+        if mode != CheckMode::Lenient {
+            if let Some(index) = sequence.iter().position(|token| *token < 1 || *token > n_tokens) {
+                return Err(CheckError::ForeignToken { index, token: sequence[index] });
+            }
+        }
+        if mode == CheckMode::Strict && n_tokens > 0 {
+            let windows = || sequence.windows(n_tokens);
+            if let Some(first) = windows().position(is_permutation) {
+                let last = windows().rposition(is_permutation).unwrap();
+                let trailing = sequence.len() - n_tokens - last;
+                if first > 0 || trailing > 0 {
+                    return Err(CheckError::Padding { leading: first, trailing });
+                }
+            }
+        }
+        if long_enough(sequence.len(), n_tokens) == false {
+            return Ok(false);
+        }
+        return Ok(self.check_superperm(sequence, n_tokens));
+    }
+}
+
+/// Largest n whose permutations can be counted in a usize, 20 on 64 bit
+/// targets. Beyond this the n! sized checklists can't be indexed.
+pub const MAX_SUPPORTED_N: usize = {
+    let mut n = 1;
+    let mut factorial: usize = 1;
+    while let Some(next) = factorial.checked_mul(n + 1) {
+        factorial = next;
+        n += 1;
+    }
+    n
+};
+
+/// Largest n the try_create_superperm of the greedy creators creates for by
+/// default. Their sequences are over n! tokens long, about 4GB at n=12 and
+/// 50GB at n=13.
+pub const MAX_CREATE_N: usize = 12;
+
+/// Error for an n larger than MAX_SUPPORTED_N, or than an implementation
+/// supports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NTooLarge {
+    pub n: usize,
+    pub max_supported: usize,
+}
+
+impl std::fmt::Display for NTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "n={} is too large, at most n={} is supported", self.n, self.max_supported)
+    }
+}
+
+impl std::error::Error for NTooLarge {}
+
+/// Ok if n is at most MAX_SUPPORTED_N
+pub fn check_n_supported(n: usize) -> Result<(), NTooLarge> {
+    if n > MAX_SUPPORTED_N {
+        return Err(NTooLarge { n, max_supported: MAX_SUPPORTED_N });
+    }
+    return Ok(());
+}
+
+/// Ok if the handle's try_create_superperm would create for n_tokens, i.e
+/// it's at most both MAX_SUPPORTED_N and the handle's max_create_n
+pub fn check_create_n<H: SuperPermHandling + ?Sized>(handle: &H, n_tokens: usize) -> Result<(), NTooLarge> {
+    check_n_supported(n_tokens)?;
+    let max_supported = handle.max_create_n();
+    if n_tokens > max_supported {
+        return Err(NTooLarge { n: n_tokens, max_supported });
+    }
+    return Ok(());
+}
+
+/// Whether a sequence of the length given could be a superpermutation of n
+/// tokens. Each of the n! permutations needs a window of its own, so it takes
+/// at least n! + n - 1 tokens. Checkers return false early otherwise rather
+/// than allocating n! sized checklists for sequences that can't pass.
+///
+/// E.g 7 tokens are too few for n=3, which needs 6 + 2.
+pub fn long_enough(len: usize, n_tokens: usize) -> bool {
+    if n_tokens == 0 {
+        return true;
+    }
+    return match checked_factorial(n_tokens).and_then(|perms| perms.checked_add(n_tokens - 1)) {
+        Some(needed) => len >= needed,
+        None => false,
+    };
+}
+
+/// What check_superperm_with tolerates besides the permutations themselves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CheckMode {
+    /// Tokens outside of (1,2,3, ...,n_tokens) only make the windows
+    /// containing them not permutations, and any tokens may come before the
+    /// first permutation or after the last, as with check_superperm
+    #[default]
+    Lenient,
+    /// Tokens outside of (1,2,3, ...,n_tokens) are an error
+    RejectForeignTokens,
+    /// Both foreign tokens and tokens before the first permutation or after
+    /// the last are errors, so the sequence starts and ends on a permutation
+    Strict,
+}
+
+/// Why check_superperm_with rejected a sequence under its CheckMode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckError {
+    /// A token outside of (1,2,3, ...,n_tokens). index counts from 0
+    ForeignToken { index: usize, token: usize },
+    /// Amounts of tokens before the first permutation and after the last
+    Padding { leading: usize, trailing: usize },
+}
+
+impl std::fmt::Display for CheckError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CheckError::ForeignToken { index, token } => {
+                write!(f, "token {} at index {} is outside of the tokens 1..=n", token, index)
+            }
+            CheckError::Padding { leading, trailing } => {
+                write!(f, "{} tokens before the first permutation and {} after the last", leading, trailing)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CheckError {}
+
+/// The implementations of SuperPermHandling, for choosing one at runtime
+/// from CLI flags, config files and the like.
+///
+/// Solvers behind a cargo feature (e.g `heuristics`) have their variants
+/// behind the same feature, so ALL and parsing only know of the solvers
+/// compiled in. Configurable solvers are registered with their default
+/// settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Algorithm {
+    Bruteforce,
+    BruteforceOptimise,
+    #[cfg(feature = "heuristics")]
+    Bidirectional,
+    /// WeightedGreedy with the default weights. Only creates up to
+    /// n=weighted_greedy::MAX_N, see SuperPermHandling::max_create_n
+    #[cfg(feature = "heuristics")]
+    WeightedGreedy,
+}
+
+impl Algorithm {
+    /// Every algorithm, so tools such as benchmarks and cross checks pick up
+    /// new implementations without being changed
+    pub const ALL: &'static [Algorithm] = &[
+        Algorithm::Bruteforce,
+        Algorithm::BruteforceOptimise,
+        #[cfg(feature = "heuristics")]
+        Algorithm::Bidirectional,
+        #[cfg(feature = "heuristics")]
+        Algorithm::WeightedGreedy,
+    ];
+
+    /// Name the algorithm is parsed from and reported as. E.g "bruteforce_optimise"
+    pub fn name(&self) -> &'static str {
+        match self {
+            Algorithm::Bruteforce => "bruteforce",
+            Algorithm::BruteforceOptimise => "bruteforce_optimise",
+            #[cfg(feature = "heuristics")]
+            Algorithm::Bidirectional => "bidirectional",
+            #[cfg(feature = "heuristics")]
+            Algorithm::WeightedGreedy => "weighted_greedy",
+        }
+    }
+
+    /// The algorithm's implementation
+    pub fn handle(&self) -> Box<dyn SuperPermHandling + Send + Sync> {
+        match self {
+            Algorithm::Bruteforce => Box::new(crate::bruteforce::Handle),
+            Algorithm::BruteforceOptimise => Box::new(crate::bruteforce_optimise::Handle),
+            #[cfg(feature = "heuristics")]
+            Algorithm::Bidirectional => Box::new(crate::bidirectional::Handle),
+            #[cfg(feature = "heuristics")]
+            Algorithm::WeightedGreedy => Box::new(crate::weighted_greedy::WeightedGreedy::default()),
+        }
+    }
+}
+
+impl std::fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl std::str::FromStr for Algorithm {
+    type Err = String;
+    /// Parses an algorithm's name, with - and _ treated the same.
+    /// E.g "bruteforce-optimise" and "bruteforce_optimise"
+    fn from_str(name: &str) -> Result<Algorithm, String> {
+        let normalised = name.replace('-', "_");
+        match Algorithm::ALL.iter().find(|algorithm| algorithm.name() == normalised) {
+            Some(algorithm) => Ok(*algorithm),
+            None => {
+                let names: Vec<&str> = Algorithm::ALL.iter().map(|algorithm| algorithm.name()).collect();
+                Err(format!("unknown algorithm {:?}, expected one of: {}", name, names.join(", ")))
+            }
+        }
+    }
+}
+
+/// Every implementation along with its name. See Algorithm::ALL
+pub fn all_handles() -> Vec<(&'static str, Box<dyn SuperPermHandling + Send + Sync>)> {
+    return Algorithm::ALL.iter().map(|algorithm| (algorithm.name(), algorithm.handle())).collect();
+}
+
+/// Common functionality for implementations supporting super-k-permutations:
+/// sequences containing every arrangement of k distinct tokens out of n as a
+/// contiguous run. With k equal to n_tokens these are superpermutations.
+pub trait SuperKPermHandling {
+    /// Creates a super-k-permutation of the tokens (1,2,3,4, ...,n_tokens).
+    /// Panics if k is larger than n_tokens.
+    fn create_super_k_perm(&self, n_tokens: usize, k: usize) -> Vec<usize>;
+
+    /// Checks if the sequence passed in contains every arrangement of k
+    /// distinct tokens out of (1,2,3,4, ...,n_tokens).
+    fn check_super_k_perm(&self, sequence: &Vec<usize>, n_tokens: usize, k: usize) -> bool;
 }
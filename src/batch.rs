@@ -0,0 +1,297 @@
+// Checking many candidate sequences at once, read from CSV or JSONL files.
+// With the `parallel` feature the work is spread across rayon's threads.
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::base::{check_n_supported, SuperPermHandling};
+use crate::analysis::SuperPermStats;
+use crate::superperm::{FormatError, SuperPerm};
+
+/// A sequence to be checked along with an identifier to report it under
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub id: String,
+    pub superperm: SuperPerm,
+}
+
+/// The outcome of checking a single candidate
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Verdict {
+    pub id: String,
+    pub n: usize,
+    pub length: usize,
+    pub valid: bool,
+    pub distinct_perms: usize,
+    pub waste: usize,
+}
+
+/// File formats a batch of candidates or verdicts can be stored in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchFormat {
+    /// Comma separated values with a header row
+    Csv,
+    /// One JSON object per line
+    Jsonl,
+}
+
+impl FromStr for BatchFormat {
+    type Err = String;
+    fn from_str(name: &str) -> Result<BatchFormat, String> {
+        match name {
+            "csv" => Ok(BatchFormat::Csv),
+            "jsonl" => Ok(BatchFormat::Jsonl),
+            _ => Err(format!("unknown batch format {:?}, expected one of: csv, jsonl", name)),
+        }
+    }
+}
+
+/// Errors that can arise whilst reading a batch of candidates
+#[derive(Debug)]
+pub enum BatchError {
+    Io(io::Error),
+    /// A line of the input couldn't be understood. Lines are numbered from 1
+    Line { line: usize, reason: String },
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BatchError::Io(err) => write!(f, "{}", err),
+            BatchError::Line { line, reason } => write!(f, "line {}: {}", line, reason),
+        }
+    }
+}
+
+impl std::error::Error for BatchError {}
+
+impl From<io::Error> for BatchError {
+    fn from(err: io::Error) -> BatchError {
+        return BatchError::Io(err);
+    }
+}
+
+/// Parses a sequence field. Fields containing spaces are read as delimited
+/// tokens (for n > 9), otherwise as a digit string.
+fn parse_sequence(field: &str) -> Result<SuperPerm, FormatError> {
+    if field.trim().contains(char::is_whitespace) {
+        return SuperPerm::from_delimited_str(field);
+    }
+    return SuperPerm::from_digit_str(field);
+}
+
+/// Reads candidates from CSV. The header row must contain a `sequence` column
+/// and may contain `id` and `n` columns. E.g
+/// ```text
+/// id,n,sequence
+/// greedy-1,3,123121321
+/// big,10,1 2 3 4 5 6 7 8 9 10 1 ...
+/// ```
+/// Sequences are digit strings, or space separated tokens for n > 9. Fields may
+/// be wrapped in double quotes but quoted commas aren't supported. Without an
+/// `id` column candidates are named by their line number, and without an `n`
+/// column n is taken to be the largest token. An n above MAX_SUPPORTED_N is
+/// an error for its line.
+pub fn read_candidates_csv<R: BufRead>(r: R) -> Result<Vec<Candidate>, BatchError> {
+    let mut lines = r.lines().enumerate();
+    let Some((_, header)) = lines.next() else {
+        return Ok(Vec::new());
+    };
+    let header = header?;
+    let columns: Vec<&str> = header.split(',').map(|c| c.trim().trim_matches('"')).collect();
+    let column = |name: &str| columns.iter().position(|c| *c == name);
+    let Some(sequence_col) = column("sequence") else {
+        return Err(BatchError::Line { line: 1, reason: String::from("missing a sequence column") });
+    };
+    let (id_col, n_col) = (column("id"), column("n"));
+
+    let mut candidates = Vec::new();
+    for (i, line) in lines {
+        let line_no = i + 1;
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(|f| f.trim().trim_matches('"')).collect();
+        let error = |reason: String| BatchError::Line { line: line_no, reason };
+        let Some(sequence) = fields.get(sequence_col) else {
+            return Err(error(String::from("missing the sequence field")));
+        };
+        let mut superperm = parse_sequence(sequence).map_err(|err| error(err.to_string()))?;
+        if let Some(n_field) = n_col.and_then(|col| fields.get(col)) {
+            superperm.n_tokens = n_field.parse().map_err(|_| error(format!("invalid n {:?}", n_field)))?;
+        }
+        check_n_supported(superperm.n_tokens).map_err(|err| error(err.to_string()))?;
+        let id = match id_col.and_then(|col| fields.get(col)) {
+            Some(id) => id.to_string(),
+            None => line_no.to_string(),
+        };
+        candidates.push(Candidate { id, superperm });
+    }
+    return Ok(candidates);
+}
+
+/// A candidate as written in JSONL. The sequence may be an array of tokens or
+/// a digit string.
+#[derive(Deserialize)]
+struct JsonCandidate {
+    id: Option<String>,
+    n: Option<usize>,
+    sequence: JsonSequence,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonSequence {
+    Tokens(Vec<usize>),
+    Text(String),
+}
+
+/// Reads candidates from JSONL, one object per line with a `sequence` field and
+/// optional `id` and `n` fields. E.g
+/// ```text
+/// {"id": "greedy-1", "n": 3, "sequence": "123121321"}
+/// {"id": "big", "sequence": [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 1]}
+/// ```
+/// Missing fields are filled in as for read_candidates_csv.
+pub fn read_candidates_jsonl<R: BufRead>(r: R) -> Result<Vec<Candidate>, BatchError> {
+    let mut candidates = Vec::new();
+    for (i, line) in r.lines().enumerate() {
+        let line_no = i + 1;
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let error = |reason: String| BatchError::Line { line: line_no, reason };
+        let parsed: JsonCandidate = serde_json::from_str(&line).map_err(|err| error(err.to_string()))?;
+        let mut superperm = match parsed.sequence {
+            JsonSequence::Tokens(tokens) => {
+                let n_tokens = tokens.iter().copied().max().unwrap_or(0);
+                SuperPerm::new(tokens, n_tokens)
+            }
+            JsonSequence::Text(text) => parse_sequence(&text).map_err(|err| error(err.to_string()))?,
+        };
+        if let Some(n) = parsed.n {
+            superperm.n_tokens = n;
+        }
+        check_n_supported(superperm.n_tokens).map_err(|err| error(err.to_string()))?;
+        let id = parsed.id.unwrap_or(line_no.to_string());
+        candidates.push(Candidate { id, superperm });
+    }
+    return Ok(candidates);
+}
+
+pub fn read_candidates<R: BufRead>(r: R, format: BatchFormat) -> Result<Vec<Candidate>, BatchError> {
+    match format {
+        BatchFormat::Csv => read_candidates_csv(r),
+        BatchFormat::Jsonl => read_candidates_jsonl(r),
+    }
+}
+
+fn check_candidate(candidate: &Candidate, handle: &(dyn SuperPermHandling + Sync)) -> Verdict {
+    let superperm = &candidate.superperm;
+    let stats = SuperPermStats::compute(&superperm.sequence, superperm.n_tokens);
+    return Verdict {
+        id: candidate.id.clone(),
+        n: superperm.n_tokens,
+        length: superperm.sequence.len(),
+        // Unsupported n and sequences too short to hold every permutation are
+        // invalid without building a checklist
+        valid: handle.try_check_superperm(&superperm.sequence, superperm.n_tokens).unwrap_or(false),
+        distinct_perms: stats.distinct_perms,
+        waste: stats.waste,
+    };
+}
+
+/// Checks every candidate with the handle given. Verdicts are returned in the
+/// same order as the candidates.
+///
+/// Candidates are checked one after another, or spread across rayon's
+/// threads with the `parallel` feature.
+pub fn check_batch(candidates: &[Candidate], handle: &(dyn SuperPermHandling + Sync)) -> Vec<Verdict> {
+    #[cfg(feature = "parallel")]
+    return candidates.par_iter().map(|c| check_candidate(c, handle)).collect();
+    #[cfg(not(feature = "parallel"))]
+    return candidates.iter().map(|c| check_candidate(c, handle)).collect();
+}
+
+/// A field as written in CSV, wrapped in double quotes if it contains a
+/// comma, quote or line break, with any quotes doubled
+pub(crate) fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        return Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")));
+    }
+    return Cow::Borrowed(field);
+}
+
+/// Writes one verdict per row/line in the format given. CSV output starts
+/// with a header row.
+pub fn write_verdicts<W: Write>(mut w: W, verdicts: &[Verdict], format: BatchFormat) -> io::Result<()> {
+    match format {
+        BatchFormat::Csv => {
+            writeln!(w, "id,n,length,valid,distinct_perms,waste")?;
+            for v in verdicts {
+                writeln!(w, "{},{},{},{},{},{}", csv_field(&v.id), v.n, v.length, v.valid, v.distinct_perms, v.waste)?;
+            }
+        }
+        BatchFormat::Jsonl => {
+            for verdict in verdicts {
+                serde_json::to_writer(&mut w, verdict)?;
+                writeln!(w)?;
+            }
+        }
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bruteforce_optimise;
+
+    #[test]
+    fn batch_from_csv_and_jsonl() {
+        let csv = "id,n,sequence\nmin3,3,123121321\nshort,3,12312\n\"spaced\",3,\"1 2 3 1 2 1 3 2 1\"\n";
+        let from_csv = read_candidates_csv(csv.as_bytes()).unwrap();
+        let jsonl = concat!(
+            "{\"id\": \"min3\", \"n\": 3, \"sequence\": \"123121321\"}\n",
+            "{\"id\": \"short\", \"sequence\": [1, 2, 3, 1, 2]}\n",
+            "\n",
+            "{\"id\": \"spaced\", \"sequence\": \"1 2 3 1 2 1 3 2 1\"}\n",
+        );
+        let from_jsonl = read_candidates_jsonl(jsonl.as_bytes()).unwrap();
+        assert_eq!(from_csv, from_jsonl);
+
+        let verdicts = check_batch(&from_csv, &bruteforce_optimise::Handle);
+        let valid: Vec<bool> = verdicts.iter().map(|v| v.valid).collect();
+        assert_eq!(valid, [true, false, true]);
+        assert_eq!(verdicts[1].id, "short");
+        assert_eq!(verdicts[1].distinct_perms, 3);
+    }
+
+    #[test]
+    fn batch_errors_name_the_line() {
+        let csv = "id,sequence\na,123\nb,12x3\n";
+        assert!(matches!(read_candidates_csv(csv.as_bytes()), Err(BatchError::Line { line: 3, .. })));
+        assert!(matches!(read_candidates_csv("id,n\n".as_bytes()), Err(BatchError::Line { line: 1, .. })));
+        assert!(matches!(read_candidates_jsonl("{}\n".as_bytes()), Err(BatchError::Line { line: 1, .. })));
+        let csv = "id,n,sequence\nmin3,3,123121321\nbig,30,123\n";
+        assert!(matches!(read_candidates_csv(csv.as_bytes()), Err(BatchError::Line { line: 3, .. })));
+        let jsonl = "{\"sequence\": [1, 30, 1]}\n";
+        assert!(matches!(read_candidates_jsonl(jsonl.as_bytes()), Err(BatchError::Line { line: 1, .. })));
+    }
+
+    #[test]
+    fn csv_verdicts_quote_ids() {
+        let verdict = Verdict { id: String::from("a,\"b\""), n: 2, length: 3, valid: true, distinct_perms: 2, waste: 0 };
+        let mut csv = Vec::new();
+        write_verdicts(&mut csv, &[verdict], BatchFormat::Csv).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap().lines().nth(1), Some("\"a,\"\"b\"\"\",2,3,true,2,0"));
+        assert_eq!(csv_field("plain"), "plain");
+    }
+}
@@ -0,0 +1,97 @@
+// Greedy creation growing the sequence from both ends. Starting from the
+// first permutation, each step finds the uncovered permutation overlapping
+// the end of the sequence the most and the one overlapping the start the
+// most, and adds whichever overlaps more. The starting permutation ends up
+// somewhere in the middle where the two halves join.
+//
+// Checking is the same as bruteforce_optimise.
+use std::collections::VecDeque;
+
+use crate::base::*;
+use crate::bruteforce_optimise;
+use crate::metrics;
+use crate::permutation::PermutationMapper;
+
+/// Bidirectional greedy creation: at each step the permutation overlapping
+/// either end of the sequence the most is added there. Ties between the ends
+/// go to the end of the sequence, and ties at an end to the lowest
+/// PermutationMapper value (RankOrder::InsertionShift). Output depends only
+/// on n, never on platform or run.
+pub struct Handle;
+
+impl SuperPermHandling for Handle {
+    fn check_superperm(&self, sequence: &Vec<usize>, n_tokens: usize) -> bool {
+        return bruteforce_optimise::Handle.check_superperm(sequence, n_tokens);
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "bidirectional::create", skip_all, fields(n = n_tokens)))]
+    fn create_superperm(&self, n_tokens: usize) -> Vec<usize> {
+        let mapper = PermutationMapper::new((1..n_tokens+1).collect());
+        let total = mapper.mixed_radix_sys.max_value;
+        let mut superperm: VecDeque<usize> = (1..n_tokens+1).collect();
+        let mut perm_checklist: Vec<bool> = vec![false; total];
+        perm_checklist[0] = true;
+        let mut nodes = 0;
+
+        for _ in 1..total {
+            let mut chosen = None;
+            // Largest overlaps first, trying the end before the start
+            for i in (1..n_tokens).rev() {
+                let trailing: Vec<usize> = superperm.range(superperm.len()-i..).copied().collect();
+                if let Some((value, perm)) = mapper.perms_with_prefix(&trailing).find(|(value, _)| {
+                    nodes += 1;
+                    perm_checklist[*value] == false
+                }) {
+                    chosen = Some((value, perm, i, true));
+                    break;
+                }
+                let leading: Vec<usize> = superperm.range(..i).copied().collect();
+                let candidates = mapper.perms_with_suffix(&leading);
+                nodes += candidates.len();
+                if let Some((value, perm)) = candidates.into_iter().find(|(value, _)| perm_checklist[*value] == false) {
+                    chosen = Some((value, perm, i, false));
+                    break;
+                }
+            }
+            match chosen {
+                Some((value, perm, overlap, true)) => {
+                    trace_event!(overlap, value, "appended");
+                    perm_checklist[value] = true;
+                    superperm.extend(&perm[overlap..]);
+                }
+                Some((value, perm, overlap, false)) => {
+                    trace_event!(overlap, value, "prepended");
+                    perm_checklist[value] = true;
+                    for token in perm[..n_tokens - overlap].iter().rev() {
+                        superperm.push_front(*token);
+                    }
+                }
+                None => {
+                    // Nothing overlaps either end, so append the first
+                    // unchecked permutation in full
+                    let value = perm_checklist.iter().position(|checked| *checked == false).unwrap();
+                    trace_event!(value, "fallback append");
+                    perm_checklist[value] = true;
+                    superperm.extend(mapper.value_to_perm(&value));
+                }
+            }
+        }
+        metrics::add_nodes_expanded(nodes);
+        metrics::add_perms_covered(total);
+        return superperm.into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bidirectional_creation() {
+        for n in 0..7 {
+            let superperm = Handle.create_superperm(n);
+            assert!(Handle.check_superperm(&superperm, n), "n={}", n);
+        }
+        assert_eq!(Handle.create_superperm(3).len(), 9);
+    }
+}
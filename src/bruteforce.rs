@@ -1,18 +1,30 @@
 #![allow(dead_code)]
 // Functions to generate and check a superperm with nieve/ignorant
 // bruteforce methods. Absolutely no optimisations have been made
-use itertools::Itertools;
 use crate::base::*;
+use crate::metrics;
+use crate::permutation::HeapPermutations;
+#[cfg(not(feature = "parallel"))]
+use crate::permutation::next_permutation;
+#[cfg(feature = "parallel")]
+use crate::permutation::PermutationMapper;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
+/// Greedy creation: the permutation overlapping the end of the sequence the
+/// most is appended next, ties going to the first in the order of Heap's
+/// algorithm. Output depends only on n, never on platform or run.
 pub struct Handle;
 
-/// Return a vec containing all possible permutations of the sequence [1..n]
-/// E.g generate_perms(2) = [[1,2], [2,1]]
-fn generate_perms(n: usize) -> Vec<Vec<usize>> {
-    let mut permuts: Vec<Vec<usize>> = Vec::new();
-    let iter = (1..n+1).permutations(n);
-    for perm in iter {
-        permuts.push(perm);
+/// Return all possible permutations of the sequence [1..n] in the order of
+/// Heap's algorithm, one after another in a single vec so each permutation
+/// is a slice of n tokens rather than its own allocation
+/// E.g generate_perms(2) = [1,2, 2,1]
+fn generate_perms(n: usize) -> Vec<usize> {
+    let mut permuts: Vec<usize> = Vec::new();
+    let mut heap = HeapPermutations::new((1..n+1).collect());
+    while let Some(perm) = heap.next_perm() {
+        permuts.extend_from_slice(perm);
     }
     return permuts;
 }
@@ -20,53 +32,74 @@ fn generate_perms(n: usize) -> Vec<Vec<usize>> {
 impl SuperPermHandling for Handle {
     /// Check if a vector of numbers is a valid superpermutation for a sequence of [1..perm_n]
     /// E.g check_superperm([1,2,1], 2) will check if [1,2,1] is a superperm of [1,2]
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "bruteforce::check", skip_all, fields(n = perm_n, len = potential_super.len())))]
     fn check_superperm(&self, potential_super: &Vec<usize>, perm_n: usize) -> bool {
-        // Brute force method
-        let perms = generate_perms(perm_n);
-        // Create boolean for each perm to check
-        let mut perm_checklist: Vec<bool> = vec![false; perms.len()];
-        // Keep slicing over potential_super and check if the slice is a permutation
-        for i in 0..potential_super.len()-perm_n+1 {
-            let slice = &potential_super[i..i+perm_n];
-            for (pos, perm) in perms.iter().enumerate() {
-                if perm == slice {
-                    perm_checklist[pos] = true;
-                }
-            }
+        // The empty permutation is in every sequence
+        if perm_n == 0 {
+            return true;
+        }
+        // Too short to hold every permutation, so there's no need to build an
+        // n! sized checklist (or search for n! permutations) to say so
+        if long_enough(potential_super.len(), perm_n) == false {
+            return false;
+        }
+        metrics::add_windows_checked((potential_super.len() + 1).saturating_sub(perm_n));
+        // Same brute force search with the permutations split across threads
+        #[cfg(feature = "parallel")]
+        {
+            let mapper = PermutationMapper::new((1..perm_n+1).collect());
+            return mapper.par_iter().all(|perm| potential_super.windows(perm_n).any(|slice| slice == perm));
         }
-        // Check if all permutations have been sliced over/seen
-        for element in perm_checklist {
-            if element == false {
-                return false;
+        // Brute force method, each permutation is generated in turn in place
+        // and searched for by sliding over potential_super
+        #[cfg(not(feature = "parallel"))]
+        {
+            let mut perm: Vec<usize> = (1..perm_n+1).collect();
+            loop {
+                if !potential_super.windows(perm_n).any(|slice| slice == perm) {
+                    return false;
+                }
+                if !next_permutation(&mut perm) {
+                    break;
+                }
             }
+            // If function made it this far then potential_super is a superperm
+            return true;
         }
-        // If function made it this far then potential_super is a superperm
-        return true;
     }
 
     /// Returns a valid superpermutation for the sequence of [1..perm_n]
     /// Does not guarantee minimality for the superpermutation returned
     /// E.g create_superperm(3) = [1,2,3,1,2,1,3,2,1]
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "bruteforce::create", skip_all, fields(n = perm_n)))]
     fn create_superperm(&self, perm_n: usize) -> Vec<usize> {
         let mut superperm: Vec<usize> = Vec::new();
         let all_perms = generate_perms(perm_n);
-        let mut perm_checklist: Vec<bool> = vec![false; all_perms.len()];
+        // The single empty permutation takes up no tokens
+        let total = all_perms.len().checked_div(perm_n).unwrap_or(1);
+        let perm = |pos: usize| &all_perms[pos * perm_n..(pos + 1) * perm_n];
+        let mut perm_checklist: Vec<bool> = vec![false; total];
         // Set an initial sequence to superperm before starting algo
-        superperm.append(&mut all_perms[0].clone());
+        superperm.extend_from_slice(perm(0));
         perm_checklist[0] = true;
+        let mut nodes = 0;
+        let mut trailing: Vec<usize> = Vec::with_capacity(perm_n);
         // Loop for n possible permutations
-        for _ in 0..all_perms.len() {
+        for _ in 0..total {
             // Loop to grab biggest trailing size then smallest
             for i in (0..perm_n).rev() {
                 let mut perm_matched = false;
-                // Retrieve a slice of the trailing elements in superperm
-                let trailing = &superperm.clone()[superperm.len()-i..];
+                // Copy the trailing elements of superperm, as it's extended below
+                trailing.clear();
+                trailing.extend_from_slice(&superperm[superperm.len()-i..]);
                 // Check if trailing equals the starting of any perms left to be checked off
-                for (pos, perm) in all_perms.iter().enumerate() {
-                    if &perm[0..i] == trailing && perm_checklist[pos] == false {
+                for (pos, checked) in perm_checklist.iter_mut().enumerate() {
+                    let perm = perm(pos);
+                    nodes += 1;
+                    if perm[0..i] == trailing[..] && *checked == false {
                         // Check off the perm and append on the rest of the perm onto the superperm
                         perm_matched = true;
-                        perm_checklist[pos] = true;
+                        *checked = true;
                         superperm.extend_from_slice(&perm[i..]);
                         break;
                     }
@@ -82,6 +115,8 @@ impl SuperPermHandling for Handle {
 
             // Reaching this point, one permutation is guaranteed to be check off
         }
+        metrics::add_nodes_expanded(nodes);
+        metrics::add_perms_covered(total);
         return superperm;
     }
 }
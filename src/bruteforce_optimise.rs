@@ -6,270 +6,38 @@
 //        for superpermutations of 6~7 tokens. <5 tokens, bruteforce.rs
 //        wins by quite a decent margin.
 
-use crate::base::*;
-
-/// Class to encode any value into a different base where
-/// each "position" can be of a different base
-struct MixedRadix {
-    /// The base of each position for this system
-    pub bases: Vec<usize>,
-    /// The first integer above 0 that is unrepresentable with the bases given
-    pub max_value: usize,
-}
-
-/// Short for "Mixed Radix Representation".
-/// Data type for a value represented in a mixed radix system
-type MixedRadixRepr = Vec<usize>;
-
-impl MixedRadix {
-    /// bases parameter details:
-    ///  - Vec passed in expected to be the base for each position,
-    ///  - Values in the front of vec refer to least significant positions.
-    ///  - It also indirectly sets the number of positions available.
-    pub fn new(bases: Vec<usize>) -> MixedRadix{
-        // Calculate maximum representable value with the bases passed in
-        let mut max: usize = 1;
-        for base in &bases {
-            max *= base;
-        }
-        return MixedRadix {
-            bases: bases,
-            max_value: max,
-        }
-    }
-
-    /// Interprets a value into a representation of the bases specified at instantiation
-    /// 
-    /// E.g The value 54 encoded to the bases (5, 4, 3) will be represented as (4, 2, 2)
-    pub fn encode_value(&self, val: &usize) -> MixedRadixRepr {
-        let mut representation: MixedRadixRepr = vec![0; self.bases.len()];
-        let mut carry_over = val.clone();
-        for (i, base) in self.bases.iter().enumerate() {
-            representation[i] = carry_over % base;
-            carry_over = carry_over / base;
-        }
-        return representation;
-    }
-    /// Interprets a representation into a value. Inverse of encode_value
-    pub fn decode_representation(&self, repr: &MixedRadixRepr) -> usize {
-        let mut sum: usize = 0;
-        let mut position_mult: usize = 1;
-        for (pos, base) in self.bases.iter().enumerate() {
-            sum += repr[pos] * position_mult;
-            position_mult *= base;
-        }
-        return sum;
-    }
-}
-
-// implementing iteration over MixedRadix
-// Used: https://stackoverflow.com/questions/68606470/how-to-return-a-reference-when-implementing-an-iterator
-pub struct MixedRadixIter<'a> {
-    system: &'a MixedRadix,
-    i: usize,
-}
-impl<'a> Iterator for MixedRadixIter<'a> {
-    type Item = MixedRadixRepr;
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.i >= self.system.max_value {
-            return None;
-        } else {
-            let output = self.system.encode_value(&self.i);
-            self.i += 1;
-            return Some(output);
-        }
-    }
-}
-impl<'a> IntoIterator for &'a MixedRadix {
-    type Item = MixedRadixRepr;
-    type IntoIter = MixedRadixIter<'a>;
-    fn into_iter(self) -> Self::IntoIter {
-        MixedRadixIter {
-            system: self,
-            i: 0,
-        }
-    }
-}
-
-
-
-/// Class to help with the handling of permutations for an arbitrary sequence
-struct PermutationMapper {
-    pub core_sequence: Vec<usize>,
-    pub mixed_radix_sys: MixedRadix,
-}
-impl PermutationMapper {
-    // Why use MixedRadix for permutations?
-    // Imagine the sequence [1, 2, 3] and all its permutations.
-    // When creating a permutation, we have 3 positions to fill up.
-    // We insert "1" in one of the three positions. Two positions are left (e.g [_, 1, _])
-    // We insert "2" in one of the two positions. One position are left (e.g [2, 1, _])
-    // We insert "3" in the final position. Permutation is complete (e.g [2, 1, 3])
-    // With this logic, each permutation can be represented as a number in a mixed radix system.
-    // [1, 2, 3] be represented as (0, 0, 0) or 0
-    // [2, 1, 3] be represented as (1, 0, 0) or 1
-    // [3, 2, 1] be represented as (2, 1, 0) or 5
-
-    /// Vector passed in defines the sequence of tokens that all permutations will be built from.
-    /// 
-    /// This vector will be set as the "0th" permutations.
-    /// 
-    /// E.g passing in the vector [1,2,3] will focus on its permutations (i.e [2,1,3], [3,1,2], etc)
-    pub fn new(sequence: Vec<usize>) -> PermutationMapper {
-        let bases: Vec<usize> = (1..sequence.len()+1).rev().collect();
-        let obj = PermutationMapper{
-            core_sequence: sequence,
-            mixed_radix_sys: MixedRadix::new(bases), 
-        };
-        return obj;
-    }
-
-    /// Reads a value and maps it to a distinct permutation.
-    /// Passing in 0 will output the same sequence given at instantiation.
-    pub fn value_to_perm(&self, value: &usize) -> Vec<usize> {
-        let mut output_perm: Vec<usize> = vec![0; self.core_sequence.len()];
-        // Convert the value to a useful MixedRadix number
-        let repr = self.mixed_radix_sys.encode_value(value);
-        for (pos, token) in self.core_sequence.iter().enumerate() {
-            let shift = repr[pos];
-            let mut ind: usize = 0;
-
-            // Use each "digit" in the mixedradix representation to know how much to shift along
-            // before inserting.
-
-            // Skip to first non-filled position
-            while output_perm[ind] != 0 {
-                ind += 1;
-            }
-            // For each shift
-            for _ in 0..shift {
-                // Move along 1
-                ind += 1;
-                // Skip to next non-filled position
-                while output_perm[ind] != 0 {
-                    ind += 1;
-                }
-            }
-            output_perm[ind] = token.clone();
-        }
-        return output_perm
-    }
-    /// Reads a permutation and maps it to a distinct value.
-    /// Can be thought of as the inverse of value_to_perm.
-    /// 
-    /// The value resulting from this method should be able to be passed
-    /// back into value_to_perm to get back the original permutation.
-    /// 
-    /// A return of None indicates that the permutation passed in is not a
-    /// valid permutation of the sequence given at instantiation.
-    fn perm_to_value(&self, permutation: &Vec<usize>) -> Option<usize> {
-        // if the lengths of core_sequence and permutation doesn't match then
-        // mapping to a value is obviously impossible
-        if permutation.len() != self.core_sequence.len() {
-            return None;
-        }
-        
-        let mut repr: MixedRadixRepr = Vec::with_capacity(self.core_sequence.len());
-        let mut pos_is_filled: Vec<bool> = vec![false; self.core_sequence.len()];
-        let max_ind = self.core_sequence.len();
-
-        for token in self.core_sequence.iter() {
-            let mut shift = 0;
-            let mut ind = 0;
-            // move index to first unfilled position
-            while ind < max_ind && pos_is_filled[ind] == true {
-                ind += 1;
-            }
-            // keep shifting index
-            while ind < max_ind && *token != permutation[ind] {
-                // keep track of shifts
-                ind += 1;
-                shift += 1;
-                // autoskip over filled positions
-                while ind < max_ind && pos_is_filled[ind] == true {
-                    ind += 1;
-                }
-            }
-            // if the index rolls off the "edge" whilst looking for a token match
-            // then the permutation passed initially passed in is impossible to map to
-            if ind >= max_ind {
-                return None;
-            }
-            // keep track which position has been filled
-            pos_is_filled[ind] = true;
-            // store the number of shifts
-            repr.push(shift);
-        }
-        return Some(self.mixed_radix_sys.decode_representation(&repr));
-    }
-
-    /// Returns a vector of values in which if they were passed into value_to_perm,
-    /// the resulting permutation would match the perm_target.
-    /// 
-    /// The permutation target can be shorter than the sequence passed in at instantiation.
-    /// If this is the case, this method will look for permutations whose starting elements matches
-    /// the perm_target.
-    pub fn possible_values_for(&self, perm_target: &Vec<usize>) -> Vec<usize> {
-        let n = perm_target.len();
-        // Check for empty perm_target, this means all permutations "fit" the target
-        if n == 0 {
-            return (1..self.mixed_radix_sys.max_value).collect();
-        }
-        
-        let mut core_leftover = self.core_sequence.clone();
-        core_leftover.retain(|x| !perm_target.contains(x));
-        
-        // Calculate the "minimum" representation in which its value would map to the perm_target
-        let mut temp_perm = perm_target.clone();
-        temp_perm.append(&mut core_leftover.clone());
-        // if no value can be mapped for temp_perm then no values are possible
-        let Some(val) = self.perm_to_value(&temp_perm) else {
-            return vec![];
-        };
-        let min_repr = self.mixed_radix_sys.encode_value(&val);
-        
-        // Calculate the "maximum" representation in which its value would map to the perm_target
-        temp_perm = perm_target.clone();
-        temp_perm.append(&mut core_leftover.into_iter().rev().collect());
-        // if no value can be mapped for temp_perm then no values are possible
-        let Some(val) = self.perm_to_value(&temp_perm) else {
-            return vec![];
-        };
-        let max_repr = self.mixed_radix_sys.encode_value(&val);
-        
-        // Use max and min representations to get range of possible representations
-        let sys = MixedRadix::new(
-            max_repr
-                .iter()
-                .zip(min_repr.clone())
-                .map(|(max, min)| max+1-min)
-                .collect()
-        );
-        // Iterate through all the representations that fit between max and min repr and store
-        // the associated value. (Keep note of the difference between "representation" and "value")
-        let mut values = Vec::with_capacity(sys.max_value);
-        for repr in sys.into_iter() {
-            let cur_val = min_repr
-                    .iter()
-                    .zip(repr)
-                    .map(|(min, num)| min+num)
-                    .collect();
-            values.push(self.mixed_radix_sys.decode_representation(&cur_val));
-        }
-
-        return values;
-    }
-}
-
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
+use crate::base::*;
+use crate::metrics;
+use crate::permutation::{k_perm_count, k_perm_rank, k_perm_unrank, PermutationMapper};
+use crate::progress::{ProgressEvent, ProgressSink, Stopwatch};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Greedy creation: the permutation overlapping the end of the sequence the
+/// most is appended next, ties going to the lowest PermutationMapper value
+/// (RankOrder::InsertionShift). Output depends only on n, never on platform
+/// or run, and matches bruteforce for n <= 4.
 pub struct Handle;
 impl SuperPermHandling for Handle {
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "bruteforce_optimise::check", skip_all, fields(n = n_tokens, len = sequence.len())))]
     fn check_superperm(&self, sequence: &Vec<usize>, n_tokens: usize) -> bool {
+        // The empty permutation is in every sequence
+        if n_tokens == 0 {
+            return true;
+        }
+        // Too short to hold every permutation, so there's no need to build an
+        // n! sized checklist (or search for n! permutations) to say so
+        if long_enough(sequence.len(), n_tokens) == false {
+            return false;
+        }
         let mapper = PermutationMapper::new((1..n_tokens+1).collect());
 
         // Brute force approach
         let mut perm_checklist: Vec<bool> = vec![false; mapper.mixed_radix_sys.max_value];
         // Perform rolling window/slice over potential_super and check if the slice is a permutation
+        #[cfg(not(feature = "parallel"))]
         for slice in sequence.windows(mapper.core_sequence.len()) {
             let values = mapper.possible_values_for(&slice.to_vec());
             // values may be an empty list thus check with a for loop
@@ -277,26 +45,70 @@ impl SuperPermHandling for Handle {
                 perm_checklist[value] = true;
             }
         }
-        // Check if all permutations have been seen
-        for element in perm_checklist {
-            if element == false {
-                return false;
-            }
+        // Rank the windows across threads and check them off afterwards
+        #[cfg(feature = "parallel")]
+        for value in sequence
+            .par_windows(mapper.core_sequence.len())
+            .flat_map_iter(|slice| mapper.possible_values_for(&slice.to_vec()))
+            .collect::<Vec<usize>>()
+        {
+            perm_checklist[value] = true;
         }
-        // If function made it this far then potential_super is a valid superperm
-        return true;
+        // Check if all permutations have been seen
+        let covered = perm_checklist.iter().filter(|checked| **checked).count();
+        metrics::add_windows_checked((sequence.len() + 1).saturating_sub(n_tokens));
+        metrics::add_perms_covered(covered);
+        return covered == perm_checklist.len();
     }
 
     fn create_superperm(&self, n_tokens: usize) -> Vec<usize> {
+        return self.create_superperm_with_progress(n_tokens, usize::MAX, &mut |_: &ProgressEvent| {});
+    }
+}
+
+impl Handle {
+    /// Same as create_superperm, reporting progress to the sink each time
+    /// another interval permutations have been placed and once finished.
+    pub fn create_superperm_with_progress(&self, n_tokens: usize, interval: usize, sink: &mut dyn ProgressSink) -> Vec<usize> {
+        return self.create_superperm_cancellable(n_tokens, interval, sink, &AtomicBool::new(false));
+    }
+
+    /// Same as create_superperm_with_progress, stopping early once cancel is
+    /// set. The sequence built so far is returned, which is only a complete
+    /// superperm if cancel wasn't set before the last permutation was placed.
+    pub fn create_superperm_cancellable(&self, n_tokens: usize, interval: usize, sink: &mut dyn ProgressSink, cancel: &AtomicBool) -> Vec<usize> {
+        return self.create_from(n_tokens, 0, interval, sink, cancel);
+    }
+
+    /// Same as create_superperm, starting from the permutation with the
+    /// PermutationMapper value given instead of (1,2,3, ...,n_tokens).
+    /// Panics if the value isn't below n_tokens!
+    pub fn create_superperm_from(&self, n_tokens: usize, start_value: usize) -> Vec<usize> {
+        return self.create_from(n_tokens, start_value, usize::MAX, &mut |_: &ProgressEvent| {}, &AtomicBool::new(false));
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "bruteforce_optimise::create", skip_all, fields(n = n_tokens, start = start_value)))]
+    fn create_from(&self, n_tokens: usize, start_value: usize, interval: usize, sink: &mut dyn ProgressSink, cancel: &AtomicBool) -> Vec<usize> {
+        let start = Stopwatch::start();
         let mapper = PermutationMapper::new((1..n_tokens+1).collect());
 
         // Set an intial sequence to build the superperm from before starting algo
-        let mut superperm: Vec<usize> = (1..mapper.core_sequence.len()+1).collect();
+        let mut superperm: Vec<usize> = mapper.value_to_perm(&start_value);
         let mut perm_checklist: Vec<bool> = vec![false; mapper.mixed_radix_sys.max_value];
-        perm_checklist[0] = true;
+        perm_checklist[start_value] = true;
+        let mut nodes = 0;
 
         // Loop for all possible permutations to be covered
-        for _ in 1..mapper.mixed_radix_sys.max_value {
+        let mut placed = 1;
+        while placed < mapper.mixed_radix_sys.max_value {
+            if placed.is_multiple_of(interval.max(1)) {
+                let elapsed = start.elapsed_secs();
+                sink.report(&ProgressEvent::new(elapsed, superperm.len(), placed, mapper.mixed_radix_sys.max_value, placed));
+            }
+            if cancel.load(Ordering::Relaxed) {
+                trace_event!(placed, "cancelled");
+                break;
+            }
             let mut trail_matched = false;
 
             // Loop to grab the trailing sequences of superperm
@@ -305,11 +117,13 @@ impl SuperPermHandling for Handle {
                 // Grab the trailing sequence
                 let trailing = &superperm[superperm.len()-i..].to_vec();
                 // Check if trailing equals the start of any perms left to be checked off
-                for value in mapper.possible_values_for(&trailing) {
+                for (value, perm) in mapper.perms_with_prefix(trailing) {
+                    nodes += 1;
                     if perm_checklist[value] == false { // Perm has not been checked off
                         // Check off perm and append rest of it onto superperm
+                        trace_event!(overlap = i, value, "overlap chosen");
                         perm_checklist[value] = true;
-                        superperm.extend_from_slice(&mapper.value_to_perm(&value)[i..]);
+                        superperm.extend_from_slice(&perm[i..]);
                         perm_matched = true;
                         break;
                     }
@@ -328,84 +142,117 @@ impl SuperPermHandling for Handle {
                 // No trailing can be used to build off of. We are free to append on an entire permutation onto the super
                 for (i, checked) in perm_checklist.iter().enumerate() {
                     if *checked == false {
+                        trace_event!(value = i, "fallback append");
                         let mut perm = mapper.value_to_perm(&i);
                         superperm.append(&mut perm);
                         break
                     }
                 }
             }
+            placed += 1;
         }
+        let total = mapper.mixed_radix_sys.max_value;
+        metrics::add_nodes_expanded(nodes);
+        metrics::add_perms_covered(placed);
+        sink.report(&ProgressEvent::new(start.elapsed_secs(), superperm.len(), placed, total, placed));
         return superperm;
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+impl SuperKPermHandling for Handle {
+    fn check_super_k_perm(&self, sequence: &Vec<usize>, n_tokens: usize, k: usize) -> bool {
+        let Some(max_value) = k_perm_count(n_tokens, k) else {
+            return false;
+        };
+        if k == 0 {
+            return true;
+        }
+        let mut perm_checklist: Vec<bool> = vec![false; max_value];
+        for slice in sequence.windows(k) {
+            if let Some(value) = k_perm_rank(slice, n_tokens) {
+                perm_checklist[value] = true;
+            }
+        }
+        return perm_checklist.iter().all(|checked| *checked);
+    }
 
-    #[test]
-    fn mixedradix_iteration() {
-        let system = MixedRadix::new(vec![3,2,2]);
-        let expected = vec![
-            vec![0,0,0],
-            vec![1,0,0],
-            vec![2,0,0],
-            vec![0,1,0],
-            vec![1,1,0],
-            vec![2,1,0],
-            vec![0,0,1],
-            vec![1,0,1],
-            vec![2,0,1],
-            vec![0,1,1],
-            vec![1,1,1],
-            vec![2,1,1],
-        ];
-        // check if iterator can be used multiple times
-        for _ in 0..2 {
-            for (i, repr) in system.into_iter().enumerate() {
-                assert_eq!(repr, expected[i]);
+    fn create_super_k_perm(&self, n_tokens: usize, k: usize) -> Vec<usize> {
+        let max_value = k_perm_count(n_tokens, k).expect("k is larger than n_tokens or the count overflows");
+        if k == 0 {
+            return Vec::new();
+        }
+        let mut superperm: Vec<usize> = (1..k+1).collect();
+        let mut perm_checklist: Vec<bool> = vec![false; max_value];
+        perm_checklist[0] = true;
+
+        for _ in 1..max_value {
+            let mut matched = None;
+            // Arrangements starting with the trailing sequence have consecutive
+            // ranks, starting from the trailing sequence followed by the
+            // smallest unused tokens
+            for i in (1..k).rev() {
+                let trailing = &superperm[superperm.len()-i..];
+                let Some(mut first) = k_perm_rank(trailing, n_tokens) else {
+                    continue;
+                };
+                let block = k_perm_count(n_tokens - i, k - i).unwrap();
+                first *= block;
+                if let Some(value) = (first..first+block).find(|value| perm_checklist[*value] == false) {
+                    matched = Some((value, i));
+                    break;
+                }
             }
+            // Otherwise append the first unchecked arrangement in full
+            let (value, overlap) = matched.unwrap_or_else(|| {
+                (perm_checklist.iter().position(|checked| *checked == false).unwrap(), 0)
+            });
+            perm_checklist[value] = true;
+            superperm.extend_from_slice(&k_perm_unrank(value, n_tokens, k).unwrap()[overlap..]);
         }
+        return superperm;
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
     #[test]
-    fn mixedradix_edgecase() {
-        let system = MixedRadix::new(vec![1,2]);
-        let expected = vec![
-            vec![0,0],
-            vec![0,1],
-        ];
-        for (i, repr) in system.into_iter().enumerate() {
-            assert_eq!(repr, expected[i]);
+    fn super_k_perms() {
+        let handle = Handle;
+        for (n, k) in [(3, 2), (4, 2), (4, 3), (5, 3), (4, 4)] {
+            let sequence = handle.create_super_k_perm(n, k);
+            assert!(handle.check_super_k_perm(&sequence, n, k), "n={} k={}", n, k);
         }
+        // Every ordered pair of distinct tokens out of 3
+        assert!(handle.check_super_k_perm(&vec![1,2,3,1,3,2,1], 3, 2));
+        assert!(!handle.check_super_k_perm(&vec![1,2,3,1,3,2], 3, 2));
+        assert_eq!(handle.create_super_k_perm(4, 4), handle.create_superperm(4));
     }
 
     #[test]
-    fn encode_and_decode_permutations() {
-        let helper = PermutationMapper::new((1..6).collect());
-
-        for i in 1..helper.mixed_radix_sys.max_value {
-            assert_eq!(
-                helper.perm_to_value(&helper.value_to_perm(&i)),
-                Some(i)
-            );
-        }
+    fn progress_reports() {
+        let mut events = Vec::new();
+        let sequence = Handle.create_superperm_with_progress(4, 10, &mut |event: &ProgressEvent| events.push(event.clone()));
+        assert_eq!(sequence, Handle.create_superperm(4));
+        let covered: Vec<usize> = events.iter().map(|event| event.covered).collect();
+        assert_eq!(covered, vec![10, 20, 24]);
+        assert_eq!(events[2].best_length, 33);
+        assert_eq!(events[2].coverage, 1.0);
     }
 
     #[test]
-    fn impossible_permutations() {
-        let helper = PermutationMapper::new((1..6).collect());
-        let impossible = vec![
-            vec![1,2,3,4,4],
-            vec![0,2,3,4,5],
-            vec![1,2,3,2,5],
-            vec![1,2,3,5],
-            vec![1,2,3,4,5,6],
-        ];
-        for imp in impossible {
-            println!("{:?}", imp);
-            assert_eq!(helper.perm_to_value(&imp), None);
-        }
+    fn cancelled_creation() {
+        let cancel = AtomicBool::new(false);
+        let mut events = Vec::new();
+        let partial = Handle.create_superperm_cancellable(4, 5, &mut |event: &ProgressEvent| {
+            events.push(event.clone());
+            cancel.store(true, Ordering::Relaxed);
+        }, &cancel);
+        // Cancelled at the first report, after 5 permutations were placed
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].covered, 5);
+        assert!(Handle.create_superperm(4).starts_with(&partial));
+        assert!(!Handle.check_superperm(&partial, 4));
     }
 }
-
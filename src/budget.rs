@@ -0,0 +1,111 @@
+// Memory budgets for creation, so runs for large n are refused up front with
+// an error instead of being OOM-killed part way through. The creators keep
+// an n! sized checklist and a sequence around sum(k!) tokens long, and
+// nothing can be dropped or spilled as they go, so the budget is enforced by
+// estimating the peak usage before starting.
+use std::fmt;
+
+use crate::base::Algorithm;
+
+/// Upper limit on the memory a run may use
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryBudget {
+    pub limit_bytes: usize,
+}
+
+/// Error for a run that would go over its MemoryBudget
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BudgetExceeded {
+    /// Estimated peak usage of the run, None if it overflows a usize
+    pub needed_bytes: Option<usize>,
+    pub limit_bytes: usize,
+}
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.needed_bytes {
+            Some(needed) => write!(f, "estimated {} bytes needed, over the limit of {} bytes", needed, self.limit_bytes),
+            None => write!(f, "estimated memory needed overflows, over the limit of {} bytes", self.limit_bytes),
+        }
+    }
+}
+
+impl std::error::Error for BudgetExceeded {}
+
+/// n! and sum(k!) for k in 1..=n, the length the greedy creators' sequences
+/// stay within. None if either overflows.
+fn perms_and_length(n: usize) -> Option<(usize, usize)> {
+    let mut factorial: usize = 1;
+    let mut length: usize = 0;
+    for k in 1..n + 1 {
+        factorial = factorial.checked_mul(k)?;
+        length = length.checked_add(factorial)?;
+    }
+    return Some((factorial, length));
+}
+
+/// Estimated peak memory in bytes for the algorithm to create a superperm of
+/// n tokens. Every algorithm has an estimate, so None only when it overflows
+/// a usize. E.g create_estimate(Algorithm::BruteforceOptimise, 10) is about 36MB
+pub fn create_estimate(algorithm: Algorithm, n: usize) -> Option<usize> {
+    let (perms, length) = perms_and_length(n)?;
+    let token = std::mem::size_of::<usize>();
+    // Checklist of a bool per permutation and the sequence being built
+    let common = perms.checked_add(length.checked_mul(token)?)?;
+    match algorithm {
+        // Also holds every permutation, n tokens each
+        Algorithm::Bruteforce => return common.checked_add(perms.checked_mul(n.checked_mul(token)?)?),
+        Algorithm::BruteforceOptimise => return Some(common),
+        #[cfg(feature = "heuristics")]
+        Algorithm::Bidirectional => return Some(common),
+        // Also holds the cost of every step between two permutations
+        #[cfg(feature = "heuristics")]
+        Algorithm::WeightedGreedy => return common.checked_add(perms.checked_mul(perms)?),
+    }
+}
+
+impl MemoryBudget {
+    pub fn new(limit_bytes: usize) -> MemoryBudget {
+        return MemoryBudget { limit_bytes };
+    }
+
+    /// Ok if the estimate given fits within the budget
+    pub fn check(&self, needed_bytes: Option<usize>) -> Result<(), BudgetExceeded> {
+        match needed_bytes {
+            Some(needed) if needed <= self.limit_bytes => Ok(()),
+            _ => Err(BudgetExceeded { needed_bytes, limit_bytes: self.limit_bytes }),
+        }
+    }
+
+    /// Creates a superperm with the algorithm unless the run is estimated to
+    /// go over the budget
+    pub fn create(&self, algorithm: Algorithm, n: usize) -> Result<Vec<usize>, BudgetExceeded> {
+        self.check(create_estimate(algorithm, n))?;
+        return Ok(algorithm.handle().create_superperm(n));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budgets() {
+        // 6 checklist bytes and a 9 token sequence
+        assert_eq!(create_estimate(Algorithm::BruteforceOptimise, 3), Some(6 + 9 * 8));
+        assert!(create_estimate(Algorithm::Bruteforce, 3) > create_estimate(Algorithm::BruteforceOptimise, 3));
+        assert_eq!(create_estimate(Algorithm::BruteforceOptimise, 30), None);
+        for algorithm in Algorithm::ALL {
+            assert!(create_estimate(*algorithm, 5).is_some(), "{}", algorithm.name());
+        }
+        // The 6x6 cost matrix on top
+        #[cfg(feature = "heuristics")]
+        assert_eq!(create_estimate(Algorithm::WeightedGreedy, 3), Some(6 + 9 * 8 + 36));
+
+        let budget = MemoryBudget::new(1000);
+        assert_eq!(budget.create(Algorithm::BruteforceOptimise, 3).unwrap().len(), 9);
+        let exceeded = budget.create(Algorithm::BruteforceOptimise, 6).unwrap_err();
+        assert!(exceeded.needed_bytes.unwrap() > 1000);
+        assert!(budget.check(None).is_err());
+    }
+}
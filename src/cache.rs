@@ -0,0 +1,173 @@
+// Persistent cache of run results, so repeated sweeps skip configurations
+// already computed and the best sequence found for each n survives between
+// sessions. Backed by a sled database storing RunReports as JSON.
+//
+// Keys are "run/<algorithm>/<n>/<seed>/<config hash>" for results and
+// "best/<n>" for the shortest valid result seen for n. Numbers are zero
+// padded so keys sort by n.
+use std::fmt;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::report::RunReport;
+
+/// Identifies a configuration whose result can be reused
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    pub algorithm: String,
+    pub n: usize,
+    /// Seed of the solver's random number generator, 0 for deterministic solvers
+    pub seed: u64,
+    /// config_hash of any further solver settings
+    pub config_hash: u64,
+}
+
+impl CacheKey {
+    pub fn new(algorithm: &str, n: usize, seed: u64, config_hash: u64) -> CacheKey {
+        return CacheKey { algorithm: String::from(algorithm), n, seed, config_hash };
+    }
+
+    fn to_db_key(&self) -> String {
+        return format!("run/{}/{:020}/{:020}/{:016x}", self.algorithm, self.n, self.seed, self.config_hash);
+    }
+}
+
+/// Hashes a solver's settings into a CacheKey's config_hash. The settings are
+/// serialised to JSON and hashed with FNV-1a, which unlike std's hashers is
+/// stable across Rust versions.
+pub fn config_hash<T: Serialize>(config: &T) -> u64 {
+    // Serialising plain data structures cannot fail
+    let json = serde_json::to_vec(config).expect("config serialisation failed");
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in json {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    return hash;
+}
+
+/// Errors that can arise whilst using the cache
+#[derive(Debug)]
+pub enum CacheError {
+    Db(sled::Error),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for CacheError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CacheError::Db(err) => write!(f, "{}", err),
+            CacheError::Json(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for CacheError {}
+
+impl From<sled::Error> for CacheError {
+    fn from(err: sled::Error) -> CacheError {
+        return CacheError::Db(err);
+    }
+}
+
+impl From<serde_json::Error> for CacheError {
+    fn from(err: serde_json::Error) -> CacheError {
+        return CacheError::Json(err);
+    }
+}
+
+/// Results cache stored in a directory on disk
+pub struct ResultCache {
+    db: sled::Db,
+}
+
+impl ResultCache {
+    /// Opens the cache at path, creating it if it doesn't exist
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<ResultCache, CacheError> {
+        return Ok(ResultCache { db: sled::open(path)? });
+    }
+
+    fn read(&self, key: &str) -> Result<Option<RunReport>, CacheError> {
+        return match self.db.get(key)? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        };
+    }
+
+    /// The cached result for key, if there is one
+    pub fn get(&self, key: &CacheKey) -> Result<Option<RunReport>, CacheError> {
+        return self.read(&key.to_db_key());
+    }
+
+    /// Stores the result for key, replacing any previous one, and records it
+    /// as the best for its n if it's valid and shorter than the best so far
+    pub fn insert(&self, key: &CacheKey, report: &RunReport) -> Result<(), CacheError> {
+        self.db.insert(key.to_db_key(), serde_json::to_vec(report)?)?;
+        if report.valid && self.best(report.n)?.is_none_or(|best| report.length < best.length) {
+            self.db.insert(format!("best/{:020}", report.n), serde_json::to_vec(report)?)?;
+        }
+        self.db.flush()?;
+        return Ok(());
+    }
+
+    /// The cached result for key, running and caching it first if it's missing
+    pub fn get_or_run<F: FnOnce() -> RunReport>(&self, key: &CacheKey, run: F) -> Result<RunReport, CacheError> {
+        if let Some(report) = self.get(key)? {
+            return Ok(report);
+        }
+        let report = run();
+        self.insert(key, &report)?;
+        return Ok(report);
+    }
+
+    /// Shortest valid result cached for n
+    pub fn best(&self, n: usize) -> Result<Option<RunReport>, CacheError> {
+        return self.read(&format!("best/{:020}", n));
+    }
+
+    /// Shortest valid result cached for every n, in order of n
+    pub fn bests(&self) -> Result<Vec<RunReport>, CacheError> {
+        let mut reports = Vec::new();
+        for entry in self.db.scan_prefix("best/") {
+            let (_, bytes) = entry?;
+            reports.push(serde_json::from_slice(&bytes)?);
+        }
+        return Ok(reports);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::SuperPermHandling;
+    use crate::{bruteforce, bruteforce_optimise};
+
+    #[test]
+    fn cache_round_trip() {
+        let path = std::env::temp_dir().join(format!("superperm-cache-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        {
+            let cache = ResultCache::open(&path).unwrap();
+            let key = CacheKey::new("bruteforce", 3, 0, config_hash(&()));
+            let report = cache.get_or_run(&key, || RunReport::run("bruteforce", &bruteforce::Handle, 3)).unwrap();
+            assert_eq!(cache.get(&key).unwrap(), Some(report.clone()));
+            // A cached result is returned without running again
+            let again = cache.get_or_run(&key, || panic!("should have been cached")).unwrap();
+            assert_eq!(again, report);
+
+            // A longer result for the same n doesn't replace the best
+            let longer = RunReport::analyze("padded", &bruteforce_optimise::Handle, vec![1,1,2,3,1,2,1,3,2,1], 3);
+            cache.insert(&CacheKey::new("padded", 3, 0, 0), &longer).unwrap();
+            assert_eq!(cache.best(3).unwrap().unwrap().length, 9);
+            assert!(bruteforce_optimise::Handle.check_superperm(&longer.sequence, 3));
+        }
+        // Results survive reopening
+        let cache = ResultCache::open(&path).unwrap();
+        assert_eq!(cache.bests().unwrap().len(), 1);
+        assert_eq!(cache.best(4).unwrap(), None);
+        assert_ne!(config_hash(&[1, 2]), config_hash(&[2, 1]));
+        drop(cache);
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+}
@@ -0,0 +1,232 @@
+// Versioned on-disk format for the state of a long running search so it can
+// be resumed later, possibly by a newer version of this crate.
+//
+// Checkpoints are JSON. CHECKPOINT_VERSION is bumped whenever the meaning of
+// any field changes, and loading refuses checkpoints written with a newer
+// format version rather than guessing at their contents.
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::permutation::{checked_factorial, is_permutation, lex_rank};
+
+/// Version of the checkpoint format written by this version of the crate
+pub const CHECKPOINT_VERSION: u32 = 1;
+
+/// Snapshot of a solver's state
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Version of the checkpoint format the checkpoint was written with
+    pub format_version: u32,
+    /// Version of this crate that wrote the checkpoint
+    pub crate_version: String,
+    /// Name of the solver the state belongs to
+    pub solver: String,
+    /// Amount of unique tokens being searched over
+    pub n: usize,
+    /// Bitset of the permutation ranks covered so far, packed into 64 bit
+    /// words with rank r stored in bit r % 64 of word r / 64
+    pub coverage: Vec<u64>,
+    /// Partial sequences still to be explored
+    pub frontier: Vec<Vec<usize>>,
+    /// Best complete sequence found so far
+    pub best: Option<Vec<usize>>,
+    /// State of the solver's random number generator, if it uses one
+    pub rng_state: Option<Vec<u64>>,
+}
+
+/// Errors that can arise whilst saving or loading a checkpoint
+#[derive(Debug)]
+pub enum CheckpointError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    /// The checkpoint was written with a format this version of the crate doesn't understand
+    UnsupportedVersion { found: u32, supported: u32 },
+    /// The checkpoint's contents contradict each other
+    Inconsistent(&'static str),
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckpointError::Io(err) => write!(f, "{}", err),
+            CheckpointError::Json(err) => write!(f, "{}", err),
+            CheckpointError::UnsupportedVersion { found, supported } => {
+                write!(f, "checkpoint format version {} is not supported (supported: {})", found, supported)
+            }
+            CheckpointError::Inconsistent(reason) => write!(f, "inconsistent checkpoint: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for CheckpointError {}
+
+impl From<std::io::Error> for CheckpointError {
+    fn from(err: std::io::Error) -> CheckpointError {
+        return CheckpointError::Io(err);
+    }
+}
+
+impl From<serde_json::Error> for CheckpointError {
+    fn from(err: serde_json::Error) -> CheckpointError {
+        return CheckpointError::Json(err);
+    }
+}
+
+impl Checkpoint {
+    /// Creates an empty checkpoint for a solver with nothing covered yet
+    pub fn new(solver: &str, n: usize) -> Checkpoint {
+        return Checkpoint {
+            format_version: CHECKPOINT_VERSION,
+            crate_version: String::from(env!("CARGO_PKG_VERSION")),
+            solver: String::from(solver),
+            n,
+            coverage: vec![0; coverage_words(n).unwrap_or(0)],
+            frontier: Vec::new(),
+            best: None,
+            rng_state: None,
+        };
+    }
+
+    /// Checkpoint of a solver stopped part way through building sequence,
+    /// with the permutations it visits marked covered and the sequence as the
+    /// only frontier entry
+    pub fn from_partial(solver: &str, n: usize, sequence: &[usize]) -> Checkpoint {
+        let mut checkpoint = Checkpoint::new(solver, n);
+        if n > 0 {
+            for window in sequence.windows(n) {
+                if is_permutation(window) {
+                    checkpoint.set_covered(lex_rank(window).unwrap());
+                }
+            }
+        }
+        checkpoint.frontier.push(sequence.to_vec());
+        return checkpoint;
+    }
+
+    pub fn to_json(&self) -> String {
+        // Serialising plain data structures to a string cannot fail
+        return serde_json::to_string(self).expect("checkpoint serialisation failed");
+    }
+
+    /// Parses and validates a checkpoint. See validate
+    pub fn from_json(json: &str) -> Result<Checkpoint, CheckpointError> {
+        // Check the version before the rest so newer formats give a clear error
+        // rather than whichever field failed to parse first
+        #[derive(Deserialize)]
+        struct Versioned {
+            format_version: u32,
+        }
+        let versioned: Versioned = serde_json::from_str(json)?;
+        if versioned.format_version > CHECKPOINT_VERSION {
+            return Err(CheckpointError::UnsupportedVersion {
+                found: versioned.format_version,
+                supported: CHECKPOINT_VERSION,
+            });
+        }
+        let checkpoint: Checkpoint = serde_json::from_str(json)?;
+        checkpoint.validate()?;
+        return Ok(checkpoint);
+    }
+
+    /// Checks the fields of the checkpoint are consistent with each other, so
+    /// a damaged checkpoint is rejected instead of resuming a corrupt search.
+    pub fn validate(&self) -> Result<(), CheckpointError> {
+        if self.format_version > CHECKPOINT_VERSION {
+            return Err(CheckpointError::UnsupportedVersion {
+                found: self.format_version,
+                supported: CHECKPOINT_VERSION,
+            });
+        }
+        let Some(words) = coverage_words(self.n) else {
+            return Err(CheckpointError::Inconsistent("n is too large to track coverage"));
+        };
+        if self.coverage.len() != words {
+            return Err(CheckpointError::Inconsistent("coverage size doesn't match n"));
+        }
+        let in_range = |sequence: &Vec<usize>| sequence.iter().all(|token| *token >= 1 && *token <= self.n);
+        if !self.frontier.iter().all(in_range) || !self.best.iter().all(in_range) {
+            return Err(CheckpointError::Inconsistent("sequence contains tokens outside of 1..=n"));
+        }
+        return Ok(());
+    }
+
+    /// Writes the checkpoint to path. The checkpoint is written to a temporary
+    /// file first and moved into place so a crash never leaves a partial file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), CheckpointError> {
+        let path = path.as_ref();
+        let mut temp_name = path.as_os_str().to_owned();
+        temp_name.push(".tmp");
+        fs::write(&temp_name, self.to_json())?;
+        fs::rename(&temp_name, path)?;
+        trace_event!(path = %path.display(), solver = %self.solver, "checkpoint written");
+        return Ok(());
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Checkpoint, CheckpointError> {
+        return Checkpoint::from_json(&fs::read_to_string(path)?);
+    }
+
+    /// Whether the permutation with the rank given is marked as covered
+    pub fn is_covered(&self, rank: usize) -> bool {
+        return self.coverage[rank / 64] & (1 << (rank % 64)) != 0;
+    }
+
+    /// Marks the permutation with the rank given as covered
+    pub fn set_covered(&mut self, rank: usize) {
+        self.coverage[rank / 64] |= 1 << (rank % 64);
+    }
+}
+
+/// Amount of 64 bit words needed to hold a bit for each permutation of n
+/// tokens. None if n! overflows.
+fn coverage_words(n: usize) -> Option<usize> {
+    let total_perms = checked_factorial(n)?;
+    return Some(total_perms.div_ceil(64));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checkpoint_round_trip() {
+        let mut checkpoint = Checkpoint::new("test", 4);
+        checkpoint.set_covered(0);
+        checkpoint.set_covered(23);
+        checkpoint.frontier.push(vec![1,2,3,4,1]);
+        checkpoint.best = Some(vec![1,2,3,4]);
+        checkpoint.rng_state = Some(vec![1, 2, 3, 4]);
+        let loaded = Checkpoint::from_json(&checkpoint.to_json()).unwrap();
+        assert_eq!(loaded, checkpoint);
+        assert!(loaded.is_covered(23));
+        assert!(!loaded.is_covered(22));
+
+        // 1234 and 2341 have ranks 0 and 9
+        let partial = Checkpoint::from_partial("test", 4, &[1,2,3,4,1]);
+        assert_eq!(partial.coverage, vec![1 | 1 << 9]);
+        assert_eq!(partial.frontier, vec![vec![1,2,3,4,1]]);
+    }
+
+    #[test]
+    fn checkpoint_rejects_incompatible() {
+        let mut checkpoint = Checkpoint::new("test", 4);
+        checkpoint.format_version = CHECKPOINT_VERSION + 1;
+        assert!(matches!(
+            Checkpoint::from_json(&checkpoint.to_json()),
+            Err(CheckpointError::UnsupportedVersion { .. })
+        ));
+        // Checkpoints from newer versions may not even parse as the current layout
+        let json = format!("{{\"format_version\": {}, \"layout\": \"new\"}}", CHECKPOINT_VERSION + 1);
+        assert!(matches!(Checkpoint::from_json(&json), Err(CheckpointError::UnsupportedVersion { .. })));
+
+        let mut checkpoint = Checkpoint::new("test", 4);
+        checkpoint.coverage.push(0);
+        assert!(matches!(Checkpoint::from_json(&checkpoint.to_json()), Err(CheckpointError::Inconsistent(_))));
+        let mut checkpoint = Checkpoint::new("test", 4);
+        checkpoint.frontier.push(vec![1,5]);
+        assert!(matches!(Checkpoint::from_json(&checkpoint.to_json()), Err(CheckpointError::Inconsistent(_))));
+    }
+}
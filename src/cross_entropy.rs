@@ -0,0 +1,217 @@
+// Cross-entropy method over visit orders. A model gives the probability of
+// visiting each permutation next given the one just visited. Each iteration
+// samples a batch of visit orders from the model, then moves the model
+// towards the transitions used by the elite: the shortest fraction of the
+// batch. The model starts out favouring cheap transitions, each extra token
+// appended making a transition n times less likely.
+//
+// Permutations are identified by their lexicographic rank, as in local_search.
+use std::fmt;
+
+use rand::Rng;
+
+use crate::local_search::{order_to_sequence, Costs};
+use crate::permutation::checked_factorial;
+
+/// Errors for settings the cross-entropy method can't run with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CrossEntropyError {
+    /// Batches need at least one sample
+    ZeroBatchSize,
+    /// The elite ratio must be above 0 and at most 1
+    EliteRatio { ratio: f64 },
+    /// The smoothing must be from 0 to 1
+    Smoothing { smoothing: f64 },
+}
+
+impl fmt::Display for CrossEntropyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CrossEntropyError::ZeroBatchSize => write!(f, "batch size must be at least 1"),
+            CrossEntropyError::EliteRatio { ratio } => write!(f, "elite ratio {} is not above 0 and at most 1", ratio),
+            CrossEntropyError::Smoothing { smoothing } => write!(f, "smoothing {} is not from 0 to 1", smoothing),
+        }
+    }
+}
+
+impl std::error::Error for CrossEntropyError {}
+
+/// Cross-entropy solver, created with CrossEntropy::builder
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CrossEntropy {
+    batch_size: usize,
+    elite_ratio: f64,
+    smoothing: f64,
+    iterations: usize,
+}
+
+/// Settings of a CrossEntropy solver. Created by CrossEntropy::builder
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CrossEntropyBuilder {
+    batch_size: usize,
+    elite_ratio: f64,
+    smoothing: f64,
+    iterations: usize,
+}
+
+impl CrossEntropyBuilder {
+    /// Visit orders sampled each iteration, 100 by default
+    pub fn batch_size(mut self, batch_size: usize) -> CrossEntropyBuilder {
+        self.batch_size = batch_size;
+        return self;
+    }
+
+    /// Fraction of each batch, the shortest, the model learns from. At least
+    /// one sample is always used. 0.1 by default
+    pub fn elite_ratio(mut self, elite_ratio: f64) -> CrossEntropyBuilder {
+        self.elite_ratio = elite_ratio;
+        return self;
+    }
+
+    /// Weight of the elite's transitions against the previous model when
+    /// updating, 0.7 by default. 1 forgets the previous model entirely
+    pub fn smoothing(mut self, smoothing: f64) -> CrossEntropyBuilder {
+        self.smoothing = smoothing;
+        return self;
+    }
+
+    /// Amount of batches sampled, 50 by default
+    pub fn iterations(mut self, iterations: usize) -> CrossEntropyBuilder {
+        self.iterations = iterations;
+        return self;
+    }
+
+    /// Creates the solver, failing if a setting is out of range
+    pub fn build(self) -> Result<CrossEntropy, CrossEntropyError> {
+        if self.batch_size == 0 {
+            return Err(CrossEntropyError::ZeroBatchSize);
+        }
+        if (self.elite_ratio > 0.0 && self.elite_ratio <= 1.0) == false {
+            return Err(CrossEntropyError::EliteRatio { ratio: self.elite_ratio });
+        }
+        if (0.0..=1.0).contains(&self.smoothing) == false {
+            return Err(CrossEntropyError::Smoothing { smoothing: self.smoothing });
+        }
+        return Ok(CrossEntropy {
+            batch_size: self.batch_size,
+            elite_ratio: self.elite_ratio,
+            smoothing: self.smoothing,
+            iterations: self.iterations,
+        });
+    }
+}
+
+impl CrossEntropy {
+    pub fn builder() -> CrossEntropyBuilder {
+        return CrossEntropyBuilder { batch_size: 100, elite_ratio: 0.1, smoothing: 0.7, iterations: 50 };
+    }
+
+    /// Runs the method, returning the shortest superpermutation of
+    /// (1,2,3, ...,n) sampled. The same seeded rng always gives the same
+    /// sequence. Only practical up to n=6, see local_search.
+    pub fn solve<R: Rng + ?Sized>(&self, n: usize, rng: &mut R) -> Vec<usize> {
+        if n == 0 {
+            return Vec::new();
+        }
+        let costs = Costs::new(n);
+        let size = checked_factorial(n).expect("n! overflows");
+        // Row-major probabilities of moving from the row's permutation to the column's
+        let mut model: Vec<f64> = Vec::with_capacity(size * size);
+        for from in 0..size {
+            let weights: Vec<f64> = (0..size).map(|to| (n as f64).powi(1 - costs.cost(from, to) as i32)).collect();
+            let total: f64 = weights.iter().sum();
+            model.extend(weights.iter().map(|weight| weight / total));
+        }
+
+        let elites = ((self.batch_size as f64 * self.elite_ratio).ceil() as usize).max(1);
+        let mut best: Option<(usize, Vec<usize>)> = None;
+        for _ in 0..self.iterations {
+            let mut batch: Vec<(usize, Vec<usize>)> = (0..self.batch_size)
+                .map(|_| {
+                    let order = sample(&model, size, rng);
+                    (costs.length(&order), order)
+                })
+                .collect();
+            // Stable, so equal lengths keep the order they were sampled in
+            batch.sort_by_key(|(length, _)| *length);
+            if best.as_ref().is_none_or(|(length, _)| batch[0].0 < *length) {
+                best = Some(batch[0].clone());
+            }
+
+            let mut counts = vec![0.0; size * size];
+            for (_, order) in &batch[..elites] {
+                for pair in order.windows(2) {
+                    counts[pair[0] * size + pair[1]] += 1.0;
+                }
+            }
+            for from in 0..size {
+                let row = from * size..(from + 1) * size;
+                let total: f64 = counts[row.clone()].iter().sum();
+                // Only the last permutation visited has no transitions out
+                if total == 0.0 {
+                    continue;
+                }
+                for i in row {
+                    model[i] = self.smoothing * counts[i] / total + (1.0 - self.smoothing) * model[i];
+                }
+            }
+        }
+        let (_, order) = best.unwrap_or_else(|| (0, (0..size).collect()));
+        return order_to_sequence(&order, n);
+    }
+}
+
+/// Samples a visit order starting from rank 0. Each step picks an unvisited
+/// permutation with the model's probabilities, renormalised over the
+/// unvisited ones, or the lowest unvisited rank if they're all 0
+fn sample<R: Rng + ?Sized>(model: &[f64], size: usize, rng: &mut R) -> Vec<usize> {
+    let mut visited = vec![false; size];
+    visited[0] = true;
+    let mut order = vec![0];
+    let mut current = 0;
+    while order.len() < size {
+        let row = &model[current * size..(current + 1) * size];
+        let unvisited: Vec<usize> = (0..size).filter(|to| visited[*to] == false).collect();
+        let total: f64 = unvisited.iter().map(|to| row[*to]).sum();
+        current = if total > 0.0 {
+            let mut target = rng.gen::<f64>() * total;
+            // Rounding can leave target just past the last probability
+            let chosen = unvisited.iter().find(|to| {
+                if target < row[**to] {
+                    return true;
+                }
+                target -= row[**to];
+                return false;
+            });
+            *chosen.unwrap_or(unvisited.last().unwrap())
+        } else {
+            unvisited[0]
+        };
+        visited[current] = true;
+        order.push(current);
+    }
+    return order;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::SuperPermHandling;
+    use crate::bruteforce_optimise;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn cross_entropy() {
+        let solver = CrossEntropy::builder().batch_size(20).elite_ratio(0.2).iterations(10).build().unwrap();
+        let sequence = solver.solve(4, &mut StdRng::seed_from_u64(1));
+        assert!(bruteforce_optimise::Handle.check_superperm(&sequence, 4));
+        assert!(sequence.len() <= 40);
+        assert_eq!(sequence, solver.solve(4, &mut StdRng::seed_from_u64(1)));
+        assert_eq!(solver.solve(3, &mut StdRng::seed_from_u64(1)).len(), 9);
+
+        assert_eq!(CrossEntropy::builder().batch_size(0).build(), Err(CrossEntropyError::ZeroBatchSize));
+        assert_eq!(CrossEntropy::builder().elite_ratio(0.0).build(), Err(CrossEntropyError::EliteRatio { ratio: 0.0 }));
+        assert_eq!(CrossEntropy::builder().smoothing(1.5).build(), Err(CrossEntropyError::Smoothing { smoothing: 1.5 }));
+    }
+}
@@ -0,0 +1,101 @@
+// De Bruijn sequences B(k, n), the sibling problem of superpermutations: the
+// shortest sequences containing every word of length n over k tokens as a
+// contiguous run, rather than every permutation.
+//
+// Words are ranked as numbers in the mixed radix system with n positions of
+// base k, in the same way permutations are ranked for checking superperms.
+use crate::mixed_radix::MixedRadix;
+
+/// Rank of a word over the tokens (1,2,3, ...,k), reading the last token as
+/// the least significant digit. None if a token is outside of 1..=k.
+fn word_rank(system: &MixedRadix, word: &[usize]) -> Option<usize> {
+    let k = system.bases.first().copied().unwrap_or(0);
+    if word.iter().any(|token| *token == 0 || *token > k) {
+        return None;
+    }
+    let repr = word.iter().rev().map(|token| token - 1).collect();
+    return Some(system.decode_representation(&repr));
+}
+
+/// Creates a de Bruijn sequence for words of length n over the tokens
+/// (1,2,3, ...,k), written out linearly so every word appears as a window.
+/// The sequence has length k^n + n - 1, or k^n when read cyclically with the
+/// last n-1 tokens dropped.
+///
+/// Uses the greedy "prefer largest" construction: start with n copies of the
+/// token 1, then keep appending the largest token that completes an unseen word.
+///
+/// E.g k=2, n=2 gives 11221. Panics if k^n doesn't fit in a usize.
+pub fn create_de_bruijn(k: usize, n: usize) -> Vec<usize> {
+    if k == 0 || n == 0 {
+        return Vec::new();
+    }
+    let system = MixedRadix::new(vec![k; n]);
+    let mut sequence = vec![1; n];
+    let mut seen = vec![false; system.max_value];
+    seen[0] = true;
+    for _ in 1..system.max_value {
+        let mut appended = false;
+        for token in (1..k + 1).rev() {
+            sequence.push(token);
+            let rank = word_rank(&system, &sequence[sequence.len() - n..]).unwrap();
+            if seen[rank] == false {
+                seen[rank] = true;
+                appended = true;
+                break;
+            }
+            sequence.pop();
+        }
+        if !appended {
+            break;
+        }
+    }
+    return sequence;
+}
+
+/// Checks whether every word of length n over the tokens (1,2,3, ...,k)
+/// appears as a contiguous run of the sequence
+pub fn check_de_bruijn(sequence: &[usize], k: usize, n: usize) -> bool {
+    if n == 0 || k == 0 {
+        return true;
+    }
+    let Ok(system) = MixedRadix::try_new(vec![k; n]) else {
+        return false;
+    };
+    let mut word_checklist = vec![false; system.max_value];
+    for window in sequence.windows(n) {
+        if let Some(rank) = word_rank(&system, window) {
+            word_checklist[rank] = true;
+        }
+    }
+    return word_checklist.iter().all(|checked| *checked);
+}
+
+/// Same as check_de_bruijn but reads the sequence cyclically, i.e windows may
+/// wrap around from the end to the start
+pub fn check_de_bruijn_cycle(sequence: &[usize], k: usize, n: usize) -> bool {
+    // Cycling rather than appending the first n-1 tokens copes with sequences
+    // shorter than the words
+    let length = if sequence.is_empty() { 0 } else { sequence.len() + n.saturating_sub(1) };
+    let linear: Vec<usize> = sequence.iter().copied().cycle().take(length).collect();
+    return check_de_bruijn(&linear, k, n);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn de_bruijn_sequences() {
+        assert_eq!(create_de_bruijn(2, 2), vec![1,1,2,2,1]);
+        for (k, n) in [(2, 3), (3, 2), (2, 5), (4, 3), (1, 4)] {
+            let sequence = create_de_bruijn(k, n);
+            assert_eq!(sequence.len(), k.pow(n as u32) + n - 1, "k={} n={}", k, n);
+            assert!(check_de_bruijn(&sequence, k, n));
+            assert!(check_de_bruijn_cycle(&sequence[..sequence.len() - (n - 1)], k, n));
+        }
+        assert!(!check_de_bruijn(&[1,1,2,2], 2, 2));
+        assert!(check_de_bruijn_cycle(&[1,1,2,2], 2, 2));
+        assert!(!check_de_bruijn(&[1,1,3,2,1], 2, 2));
+    }
+}
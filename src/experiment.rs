@@ -0,0 +1,69 @@
+// Experiments studying how the greedy creators behave, rather than creating
+// a single superpermutation.
+use std::collections::BTreeMap;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::bruteforce_optimise;
+use crate::permutation::checked_factorial;
+
+/// Lengths the greedy creator reaches from each starting permutation. See
+/// greedy_start_distribution
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StartDistribution {
+    pub n: usize,
+    /// Amount of starting permutations giving each length, in order of length
+    pub histogram: BTreeMap<usize, usize>,
+    /// PermutationMapper value (RankOrder::InsertionShift) of the start giving
+    /// the shortest sequence, ties going to the lowest value
+    pub best_start: usize,
+    /// Sequence created from best_start
+    pub best: Vec<usize>,
+}
+
+/// Runs bruteforce_optimise's greedy creation from each of the n!
+/// permutations of (1,2,3, ...,n) and gathers the lengths reached. With the
+/// `parallel` feature the starts are spread across rayon's threads; the
+/// result is the same either way.
+///
+/// E.g for n=3 every start reaches the minimal length of 9.
+/// Panics if n! overflows.
+pub fn greedy_start_distribution(n: usize) -> StartDistribution {
+    let total = checked_factorial(n).expect("n! overflows");
+    let handle = bruteforce_optimise::Handle;
+    let length_from = |start: usize| handle.create_superperm_from(n, start).len();
+    #[cfg(not(feature = "parallel"))]
+    let lengths: Vec<usize> = (0..total).map(length_from).collect();
+    #[cfg(feature = "parallel")]
+    let lengths: Vec<usize> = (0..total).into_par_iter().map(length_from).collect();
+
+    let mut histogram = BTreeMap::new();
+    for length in &lengths {
+        *histogram.entry(*length).or_insert(0) += 1;
+    }
+    // min_by_key keeps the first of equal lengths, i.e the lowest start.
+    // Only the best sequence is created again rather than keeping all of them
+    let (best_start, _) = lengths.iter().enumerate().min_by_key(|(_, length)| **length).unwrap();
+    return StartDistribution { n, histogram, best_start, best: handle.create_superperm_from(n, best_start) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::SuperPermHandling;
+
+    #[test]
+    fn start_distributions() {
+        let distribution = greedy_start_distribution(3);
+        assert_eq!(distribution.histogram, BTreeMap::from([(9, 6)]));
+        assert_eq!(distribution.best_start, 0);
+
+        let distribution = greedy_start_distribution(4);
+        assert_eq!(distribution.histogram.values().sum::<usize>(), 24);
+        assert_eq!(distribution.best.len(), *distribution.histogram.keys().next().unwrap());
+        assert!(bruteforce_optimise::Handle.check_superperm(&distribution.best, 4));
+        assert_eq!(greedy_start_distribution(1).histogram, BTreeMap::from([(1, 1)]));
+    }
+}
@@ -0,0 +1,87 @@
+// C ABI for calling the creation and checking code from C/C++ programs. The
+// header include/superperm.h is generated from this file with cbindgen:
+//
+//   cbindgen --config cbindgen.toml --output include/superperm.h
+//
+// Sequences are arrays of size_t tokens. Sequences created here are owned by
+// the caller and must be given back to superperm_free.
+use std::slice;
+
+use crate::base::{check_n_supported, SuperPermHandling};
+use crate::bruteforce_optimise;
+
+/// Creates a superpermutation of the tokens 1..=n, writing its length to
+/// out_len. Returns null if out_len is null or n is above the largest n
+/// supported (MAX_SUPPORTED_N, 20 on 64 bit platforms).
+///
+/// # Safety
+/// out_len must be null or valid to write a size_t to.
+#[no_mangle]
+pub unsafe extern "C" fn superperm_create(n: usize, out_len: *mut usize) -> *mut usize {
+    if out_len.is_null() || check_n_supported(n).is_err() {
+        return std::ptr::null_mut();
+    }
+    let sequence = bruteforce_optimise::Handle.create_superperm(n).into_boxed_slice();
+    *out_len = sequence.len();
+    return Box::into_raw(sequence) as *mut usize;
+}
+
+/// Checks whether the len tokens at sequence make up a superpermutation of
+/// the tokens 1..=n. Returns 1 if so, 0 if not and -1 if sequence is null
+/// with a non-zero len or n is above the largest n supported.
+///
+/// # Safety
+/// sequence must be valid to read len tokens from, or may be null if len is 0.
+#[no_mangle]
+pub unsafe extern "C" fn superperm_check(sequence: *const usize, len: usize, n: usize) -> i32 {
+    // Panicking here would abort the calling program
+    if check_n_supported(n).is_err() {
+        return -1;
+    }
+    let tokens = if len == 0 {
+        Vec::new()
+    } else if sequence.is_null() {
+        return -1;
+    } else {
+        slice::from_raw_parts(sequence, len).to_vec()
+    };
+    return bruteforce_optimise::Handle.check_superperm(&tokens, n) as i32;
+}
+
+/// Frees a sequence returned by superperm_create. Does nothing if sequence is null.
+///
+/// # Safety
+/// sequence must be null or a pointer returned by superperm_create along with
+/// the length it gave, and not freed already.
+#[no_mangle]
+pub unsafe extern "C" fn superperm_free(sequence: *mut usize, len: usize) {
+    if sequence.is_null() {
+        return;
+    }
+    drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(sequence, len)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::MAX_SUPPORTED_N;
+
+    #[test]
+    fn c_abi_round_trip() {
+        unsafe {
+            let mut len = 0;
+            let sequence = superperm_create(4, &mut len);
+            assert_eq!(len, 33);
+            assert_eq!(superperm_check(sequence, len, 4), 1);
+            assert_eq!(superperm_check(sequence, len - 1, 4), 0);
+            superperm_free(sequence, len);
+
+            assert!(superperm_create(4, std::ptr::null_mut()).is_null());
+            assert_eq!(superperm_check(std::ptr::null(), 3, 2), -1);
+            assert_eq!(superperm_check(std::ptr::null(), 0, 0), 1);
+
+            assert!(superperm_create(MAX_SUPPORTED_N + 1, &mut len).is_null());
+            assert_eq!(superperm_check([1].as_ptr(), 1, MAX_SUPPORTED_N + 1), -1);
+        }
+    }
+}
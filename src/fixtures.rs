@@ -0,0 +1,97 @@
+// Embedded known sequences for validating implementations of
+// SuperPermHandling, including custom ones from outside this crate.
+//
+// The valid fixtures are the minimal superpermutations for n <= 5 and their
+// reversals. The invalid ones are near misses made by deleting a token from
+// a minimal superpermutation, which leaves it too short to be valid.
+use crate::base::SuperPermHandling;
+use crate::superperm::SuperPerm;
+
+/// Digit strings of the minimal superpermutations from the standard recursive
+/// construction, indexed by n-1. n=5 is split over lines for readability
+pub(crate) const MINIMAL: [&[&str]; 5] = [
+    &["1"],
+    &["121"],
+    &["123121321"],
+    &["123412314231243121342132413214321"],
+    &[
+        "1234512341523412534123541231452314253142351423154231245312435124",
+        "3152431254312134521342513421534213542132451324153241352413254132",
+        "1453214352143251432154321",
+    ],
+];
+
+/// The minimal superpermutation of n tokens for n in 1..=5
+pub(crate) fn minimal(n: usize) -> Option<SuperPerm> {
+    let digits = MINIMAL.get(n.checked_sub(1)?)?.concat();
+    let mut superperm = SuperPerm::from_digit_str(&digits).expect("embedded sequence is malformed");
+    superperm.n_tokens = n;
+    return Some(superperm);
+}
+
+/// A sequence with the verdict every checker should give it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fixture {
+    /// E.g "minimal-4-reversed" or "minimal-4-without-first"
+    pub name: String,
+    pub n: usize,
+    pub sequence: Vec<usize>,
+    /// Whether the sequence is a superpermutation of n tokens
+    pub valid: bool,
+}
+
+/// Every embedded fixture, in order of n
+pub fn fixtures() -> Vec<Fixture> {
+    let mut fixtures = Vec::new();
+    for n in 1..MINIMAL.len() + 1 {
+        let sequence = minimal(n).unwrap().sequence;
+        let fixture = |suffix: &str, sequence: Vec<usize>, valid: bool| Fixture {
+            name: format!("minimal-{}{}", n, suffix),
+            n,
+            sequence,
+            valid,
+        };
+        fixtures.push(fixture("", sequence.clone(), true));
+        fixtures.push(fixture("-reversed", sequence.iter().rev().copied().collect(), true));
+        for (suffix, i) in [("-without-first", 0), ("-without-middle", sequence.len() / 2), ("-without-last", sequence.len() - 1)] {
+            let mut near_miss = sequence.clone();
+            near_miss.remove(i);
+            fixtures.push(fixture(suffix, near_miss, false));
+        }
+    }
+    return fixtures;
+}
+
+/// Checks every fixture with the handle, giving those it got wrong. An empty
+/// list means the handle agrees with every fixture.
+pub fn verify_against_fixtures(handle: &dyn SuperPermHandling) -> Vec<Fixture> {
+    return fixtures().into_iter()
+        .filter(|fixture| handle.check_superperm(&fixture.sequence, fixture.n) != fixture.valid)
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Accepts everything, so gets every near miss wrong
+    struct AcceptAll;
+    impl SuperPermHandling for AcceptAll {
+        fn create_superperm(&self, _: usize) -> Vec<usize> {
+            return Vec::new();
+        }
+        fn check_superperm(&self, _: &Vec<usize>, _: usize) -> bool {
+            return true;
+        }
+    }
+
+    #[test]
+    fn fixture_verification() {
+        assert_eq!(fixtures().len(), 25);
+        assert_eq!(minimal(5).unwrap().sequence.len(), 153);
+        assert_eq!(minimal(0), None);
+        let failures = verify_against_fixtures(&AcceptAll);
+        assert_eq!(failures.len(), 15);
+        assert!(failures.iter().all(|fixture| fixture.valid == false));
+    }
+}
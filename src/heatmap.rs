@@ -0,0 +1,86 @@
+// PNG heatmaps of how a sequence covers the permutations of its tokens, for
+// inspecting long runs visually. Requires the `heatmap` feature.
+//
+// The image has two parts:
+// - A band along the top showing the coverage timeline, from black (nothing
+//   covered) to white (everything covered) going along the sequence
+// - A grid below with a cell per permutation in lexicographic order, coloured
+//   from blue (visited early) to red (visited late). Unvisited cells are black
+use std::path::Path;
+
+use image::{ImageResult, Rgb, RgbImage};
+
+use crate::analysis::{coverage_timeline, first_visits};
+use crate::permutation::{checked_factorial, lex_rank};
+
+/// Height in pixels of the coverage timeline band
+const TIMELINE_HEIGHT: u32 = 16;
+/// Images are scaled up to be at least this wide
+const MIN_WIDTH: u32 = 256;
+
+/// Colour for a visit at the fraction t of the way along the sequence
+fn heat(t: f64) -> Rgb<u8> {
+    let t = t.clamp(0.0, 1.0);
+    return Rgb([(255.0 * t) as u8, 0, (255.0 * (1.0 - t)) as u8]);
+}
+
+/// Renders the heatmap of a sequence for the tokens (1,2,3, ...,n). Panics
+/// if n! overflows a u32, i.e above n=12.
+pub fn render_coverage_heatmap(sequence: &[usize], n: usize) -> RgbImage {
+    let total_perms = checked_factorial(n).expect("n! overflows");
+    let cells = u32::try_from(total_perms).expect("too many permutations for a heatmap");
+    let side = (total_perms as f64).sqrt().ceil().max(1.0) as u32;
+    let scale = (MIN_WIDTH / side).max(1);
+    let width = side * scale;
+    let grid_rows = cells.div_ceil(side);
+    let mut image = RgbImage::new(width, TIMELINE_HEIGHT + grid_rows * scale);
+
+    let timeline = coverage_timeline(sequence, n);
+    for x in 0..width {
+        // Covered so far at the end of the run of positions this column stands for
+        let position = ((x as usize + 1) * sequence.len()).div_ceil(width as usize);
+        let covered = if position == 0 { 0 } else { timeline[position - 1] };
+        let level = (255 * covered / total_perms) as u8;
+        for y in 0..TIMELINE_HEIGHT {
+            image.put_pixel(x, y, Rgb([level, level, level]));
+        }
+    }
+
+    for start in first_visits(sequence, n) {
+        let rank = lex_rank(&sequence[start..start + n]).expect("first visits are permutations") as u32;
+        let colour = heat(start as f64 / sequence.len().saturating_sub(n).max(1) as f64);
+        let (cell_x, cell_y) = (rank % side * scale, TIMELINE_HEIGHT + rank / side * scale);
+        for dy in 0..scale {
+            for dx in 0..scale {
+                image.put_pixel(cell_x + dx, cell_y + dy, colour);
+            }
+        }
+    }
+    return image;
+}
+
+/// Renders the heatmap of a sequence and saves it as a PNG
+pub fn save_coverage_heatmap(sequence: &[usize], n: usize, path: impl AsRef<Path>) -> ImageResult<()> {
+    return render_coverage_heatmap(sequence, n).save(path);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heatmap_layout() {
+        let image = render_coverage_heatmap(&[1,2,3,1,2,1,3,2,1], 3);
+        // 6 permutations fit in a 3 by 2 grid, scaled up to 85 pixels a cell
+        assert_eq!(image.dimensions(), (255, TIMELINE_HEIGHT + 2 * 85));
+        // Fully covered by the end, 123 visited first and 321 last
+        assert_eq!(image.get_pixel(254, 0), &Rgb([255, 255, 255]));
+        assert_eq!(image.get_pixel(0, TIMELINE_HEIGHT), &Rgb([0, 0, 255]));
+        assert_eq!(image.get_pixel(254, TIMELINE_HEIGHT + 85), &Rgb([255, 0, 0]));
+
+        let image = render_coverage_heatmap(&[1,2,3], 3);
+        assert_eq!(image.get_pixel(254, TIMELINE_HEIGHT + 85), &Rgb([0, 0, 0]));
+        // 13! cells don't fit in a u32, refused rather than truncated
+        assert!(std::panic::catch_unwind(|| render_coverage_heatmap(&[1], 13)).is_err());
+    }
+}
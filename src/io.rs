@@ -0,0 +1,556 @@
+// Reading and writing of superpermutation sequences to text and files in
+// the supported formats.
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+
+use crate::superperm::{FormatError, SuperPerm};
+
+/// The on-disk/textual formats a sequence can be stored in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Each token written as a single digit. E.g "123121321". Only for n <= 9
+    Digits,
+    /// Comma separated tokens with a `n=<n_tokens>` header. Works for any n
+    Delimited,
+    /// Compact packed binary. See encode_binary for the layout. n <= 255
+    Binary,
+    /// Digit string spread over any number of lines with `#` comment lines,
+    /// as published by the superpermutators community. See parse_community
+    Community,
+    /// Single line base64 string for pasting into chat. See encode_share_string
+    Share,
+}
+
+impl FromStr for Format {
+    type Err = String;
+    fn from_str(name: &str) -> Result<Format, String> {
+        match name {
+            "digits" => Ok(Format::Digits),
+            "delimited" => Ok(Format::Delimited),
+            "binary" => Ok(Format::Binary),
+            "community" => Ok(Format::Community),
+            "share" => Ok(Format::Share),
+            _ => Err(format!(
+                "unknown format {:?}, expected one of: digits, delimited, binary, community, share",
+                name,
+            )),
+        }
+    }
+}
+
+/// Errors that can arise whilst reading or writing sequence files
+#[derive(Debug)]
+pub enum Error {
+    /// The underlying file couldn't be read or written
+    Io(std::io::Error),
+    /// The contents couldn't be converted to or from the format requested
+    Format(FormatError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "{}", err),
+            Error::Format(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Format(err) => Some(err),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Error {
+        return Error::Io(err);
+    }
+}
+
+impl From<FormatError> for Error {
+    fn from(err: FormatError) -> Error {
+        return Error::Format(err);
+    }
+}
+
+/// Magic bytes at the start of every file in the binary format
+pub const BINARY_MAGIC: &[u8; 4] = b"SPRM";
+/// Version of the binary format written by encode_binary
+pub const BINARY_VERSION: u8 = 1;
+
+/// Decodes a single sequence written in the format given
+pub fn decode_superperm(bytes: &[u8], format: Format) -> Result<SuperPerm, FormatError> {
+    match format {
+        Format::Digits => SuperPerm::from_digit_str(&String::from_utf8_lossy(bytes)),
+        Format::Delimited => SuperPerm::from_delimited_str(&String::from_utf8_lossy(bytes)),
+        Format::Binary => decode_binary(bytes),
+        Format::Community => parse_community(&String::from_utf8_lossy(bytes)),
+        Format::Share => decode_share_string(&String::from_utf8_lossy(bytes)),
+    }
+}
+
+/// Encodes a sequence in the format given. Text formats end with a newline.
+pub fn encode_superperm(superperm: &SuperPerm, format: Format) -> Result<Vec<u8>, FormatError> {
+    return to_bytes(|bytes| write_superperm(bytes, superperm, format));
+}
+
+/// Reads a single sequence written in the format given from any reader (file,
+/// pipe, socket, decompressor, ...). The reader is read until its end.
+pub fn read_superperm<R: Read>(mut r: R, format: Format) -> Result<SuperPerm, Error> {
+    let mut bytes = Vec::new();
+    r.read_to_end(&mut bytes)?;
+    return Ok(decode_superperm(&bytes, format)?);
+}
+
+/// Streams a sequence in the format given to any writer (file, pipe, socket,
+/// compressor, ...) without building the whole output in memory first. Text
+/// formats end with a newline.
+///
+/// Tokens are validated before anything is written, so a format error never
+/// leaves partial output behind.
+pub fn write_superperm<W: Write>(w: W, superperm: &SuperPerm, format: Format) -> Result<(), Error> {
+    let mut w = BufWriter::new(w);
+    match format {
+        Format::Digits => {
+            check_digits(&superperm.sequence)?;
+            write_digits(&mut w, &superperm.sequence)?;
+            w.write_all(b"\n")?;
+        }
+        Format::Delimited => {
+            writeln!(w, "n={}", superperm.n_tokens)?;
+            for (i, token) in superperm.sequence.iter().enumerate() {
+                if i != 0 {
+                    w.write_all(b",")?;
+                }
+                write!(w, "{}", token)?;
+            }
+            w.write_all(b"\n")?;
+        }
+        Format::Binary => write_binary(&mut w, superperm)?,
+        Format::Community => {
+            check_digits(&superperm.sequence)?;
+            writeln!(w, "# n={} length={}", superperm.n_tokens, superperm.sequence.len())?;
+            write_digits(&mut w, &superperm.sequence)?;
+            w.write_all(b"\n")?;
+        }
+        Format::Share => writeln!(w, "{}", encode_share_string(superperm)?)?,
+    }
+    w.flush()?;
+    return Ok(());
+}
+
+/// Runs a writing function against an in-memory buffer. Writing to memory
+/// can't fail so only format errors are possible.
+fn to_bytes(write: impl FnOnce(&mut Vec<u8>) -> Result<(), Error>) -> Result<Vec<u8>, FormatError> {
+    let mut bytes = Vec::new();
+    match write(&mut bytes) {
+        Ok(()) => Ok(bytes),
+        Err(Error::Format(err)) => Err(err),
+        Err(Error::Io(err)) => unreachable!("writing to memory failed: {}", err),
+    }
+}
+
+/// Checks every token can be written as a single digit
+fn check_digits(sequence: &[usize]) -> Result<(), FormatError> {
+    for (index, token) in sequence.iter().enumerate() {
+        if *token == 0 || *token > 9 {
+            return Err(FormatError::TokenNotDigit { index, token: *token });
+        }
+    }
+    return Ok(());
+}
+
+/// Writes each token as a digit. Tokens must have been checked by check_digits
+fn write_digits<W: Write>(w: &mut W, sequence: &[usize]) -> std::io::Result<()> {
+    let mut buffer = [0u8; 4096];
+    for chunk in sequence.chunks(buffer.len()) {
+        for (byte, token) in buffer.iter_mut().zip(chunk) {
+            *byte = b'0' + *token as u8;
+        }
+        w.write_all(&buffer[..chunk.len()])?;
+    }
+    return Ok(());
+}
+
+/// Opens a file for reading, transparently decompressing it if it's gzip
+/// compressed (requires the `gzip` feature).
+pub fn open_file(path: impl AsRef<Path>) -> std::io::Result<Box<dyn Read>> {
+    let mut file = BufReader::new(File::open(path)?);
+    if file.fill_buf()?.starts_with(GZIP_MAGIC) {
+        #[cfg(feature = "gzip")]
+        return Ok(Box::new(flate2::bufread::GzDecoder::new(file)));
+        #[cfg(not(feature = "gzip"))]
+        return Err(gzip_unsupported());
+    }
+    return Ok(Box::new(file));
+}
+
+/// Loads a sequence from the file at path, expecting the format given.
+///
+/// Gzip compressed files are decompressed transparently (requires the `gzip`
+/// feature).
+pub fn read_file(path: impl AsRef<Path>, format: Format) -> Result<SuperPerm, Error> {
+    return read_superperm(open_file(path)?, format);
+}
+
+/// Saves a sequence to the file at path in the format given, replacing any
+/// existing contents.
+///
+/// Paths ending in ".gz" are gzip compressed (requires the `gzip` feature).
+pub fn write_file(path: impl AsRef<Path>, superperm: &SuperPerm, format: Format) -> Result<(), Error> {
+    let path = path.as_ref();
+    if path.extension().is_some_and(|ext| ext == "gz") {
+        #[cfg(feature = "gzip")]
+        {
+            let file = File::create(path)?;
+            let mut encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+            write_superperm(&mut encoder, superperm, format)?;
+            encoder.finish()?;
+            return Ok(());
+        }
+        #[cfg(not(feature = "gzip"))]
+        return Err(Error::Io(gzip_unsupported()));
+    }
+    return write_superperm(File::create(path)?, superperm, format);
+}
+
+/// Bytes every gzip stream starts with
+const GZIP_MAGIC: &[u8; 2] = &[0x1f, 0x8b];
+
+#[cfg(not(feature = "gzip"))]
+fn gzip_unsupported() -> std::io::Error {
+    return std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "gzip compressed files require the `gzip` feature",
+    );
+}
+
+/// Parses a sequence file in the style published by the superpermutators
+/// community. E.g
+/// ```text
+/// # Superpermutation for n=3, length 9
+/// 12312
+/// 1321
+/// ```
+/// Lines starting with '#' are comments and the digits of the sequence may be
+/// split across any number of lines. A comment containing "n=<n>" sets
+/// n_tokens, otherwise it's taken to be the largest token found.
+pub fn parse_community(text: &str) -> Result<SuperPerm, FormatError> {
+    let mut digits = String::new();
+    let mut header_n: Option<usize> = None;
+    for line in text.lines() {
+        let line = line.trim();
+        if let Some(comment) = line.strip_prefix('#') {
+            header_n = header_n.or(comment_n(comment));
+            continue;
+        }
+        digits.extend(line.chars().filter(|c| !c.is_whitespace()));
+    }
+    let mut superperm = SuperPerm::from_digit_str(&digits)?;
+    if let Some(n) = header_n {
+        superperm.n_tokens = n;
+    }
+    return Ok(superperm);
+}
+
+/// Writes a sequence in the community style: a comment line giving n and the
+/// length, followed by the digit string
+pub fn format_community(superperm: &SuperPerm) -> Result<String, FormatError> {
+    let bytes = to_bytes(|bytes| write_superperm(bytes, superperm, Format::Community))?;
+    return Ok(String::from_utf8(bytes).expect("community format is ASCII"));
+}
+
+/// Looks for "n=<n>" within a comment
+fn comment_n(comment: &str) -> Option<usize> {
+    let start = comment.find("n=")? + 2;
+    let digits: String = comment[start..].chars().take_while(|c| c.is_ascii_digit()).collect();
+    return digits.parse().ok();
+}
+
+/// A sequence listed in a score file along with its score
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScoredSequence {
+    pub score: usize,
+    pub superperm: SuperPerm,
+}
+
+/// Parses a score file where each line is a score followed by a digit
+/// sequence, separated by whitespace or a comma. E.g
+/// ```text
+/// # waste sequence
+/// 1 123121321
+/// 2 1231213214
+/// ```
+/// Blank lines and lines starting with '#' are skipped. The meaning of the
+/// score (waste, permutations visited, ...) is left to the caller.
+pub fn parse_score_file(text: &str) -> Result<Vec<ScoredSequence>, FormatError> {
+    let mut scored = Vec::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.splitn(2, |c: char| c == ',' || c.is_whitespace());
+        let score_field = fields.next().unwrap_or("");
+        let Ok(score) = score_field.parse::<usize>() else {
+            return Err(FormatError::InvalidToken { index: scored.len(), token: score_field.to_string() });
+        };
+        let superperm = SuperPerm::from_digit_str(fields.next().unwrap_or(""))?;
+        scored.push(ScoredSequence { score, superperm });
+    }
+    return Ok(scored);
+}
+
+/// Loads every sequence from a score file. See parse_score_file
+pub fn read_score_file(path: impl AsRef<Path>) -> Result<Vec<ScoredSequence>, Error> {
+    let mut text = String::new();
+    open_file(path)?.read_to_string(&mut text)?;
+    return Ok(parse_score_file(&text)?);
+}
+
+/// Encodes a sequence as a single line that can be pasted into chat or issues
+/// and decoded exactly with decode_share_string. The binary format is wrapped
+/// in URL safe base64 (without padding) behind a prefix giving n, e.g "sp3:U1BS...".
+pub fn encode_share_string(superperm: &SuperPerm) -> Result<String, FormatError> {
+    let bytes = encode_binary(superperm)?;
+    return Ok(format!("sp{}:{}", superperm.n_tokens, URL_SAFE_NO_PAD.encode(bytes)));
+}
+
+/// Decodes a string created by encode_share_string. Surrounding whitespace is ignored.
+pub fn decode_share_string(share: &str) -> Result<SuperPerm, FormatError> {
+    let invalid = |reason: &'static str| FormatError::InvalidShareString { reason };
+    let share = share.trim();
+    let Some((prefix, data)) = share.split_once(':') else {
+        return Err(invalid("missing ':' after the prefix"));
+    };
+    let Some(Ok(n_tokens)) = prefix.strip_prefix("sp").map(|n| n.parse::<usize>()) else {
+        return Err(invalid("prefix isn't of the form sp<n>"));
+    };
+    let Ok(bytes) = URL_SAFE_NO_PAD.decode(data) else {
+        return Err(invalid("data isn't valid base64"));
+    };
+    let superperm = decode_binary(&bytes)?;
+    if superperm.n_tokens != n_tokens {
+        return Err(invalid("prefix doesn't match the encoded n"));
+    }
+    return Ok(superperm);
+}
+
+// Binary format layout:
+//  - 4 bytes of magic "SPRM"
+//  - 1 byte format version
+//  - n_tokens as a varint
+//  - amount of tokens in the sequence as a varint
+//  - 1 byte giving the bits used per token (4 or 8)
+//  - the packed tokens. With 4 bits per token, two tokens share a byte with
+//    the earlier token in the high nibble. A trailing unused nibble is 0.
+// Varints are LEB128: 7 bits per byte, least significant group first, with the
+// top bit set on every byte except the last.
+
+/// Packs a sequence into the binary format. 4 bits are used per token when
+/// n_tokens <= 15, otherwise 8 bits. Tokens above 255 can't be represented.
+pub fn encode_binary(superperm: &SuperPerm) -> Result<Vec<u8>, FormatError> {
+    return to_bytes(|bytes| write_binary(bytes, superperm));
+}
+
+fn write_binary<W: Write>(w: &mut W, superperm: &SuperPerm) -> Result<(), Error> {
+    for (index, token) in superperm.sequence.iter().enumerate() {
+        if *token > u8::MAX as usize {
+            return Err(Error::Format(FormatError::TokenTooLarge { index, token: *token }));
+        }
+    }
+    let largest = superperm.sequence.iter().copied().max().unwrap_or(0).max(superperm.n_tokens);
+    let bits: u8 = if largest <= 15 { 4 } else { 8 };
+
+    w.write_all(BINARY_MAGIC)?;
+    w.write_all(&[BINARY_VERSION])?;
+    write_varint(w, superperm.n_tokens)?;
+    write_varint(w, superperm.sequence.len())?;
+    w.write_all(&[bits])?;
+    if bits == 4 {
+        for pair in superperm.sequence.chunks(2) {
+            let low = pair.get(1).copied().unwrap_or(0);
+            w.write_all(&[((pair[0] << 4) | low) as u8])?;
+        }
+    } else {
+        for token in &superperm.sequence {
+            w.write_all(&[*token as u8])?;
+        }
+    }
+    return Ok(());
+}
+
+/// Unpacks a sequence from the binary format. Inverse of encode_binary
+pub fn decode_binary(bytes: &[u8]) -> Result<SuperPerm, FormatError> {
+    let invalid = |reason: &'static str| FormatError::InvalidBinary { reason };
+    if bytes.len() < BINARY_MAGIC.len() + 1 || &bytes[..BINARY_MAGIC.len()] != BINARY_MAGIC {
+        return Err(invalid("missing magic header"));
+    }
+    if bytes[BINARY_MAGIC.len()] != BINARY_VERSION {
+        return Err(invalid("unsupported format version"));
+    }
+    let mut pos = BINARY_MAGIC.len() + 1;
+    let n_tokens = read_varint(bytes, &mut pos).ok_or(invalid("malformed token count"))?;
+    let length = read_varint(bytes, &mut pos).ok_or(invalid("malformed sequence length"))?;
+    let bits = *bytes.get(pos).ok_or(invalid("truncated header"))?;
+    pos += 1;
+
+    let packed = &bytes[pos..];
+    // Checked before allocating, as the length comes from untrusted input
+    let expected = match bits {
+        4 => length.div_ceil(2),
+        8 => length,
+        _ => return Err(invalid("unsupported bits per token")),
+    };
+    if packed.len() != expected {
+        return Err(invalid("packed data doesn't match the sequence length"));
+    }
+    let mut sequence = Vec::with_capacity(length);
+    if bits == 4 {
+        for byte in packed {
+            sequence.push((byte >> 4) as usize);
+            sequence.push((byte & 0x0f) as usize);
+        }
+        sequence.truncate(length);
+    } else {
+        sequence.extend(packed.iter().map(|byte| *byte as usize));
+    }
+    return Ok(SuperPerm::new(sequence, n_tokens));
+}
+
+fn write_varint<W: Write>(w: &mut W, mut value: usize) -> std::io::Result<()> {
+    while value >= 0x80 {
+        w.write_all(&[(value as u8 & 0x7f) | 0x80])?;
+        value >>= 7;
+    }
+    return w.write_all(&[value as u8]);
+}
+
+/// Reads a varint starting at pos, moving pos past it
+fn read_varint(bytes: &[u8], pos: &mut usize) -> Option<usize> {
+    let mut value: usize = 0;
+    let mut shift = 0;
+    loop {
+        let byte = *bytes.get(*pos)?;
+        *pos += 1;
+        if shift >= usize::BITS {
+            return None;
+        }
+        let bits = (byte & 0x7f) as usize;
+        // Bits shifted past the top of a usize would be silently lost
+        if (bits << shift) >> shift != bits {
+            return None;
+        }
+        value |= bits << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+        shift += 7;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binary_round_trip() {
+        let cases = [
+            SuperPerm::new(vec![1,2,3,1,2,1,3,2,1], 3),
+            SuperPerm::new(vec![1,2,1,2], 2),
+            SuperPerm::new(vec![16,1,200,3], 200),
+            SuperPerm::new((0..300).map(|i| i % 7 + 1).collect(), 7),
+            SuperPerm::new(vec![], 0),
+        ];
+        for superperm in cases {
+            let bytes = encode_binary(&superperm).unwrap();
+            assert_eq!(decode_binary(&bytes).unwrap(), superperm);
+        }
+        // 4 bit packing halves the size of the token data
+        let bytes = encode_binary(&SuperPerm::new(vec![1; 1000], 7)).unwrap();
+        assert!(bytes.len() < 520);
+    }
+
+    #[test]
+    fn binary_invalid() {
+        assert!(encode_binary(&SuperPerm::new(vec![1,256], 256)).is_err());
+        assert!(decode_binary(b"SPR").is_err());
+        let mut bytes = encode_binary(&SuperPerm::new(vec![1,2,3,1,2,1,3,2,1], 3)).unwrap();
+        bytes.pop();
+        assert!(decode_binary(&bytes).is_err());
+
+        // Hostile lengths are rejected before anything is allocated
+        let mut hostile = b"SPRM\x01\x03".to_vec();
+        hostile.extend([0xff; 9]);
+        hostile.extend([0x7f, 4]);
+        assert_eq!(decode_binary(&hostile), Err(FormatError::InvalidBinary { reason: "malformed sequence length" }));
+        let mut huge = Vec::new();
+        write_varint(&mut huge, 1 << 40).unwrap();
+        let huge = [b"SPRM\x01\x03".as_slice(), &huge, &[4, 0x12]].concat();
+        assert!(decode_binary(&huge).is_err());
+        assert!(decode_share_string(&format!("sp3:{}", URL_SAFE_NO_PAD.encode(&huge))).is_err());
+    }
+
+    #[test]
+    fn reader_writer_round_trip() {
+        let superperm = SuperPerm::new(vec![1,2,3,1,2,1,3,2,1], 3);
+        for format in [Format::Digits, Format::Delimited, Format::Binary, Format::Community, Format::Share] {
+            let mut stream = Vec::new();
+            write_superperm(&mut stream, &superperm, format).unwrap();
+            assert_eq!(read_superperm(stream.as_slice(), format).unwrap(), superperm);
+        }
+        // Nothing is written when the sequence can't be represented
+        let mut stream = Vec::new();
+        let result = write_superperm(&mut stream, &SuperPerm::new(vec![1,2,10], 10), Format::Digits);
+        assert!(matches!(result, Err(Error::Format(FormatError::TokenNotDigit { index: 2, .. }))));
+        assert!(stream.is_empty());
+    }
+
+    #[test]
+    fn share_string_round_trip() {
+        let superperm = SuperPerm::new(vec![1,2,3,1,2,1,3,2,1], 3);
+        let share = encode_share_string(&superperm).unwrap();
+        assert!(share.starts_with("sp3:"));
+        assert_eq!(decode_share_string(&format!(" {}\n", share)).unwrap(), superperm);
+
+        assert!(decode_share_string("123121321").is_err());
+        assert!(decode_share_string(&share.replace("sp3:", "sp4:")).is_err());
+        assert!(decode_share_string("sp3:not*base64").is_err());
+    }
+
+    #[test]
+    fn community_files() {
+        let text = "# Superpermutation for n=4 (found by hand)\n1234123142312431213\n\n42132413214321\n";
+        let superperm = parse_community(text).unwrap();
+        assert_eq!(superperm.n_tokens, 4);
+        assert_eq!(superperm.sequence.len(), 33);
+        assert_eq!(parse_community(&format_community(&superperm).unwrap()).unwrap(), superperm);
+
+        let scored = parse_score_file("# waste sequence\n1 123121321\n\n2,1231213214\n").unwrap();
+        assert_eq!(scored.len(), 2);
+        assert_eq!(scored[1].score, 2);
+        assert_eq!(scored[1].superperm.sequence, vec![1,2,3,1,2,1,3,2,1,4]);
+        assert!(parse_score_file("x 123").is_err());
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn gzip_file_round_trip() {
+        let superperm = SuperPerm::new(vec![1,2,3,1,2,1,3,2,1], 3);
+        let path = std::env::temp_dir().join(format!("superperm_test_{}.txt.gz", std::process::id()));
+        write_file(&path, &superperm, Format::Digits).unwrap();
+        assert!(std::fs::read(&path).unwrap().starts_with(GZIP_MAGIC));
+        assert_eq!(read_file(&path, Format::Digits).unwrap(), superperm);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,299 @@
+// A transport independent job protocol for spreading work over many workers.
+// A Coordinator hands out Jobs and collects JobResults, and a worker turns
+// jobs into results. Both are plain serde types written one JSON object per
+// line by run_worker, so they can be passed over TCP, files, pipes or message
+// queues alike.
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::io::{self, BufRead, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::base::{check_n_supported, Algorithm};
+use crate::batch::{self, Candidate, Verdict};
+use crate::permutation::{checked_factorial, is_permutation, lex_rank};
+use crate::superperm::SuperPerm;
+
+/// Most ranks a CoverageChunk job may cover, as each could be reported missing
+pub const MAX_CHUNK_RANKS: usize = 1 << 24;
+
+/// A sequence to check as part of a CheckBatch job
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobCandidate {
+    pub id: String,
+    pub n: usize,
+    pub sequence: Vec<usize>,
+}
+
+/// The work to be done for a job
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Task {
+    /// Check each candidate with the algorithm named
+    CheckBatch { algorithm: String, candidates: Vec<JobCandidate> },
+    /// Find which permutations of n tokens with a lexicographic rank in
+    /// start..end don't appear in the sequence. Splitting the ranks into
+    /// chunks spreads the checking of a single long sequence
+    CoverageChunk { sequence: Vec<usize>, n: usize, start: usize, end: usize },
+    /// Create a superpermutation of n tokens with the algorithm named
+    Create { algorithm: String, n: usize },
+}
+
+/// A task along with the identifier its result is reported under
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Job {
+    pub id: u64,
+    pub task: Task,
+}
+
+/// What a worker produced for a job
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Outcome {
+    Verdicts { verdicts: Vec<Verdict> },
+    /// Ranks of the permutations missing from the chunk
+    Missing { ranks: Vec<usize> },
+    Created { sequence: Vec<usize> },
+    /// The job couldn't be done, e.g an unknown algorithm was named
+    Failed { reason: String },
+}
+
+/// The result of a job, reported under the job's id
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobResult {
+    pub id: u64,
+    pub outcome: Outcome,
+}
+
+/// Does the work of a single job. Jobs come from untrusted input, so an n
+/// above MAX_SUPPORTED_N gives a Failed outcome rather than a panic.
+pub fn run_job(job: &Job) -> JobResult {
+    let outcome = match &job.task {
+        Task::CheckBatch { algorithm, candidates } => match algorithm.parse::<Algorithm>() {
+            Ok(algorithm) => {
+                if let Err(err) = candidates.iter().try_for_each(|c| check_n_supported(c.n)) {
+                    return JobResult { id: job.id, outcome: Outcome::Failed { reason: err.to_string() } };
+                }
+                let candidates: Vec<Candidate> = candidates
+                    .iter()
+                    .map(|c| Candidate { id: c.id.clone(), superperm: SuperPerm::new(c.sequence.clone(), c.n) })
+                    .collect();
+                Outcome::Verdicts { verdicts: batch::check_batch(&candidates, algorithm.handle().as_ref()) }
+            }
+            Err(reason) => Outcome::Failed { reason },
+        },
+        Task::CoverageChunk { sequence, n, start, end } => {
+            if let Err(err) = check_n_supported(*n) {
+                return JobResult { id: job.id, outcome: Outcome::Failed { reason: err.to_string() } };
+            }
+            // There are only n! ranks, however large the chunk asked for
+            let end = (*end).min(checked_factorial(*n).unwrap());
+            if end.saturating_sub(*start) > MAX_CHUNK_RANKS {
+                let reason = format!("chunk of {} ranks is over the limit of {}", end - start, MAX_CHUNK_RANKS);
+                return JobResult { id: job.id, outcome: Outcome::Failed { reason } };
+            }
+            let mut covered = HashSet::new();
+            if *n > 0 {
+                for window in sequence.windows(*n) {
+                    if is_permutation(window) {
+                        covered.insert(lex_rank(window).unwrap());
+                    }
+                }
+            }
+            Outcome::Missing { ranks: (*start..end).filter(|rank| covered.contains(rank) == false).collect() }
+        }
+        Task::Create { algorithm, n } => match algorithm.parse::<Algorithm>() {
+            Ok(algorithm) => match algorithm.handle().try_create_superperm(*n) {
+                Ok(sequence) => Outcome::Created { sequence },
+                Err(err) => Outcome::Failed { reason: err.to_string() },
+            },
+            Err(reason) => Outcome::Failed { reason },
+        },
+    };
+    return JobResult { id: job.id, outcome };
+}
+
+/// Worker loop: reads one JSON Job per line until the end of the input and
+/// writes one JSON JobResult per line for each. A line that isn't a job is
+/// answered with a Failed result under the id 0.
+pub fn run_worker<R: BufRead, W: Write>(r: R, mut w: W) -> io::Result<()> {
+    for line in r.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let result = match serde_json::from_str::<Job>(&line) {
+            Ok(job) => run_job(&job),
+            Err(err) => JobResult { id: 0, outcome: Outcome::Failed { reason: err.to_string() } },
+        };
+        writeln!(w, "{}", serde_json::to_string(&result)?)?;
+        w.flush()?;
+    }
+    return Ok(());
+}
+
+/// Queue of jobs waiting for a worker, keeping track of the jobs handed out
+/// so they can be requeued if their worker goes away
+#[derive(Debug, Default)]
+pub struct Coordinator {
+    next_id: u64,
+    queue: VecDeque<Job>,
+    in_flight: BTreeMap<u64, Job>,
+    results: BTreeMap<u64, JobResult>,
+}
+
+impl Coordinator {
+    pub fn new() -> Coordinator {
+        return Coordinator::default();
+    }
+
+    /// Queues a task, giving the id its result will be reported under. Ids start from 1
+    pub fn submit(&mut self, task: Task) -> u64 {
+        self.next_id += 1;
+        self.queue.push_back(Job { id: self.next_id, task });
+        return self.next_id;
+    }
+
+    /// Queues the checking of a sequence as CoverageChunk tasks of at most
+    /// chunk_size ranks each, giving their ids. Chunks are never larger than
+    /// MAX_CHUNK_RANKS. Panics if n! overflows.
+    pub fn submit_coverage(&mut self, sequence: &[usize], n: usize, chunk_size: usize) -> Vec<u64> {
+        let total = checked_factorial(n).expect("n! overflows a usize");
+        let chunk_size = chunk_size.clamp(1, MAX_CHUNK_RANKS);
+        let mut ids = Vec::new();
+        for start in (0..total).step_by(chunk_size) {
+            let end = (start + chunk_size).min(total);
+            ids.push(self.submit(Task::CoverageChunk { sequence: sequence.to_vec(), n, start, end }));
+        }
+        return ids;
+    }
+
+    /// Hands out the next queued job, if any
+    pub fn next_job(&mut self) -> Option<Job> {
+        let job = self.queue.pop_front()?;
+        self.in_flight.insert(job.id, job.clone());
+        return Some(job);
+    }
+
+    /// Records the result of a job handed out. Results for unknown or already
+    /// completed ids are ignored, giving false.
+    pub fn complete(&mut self, result: JobResult) -> bool {
+        if self.in_flight.remove(&result.id).is_none() {
+            return false;
+        }
+        self.results.insert(result.id, result);
+        return true;
+    }
+
+    /// Puts a job handed out back at the front of the queue, e.g when its
+    /// worker disconnected. Gives false if the job isn't in flight.
+    pub fn requeue(&mut self, id: u64) -> bool {
+        match self.in_flight.remove(&id) {
+            Some(job) => {
+                self.queue.push_front(job);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether every job submitted has a result
+    pub fn is_done(&self) -> bool {
+        return self.queue.is_empty() && self.in_flight.is_empty();
+    }
+
+    /// Results received so far, in order of job id
+    pub fn results(&self) -> impl Iterator<Item = &JobResult> {
+        return self.results.values();
+    }
+
+    /// The shortest sequence amongst the Created results, e.g when the same
+    /// n has been submitted to several algorithms or workers. Equal lengths go
+    /// to the smallest job id, so the pick doesn't depend on the order workers
+    /// finished in and repeated runs select the same result.
+    pub fn best_created(&self) -> Option<&JobResult> {
+        // min_by_key keeps the first of equal keys, and results are in id order
+        return self.results.values()
+            .filter_map(|result| match &result.outcome {
+                Outcome::Created { sequence } => Some((sequence.len(), result)),
+                _ => None,
+            })
+            .min_by_key(|(length, _)| *length)
+            .map(|(_, result)| result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coordinator_and_worker() {
+        let mut coordinator = Coordinator::new();
+        let chunks = coordinator.submit_coverage(&[1,2,3,1,2,1,3,2], 3, 4);
+        assert_eq!(chunks, vec![1, 2]);
+        coordinator.submit(Task::Create { algorithm: String::from("bruteforce"), n: 3 });
+        coordinator.submit(Task::CheckBatch {
+            algorithm: String::from("bruteforce_optimise"),
+            candidates: vec![JobCandidate { id: String::from("a"), n: 2, sequence: vec![1,2,1] }],
+        });
+        coordinator.submit(Task::Create { algorithm: String::from("unknown"), n: 3 });
+
+        // Hand the jobs to a worker as JSON lines, losing the first one once
+        let lost = coordinator.next_job().unwrap();
+        assert!(coordinator.requeue(lost.id));
+        let mut input = String::new();
+        while let Some(job) = coordinator.next_job() {
+            input.push_str(&serde_json::to_string(&job).unwrap());
+            input.push('\n');
+        }
+        let mut output = Vec::new();
+        run_worker(input.as_bytes(), &mut output).unwrap();
+        for line in String::from_utf8(output).unwrap().lines() {
+            assert!(coordinator.complete(serde_json::from_str(line).unwrap()));
+        }
+        assert!(coordinator.is_done());
+
+        let outcomes: Vec<&Outcome> = coordinator.results().map(|result| &result.outcome).collect();
+        // 321 is the one permutation missing
+        assert_eq!(outcomes[0], &Outcome::Missing { ranks: vec![] });
+        assert_eq!(outcomes[1], &Outcome::Missing { ranks: vec![5] });
+        assert_eq!(outcomes[2], &Outcome::Created { sequence: vec![1,2,3,1,2,1,3,2,1] });
+        assert!(matches!(outcomes[3], Outcome::Verdicts { verdicts } if verdicts[0].valid));
+        assert!(matches!(outcomes[4], Outcome::Failed { .. }));
+        assert!(!coordinator.complete(JobResult { id: 1, outcome: Outcome::Missing { ranks: vec![] } }));
+    }
+
+    #[test]
+    fn best_created_ignores_completion_order() {
+        let mut coordinator = Coordinator::new();
+        let mut jobs = Vec::new();
+        for algorithm in ["bruteforce", "bruteforce_optimise", "bruteforce"] {
+            coordinator.submit(Task::Create { algorithm: String::from(algorithm), n: 4 });
+            jobs.push(coordinator.next_job().unwrap());
+        }
+        // Finish the jobs last to first, all giving sequences of equal length
+        for job in jobs.iter().rev() {
+            coordinator.complete(run_job(job));
+        }
+        assert_eq!(coordinator.best_created().unwrap().id, 1);
+        assert_eq!(Coordinator::new().best_created(), None);
+    }
+
+    #[test]
+    fn hostile_jobs_fail() {
+        let run = |task: Task| run_job(&Job { id: 7, task }).outcome;
+        let failed = |outcome: &Outcome| matches!(outcome, Outcome::Failed { reason } if reason.contains("too large"));
+        assert!(failed(&run(Task::Create { algorithm: String::from("bruteforce"), n: 30 })));
+        assert!(failed(&run(Task::CoverageChunk { sequence: vec![1], n: 30, start: 0, end: 1 })));
+        assert!(failed(&run(Task::CheckBatch {
+            algorithm: String::from("bruteforce"),
+            candidates: vec![JobCandidate { id: String::from("big"), n: 30, sequence: vec![1,2,3] }],
+        })));
+        let outcome = run(Task::CoverageChunk { sequence: vec![1], n: 20, start: 0, end: usize::MAX });
+        assert!(matches!(outcome, Outcome::Failed { reason } if reason.contains("over the limit")));
+        assert!(failed(&run(Task::Create { algorithm: String::from("bruteforce"), n: 13 })));
+        // Ranks past n! are never reported missing
+        let outcome = run(Task::CoverageChunk { sequence: vec![1,2,1], n: 2, start: 1, end: usize::MAX });
+        assert_eq!(outcome, Outcome::Missing { ranks: vec![] });
+    }
+}
@@ -0,0 +1,61 @@
+// Embedded database of the best known superpermutations for small n.
+//
+// Lengths for n <= 5 are proven minimal (1, 3, 9, 33, 153). The sequences
+// stored are those produced by the standard recursive construction. Best known
+// sequences for n >= 6 (e.g Houston's 872 for n=6) aren't included.
+use crate::fixtures;
+use crate::records;
+use crate::superperm::SuperPerm;
+
+/// Returns the best known superpermutation of the tokens (1,2,3, ...,n_tokens)
+/// or None if this crate doesn't know of one. For n=0 it's the empty
+/// sequence, matching records::minimal_length(0).
+pub fn best(n_tokens: usize) -> Option<SuperPerm> {
+    if n_tokens == 0 {
+        return Some(SuperPerm::new(Vec::new(), 0));
+    }
+    // Only the proven minimal sequences are known so far, which are shared
+    // with the fixtures
+    return fixtures::minimal(n_tokens);
+}
+
+/// Lengths of the sequences returned by best for n >= 1, indexed by n-1.
+/// The same as the proven minimal lengths in records
+pub const BEST_LENGTHS: [usize; 5] = records::MINIMAL_LENGTHS;
+
+// The minimal superpermutations for n <= 4 as compile time tables, the same
+// sequences best returns without parsing or allocating anything
+
+/// Minimal superpermutation of 1 token
+pub const MINIMAL_1: [usize; 1] = [1];
+/// Minimal superpermutation of the tokens (1,2)
+pub const MINIMAL_2: [usize; 3] = [1,2,1];
+/// Minimal superpermutation of the tokens (1,2,3)
+pub const MINIMAL_3: [usize; 9] = [1,2,3,1,2,1,3,2,1];
+/// Minimal superpermutation of the tokens (1,2,3,4)
+pub const MINIMAL_4: [usize; 33] = [
+    1,2,3,4,1,2,3,1,4,2,3,1,2,4,3,1,2,1,3,4,2,1,3,2,4,1,3,2,1,4,3,2,1,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::SuperPermHandling;
+    use crate::bruteforce_optimise;
+
+    #[test]
+    fn best_sequences_are_valid() {
+        for n in 1..6 {
+            let superperm = best(n).unwrap();
+            assert_eq!(superperm.sequence.len(), BEST_LENGTHS[n - 1]);
+            assert_eq!(Some(superperm.sequence.len()), crate::records::minimal_length(n));
+            assert!(bruteforce_optimise::Handle.check_superperm(&superperm.sequence, n));
+        }
+        let tables: [&[usize]; 4] = [&MINIMAL_1, &MINIMAL_2, &MINIMAL_3, &MINIMAL_4];
+        for (i, table) in tables.iter().enumerate() {
+            assert_eq!(best(i + 1).unwrap().sequence, table.to_vec());
+        }
+        assert_eq!(best(0).map(|superperm| superperm.sequence.len()), records::minimal_length(0));
+        assert_eq!(best(6), None);
+    }
+}
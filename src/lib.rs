@@ -1,3 +1,63 @@
+// Emits a tracing event with the `tracing` feature and compiles to nothing
+// without it, so instrumented hot loops cost nothing by default
+macro_rules! trace_event {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::trace!($($arg)*);
+    };
+}
+
+pub mod analysis;
+#[cfg(feature = "async")]
+pub mod async_api;
 pub mod base;
+pub mod batch;
+#[cfg(feature = "heuristics")]
+pub mod bidirectional;
+pub mod budget;
 pub mod bruteforce;
-pub mod bruteforce_optimise;
\ No newline at end of file
+pub mod bruteforce_optimise;
+#[cfg(feature = "cache")]
+pub mod cache;
+pub mod checkpoint;
+#[cfg(feature = "heuristics")]
+pub mod cross_entropy;
+pub mod de_bruijn;
+pub mod experiment;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fixtures;
+#[cfg(feature = "heatmap")]
+pub mod heatmap;
+pub mod io;
+pub mod jobs;
+#[cfg(feature = "known")]
+pub mod known;
+#[cfg(feature = "heuristics")]
+pub mod local_search;
+pub mod matrix;
+pub mod metrics;
+pub mod mixed_radix;
+pub mod multiset;
+pub mod permutation;
+#[cfg(feature = "heuristics")]
+pub mod policy;
+pub mod pool;
+pub mod progress;
+pub mod records;
+pub mod recursive;
+pub mod report;
+pub mod score;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod superperm;
+pub mod universal_cycle;
+pub mod verify;
+#[cfg(feature = "wasm-bindgen")]
+pub mod wasm;
+#[cfg(feature = "heuristics")]
+pub mod weighted_greedy;
+
+pub use mixed_radix::{GrayDelta, GrayIter, MixedRadix, MixedRadixError, MixedRadixIter, MixedRadixRepr};
+#[cfg(feature = "bigint")]
+pub use mixed_radix::BigMixedRadix;
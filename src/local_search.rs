@@ -0,0 +1,299 @@
+// Improvers working on the visit order of a superpermutation: the order its
+// permutations are first visited in. Any order of all n! permutations gives
+// a superpermutation by overlapping each permutation with the last as much as
+// possible, so improving the sequence becomes improving the order, with the
+// cost of each step taken from matrix::overlap_cost_matrix.
+//
+// Permutations are identified by their lexicographic rank throughout. The
+// cost matrix has (n!)² entries, so these are only practical up to n=6.
+use rand::Rng;
+
+use crate::analysis::covered_in_order;
+use crate::matrix::overlap_cost_matrix;
+use crate::permutation::{checked_factorial, lex_rank, lex_unrank, metrics};
+
+/// Costs of visiting each permutation straight after each other one
+pub(crate) struct Costs {
+    n: usize,
+    size: usize,
+    matrix: Vec<u8>,
+}
+
+impl Costs {
+    pub(crate) fn new(n: usize) -> Costs {
+        return Costs { n, size: checked_factorial(n).expect("n! overflows"), matrix: overlap_cost_matrix(n) };
+    }
+
+    /// Tokens appended to visit the permutation ranked to straight after from
+    pub(crate) fn cost(&self, from: usize, to: usize) -> usize {
+        return self.matrix[from * self.size + to] as usize;
+    }
+
+    /// Length of the sequence given by a visit order
+    pub(crate) fn length(&self, order: &[usize]) -> usize {
+        if order.is_empty() {
+            return 0;
+        }
+        return self.n + order.windows(2).map(|pair| self.cost(pair[0], pair[1])).sum::<usize>();
+    }
+
+    /// Visit order built greedily from a random start, the cheapest next
+    /// permutation being visited each step with ties broken at random
+    pub(crate) fn random_greedy<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<usize> {
+        let mut visited = vec![false; self.size];
+        let mut current = rng.gen_range(0..self.size);
+        visited[current] = true;
+        let mut order = vec![current];
+        let mut ties = Vec::new();
+        while order.len() < self.size {
+            let mut cheapest = usize::MAX;
+            ties.clear();
+            for next in (0..self.size).filter(|next| visited[*next] == false) {
+                let cost = self.cost(current, next);
+                if cost < cheapest {
+                    cheapest = cost;
+                    ties.clear();
+                }
+                if cost == cheapest {
+                    ties.push(next);
+                }
+            }
+            current = ties[rng.gen_range(0..ties.len())];
+            visited[current] = true;
+            order.push(current);
+        }
+        return order;
+    }
+}
+
+/// Lexicographic ranks of the permutations of (1,2,3, ...,n) in the order the
+/// sequence first visits them, followed by those it misses in order of rank,
+/// so it's always an order of all n! permutations.
+/// Panics if n! overflows.
+pub fn visit_order(sequence: &[usize], n: usize) -> Vec<usize> {
+    let mut order: Vec<usize> = covered_in_order(sequence, n).map(|(_, perm)| lex_rank(perm).unwrap()).collect();
+    let mut covered = vec![false; checked_factorial(n).expect("n! overflows")];
+    for rank in &order {
+        covered[*rank] = true;
+    }
+    order.extend((0..covered.len()).filter(|rank| covered[*rank] == false));
+    return order;
+}
+
+/// The shortest sequence visiting the permutations of (1,2,3, ...,n) in the
+/// order given by lexicographic rank. E.g [0, 3] for n=3 gives 1231
+pub fn order_to_sequence(order: &[usize], n: usize) -> Vec<usize> {
+    let tokens: Vec<usize> = (1..n + 1).collect();
+    let mut sequence = Vec::new();
+    let mut previous: Option<Vec<usize>> = None;
+    for rank in order {
+        let perm = lex_unrank(*rank, &tokens).expect("rank out of range");
+        match &previous {
+            Some(previous) => sequence.extend_from_slice(&perm[n - metrics::overlap(previous, &perm)..]),
+            None => sequence.extend_from_slice(&perm),
+        }
+        previous = Some(perm);
+    }
+    return sequence;
+}
+
+/// Hill climbing over visit orders. Each step makes the best single move,
+/// taking one permutation out of the order and putting it back elsewhere,
+/// until no move shortens the sequence. Then it restarts from a random
+/// greedy solution, until the restart budget runs out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HillClimber {
+    /// Amount of random greedy solutions to climb from after the first
+    pub restarts: usize,
+}
+
+impl HillClimber {
+    pub fn new(restarts: usize) -> HillClimber {
+        return HillClimber { restarts };
+    }
+
+    /// Climbs from the visit order of the sequence given and from each
+    /// restart, returning the shortest superpermutation of (1,2,3, ...,n)
+    /// reached. This is never longer than the visit order of the sequence
+    /// given, and is valid even if the sequence given isn't.
+    pub fn improve<R: Rng + ?Sized>(&self, sequence: &[usize], n: usize, rng: &mut R) -> Vec<usize> {
+        if n == 0 {
+            return Vec::new();
+        }
+        let costs = Costs::new(n);
+        let mut best = climb(&costs, visit_order(sequence, n));
+        for _ in 0..self.restarts {
+            let order = climb(&costs, costs.random_greedy(rng));
+            if costs.length(&order) < costs.length(&best) {
+                best = order;
+            }
+        }
+        return order_to_sequence(&best, n);
+    }
+}
+
+/// Makes the best relocation move until none improves the order
+fn climb(costs: &Costs, mut order: Vec<usize>) -> Vec<usize> {
+    let size = order.len();
+    // Cost between positions a and b, where None is off either end
+    let cost = |order: &[usize], a: Option<usize>, b: Option<usize>| match (a, b) {
+        (Some(a), Some(b)) => costs.cost(order[a], order[b]) as isize,
+        _ => 0,
+    };
+    loop {
+        let mut best_move = None;
+        let mut best_delta = 0;
+        for i in 0..size {
+            let before = i.checked_sub(1);
+            let after = Some(i + 1).filter(|after| *after < size);
+            let removal = cost(&order, before, after) - cost(&order, before, Some(i)) - cost(&order, Some(i), after);
+            // Insert between positions gap - 1 and gap of the original order
+            for gap in 0..size + 1 {
+                if gap == i || gap == i + 1 {
+                    continue;
+                }
+                let left = gap.checked_sub(1);
+                let right = Some(gap).filter(|right| *right < size);
+                let insertion = cost(&order, left, Some(i)) + cost(&order, Some(i), right) - cost(&order, left, right);
+                if removal + insertion < best_delta {
+                    best_delta = removal + insertion;
+                    best_move = Some((i, gap));
+                }
+            }
+        }
+        let Some((i, gap)) = best_move else {
+            return order;
+        };
+        let rank = order.remove(i);
+        order.insert(if gap > i { gap - 1 } else { gap }, rank);
+    }
+}
+
+/// Large neighbourhood search (destroy and repair) over visit orders. Each
+/// iteration removes a random contiguous block of the order and inserts its
+/// permutations back one at a time by regret: the permutation losing the most
+/// if not put in its cheapest place now goes first. The repaired order is kept
+/// if it's shorter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LargeNeighbourhood {
+    /// Amount of destroy and repair iterations
+    pub iterations: usize,
+    /// Most permutations removed at once
+    pub max_block: usize,
+}
+
+impl LargeNeighbourhood {
+    pub fn new(iterations: usize, max_block: usize) -> LargeNeighbourhood {
+        return LargeNeighbourhood { iterations, max_block };
+    }
+
+    /// Searches from the visit order of the sequence given, returning the
+    /// shortest superpermutation of (1,2,3, ...,n) reached. This is never
+    /// longer than the visit order of the sequence given, and is valid even if
+    /// the sequence given isn't.
+    pub fn improve<R: Rng + ?Sized>(&self, sequence: &[usize], n: usize, rng: &mut R) -> Vec<usize> {
+        if n == 0 {
+            return Vec::new();
+        }
+        let costs = Costs::new(n);
+        let mut best = visit_order(sequence, n);
+        let mut best_length = costs.length(&best);
+        let max_block = self.max_block.clamp(1, best.len());
+        for _ in 0..self.iterations {
+            let block = rng.gen_range(1..max_block + 1);
+            let start = rng.gen_range(0..best.len() + 1 - block);
+            let mut order = best.clone();
+            let removed: Vec<usize> = order.drain(start..start + block).collect();
+            repair(&costs, &mut order, removed);
+            let length = costs.length(&order);
+            if length < best_length {
+                best = order;
+                best_length = length;
+            }
+        }
+        return order_to_sequence(&best, n);
+    }
+}
+
+/// Cheapest and second cheapest cost of inserting the permutation ranked rank
+/// into the order, along with the position of the cheapest
+fn insertion_costs(costs: &Costs, order: &[usize], rank: usize) -> (usize, isize, isize) {
+    let (mut best, mut second) = ((0, isize::MAX), isize::MAX);
+    for gap in 0..order.len() + 1 {
+        let left = gap.checked_sub(1).map(|left| order[left]);
+        let right = order.get(gap).copied();
+        let cost = match (left, right) {
+            (Some(left), Some(right)) => {
+                (costs.cost(left, rank) + costs.cost(rank, right)) as isize - costs.cost(left, right) as isize
+            }
+            (Some(left), None) => costs.cost(left, rank) as isize,
+            (None, Some(right)) => costs.cost(rank, right) as isize,
+            (None, None) => 0,
+        };
+        if cost < best.1 {
+            second = best.1;
+            best = (gap, cost);
+        } else if cost < second {
+            second = cost;
+        }
+    }
+    return (best.0, best.1, second);
+}
+
+/// Regret insertion of the removed permutations back into the order
+fn repair(costs: &Costs, order: &mut Vec<usize>, mut removed: Vec<usize>) {
+    while removed.is_empty() == false {
+        // Ties go to the first removed, keeping runs reproducible
+        let mut chosen = (0, 0, isize::MIN);
+        for (i, rank) in removed.iter().enumerate() {
+            let (gap, best, second) = insertion_costs(costs, order, *rank);
+            let regret = second.saturating_sub(best);
+            if regret > chosen.2 {
+                chosen = (i, gap, regret);
+            }
+        }
+        let rank = removed.remove(chosen.0);
+        order.insert(chosen.1, rank);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::SuperPermHandling;
+    use crate::bruteforce_optimise;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn hill_climbing() {
+        assert_eq!(order_to_sequence(&[0, 3], 3), vec![1,2,3,1]);
+        let superperm = bruteforce_optimise::Handle.create_superperm(4);
+        assert_eq!(order_to_sequence(&visit_order(&superperm, 4), 4), superperm);
+
+        let mut rng = StdRng::seed_from_u64(3);
+        // Every permutation written out in full, 96 tokens with no overlaps
+        let concatenated: Vec<usize> = (0..24).flat_map(|rank| lex_unrank(rank, &[1,2,3,4]).unwrap()).collect();
+        for restarts in [0, 3] {
+            let improved = HillClimber::new(restarts).improve(&concatenated, 4, &mut rng);
+            assert!(bruteforce_optimise::Handle.check_superperm(&improved, 4));
+            assert!(improved.len() < concatenated.len());
+        }
+        // Starting from nothing still gives a valid sequence
+        let improved = HillClimber::new(2).improve(&[], 3, &mut rng);
+        assert_eq!(improved.len(), 9);
+    }
+
+    #[test]
+    fn large_neighbourhood_search() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let concatenated: Vec<usize> = (0..24).flat_map(|rank| lex_unrank(rank, &[1,2,3,4]).unwrap()).collect();
+        let improved = LargeNeighbourhood::new(200, 6).improve(&concatenated, 4, &mut rng);
+        assert!(bruteforce_optimise::Handle.check_superperm(&improved, 4));
+        assert!(improved.len() < concatenated.len());
+        // Already minimal sequences can't be improved on
+        let superperm = bruteforce_optimise::Handle.create_superperm(4);
+        assert_eq!(LargeNeighbourhood::new(50, 4).improve(&superperm, 4, &mut rng), superperm);
+        assert_eq!(LargeNeighbourhood::new(10, 100).improve(&[], 3, &mut rng).len(), 9);
+    }
+}
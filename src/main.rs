@@ -0,0 +1,472 @@
+// Command line interface for creating, checking and converting superpermutations
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use clap::{Parser, Subcommand, ValueEnum};
+
+use super_permutations_attempt::analysis;
+use super_permutations_attempt::base::{check_create_n, check_n_supported, Algorithm, CheckMode};
+use super_permutations_attempt::budget::{self, MemoryBudget};
+use super_permutations_attempt::fixtures;
+use super_permutations_attempt::checkpoint::Checkpoint;
+#[cfg(feature = "cache")]
+use super_permutations_attempt::cache::{CacheKey, ResultCache};
+use super_permutations_attempt::io::{self, Format};
+use super_permutations_attempt::metrics::Metrics;
+use super_permutations_attempt::progress::ProgressEvent;
+use super_permutations_attempt::report::{RunReport, Stats};
+#[cfg(feature = "websocket")]
+use super_permutations_attempt::progress::websocket::ProgressServer;
+#[cfg(feature = "server")]
+use super_permutations_attempt::server;
+use super_permutations_attempt::superperm::SuperPerm;
+use super_permutations_attempt::batch::{self, BatchFormat};
+use super_permutations_attempt::bruteforce_optimise;
+
+#[derive(Parser)]
+#[command(version, about = "Create and check superpermutations")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create a superpermutation of the tokens 1..=n
+    Create {
+        n: usize,
+        /// Algorithm to use. An unknown name lists the algorithms available
+        #[arg(short, long, default_value_t = Algorithm::BruteforceOptimise)]
+        algorithm: Algorithm,
+        /// Format to write the sequence in (digits, delimited, binary, community, share)
+        #[arg(short, long, default_value = "digits")]
+        format: Format,
+        /// File to write to instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Write a JSON run report (timings, stats, sequence) instead of the sequence
+        #[arg(long)]
+        json: bool,
+        /// Write a Markdown or HTML report (stats, bounds, coverage) instead of the sequence
+        #[arg(long, value_enum)]
+        report: Option<ReportFormat>,
+        /// Refuse to start if the run is estimated to need more than this many MiB
+        #[arg(long)]
+        memory_limit: Option<usize>,
+        /// Where to save a checkpoint if bruteforce_optimise is interrupted with Ctrl-C.
+        /// The partial sequence is written to the output as usual
+        #[arg(long, default_value = "superperm.checkpoint.json")]
+        checkpoint: PathBuf,
+        /// Stream progress as JSON over a WebSocket listening on this address.
+        /// Only bruteforce_optimise reports progress
+        #[cfg(feature = "websocket")]
+        #[arg(long)]
+        progress_addr: Option<String>,
+    },
+    /// Check whether a sequence is a superpermutation
+    Check {
+        /// File containing the sequence, or "-" for stdin
+        input: PathBuf,
+        /// Amount of unique tokens. Defaults to the largest token in the sequence
+        #[arg(short, long)]
+        n: Option<usize>,
+        /// Algorithm to use. An unknown name lists the algorithms available
+        #[arg(short, long, default_value_t = Algorithm::BruteforceOptimise)]
+        algorithm: Algorithm,
+        /// Format the sequence is written in (digits, delimited, binary, community, share)
+        #[arg(short, long, default_value = "digits")]
+        format: Format,
+        /// Fail on tokens outside 1..=n and on tokens before the first or after the
+        /// last permutation, instead of tolerating them
+        #[arg(long)]
+        strict: bool,
+    },
+    /// Check many candidate sequences listed in a CSV or JSONL file
+    CheckBatch {
+        /// File listing the candidates, or "-" for stdin
+        input: PathBuf,
+        /// Format of the input (csv, jsonl). Guessed from the file extension if not given
+        #[arg(long)]
+        input_format: Option<BatchFormat>,
+        /// Format to write the verdicts in (csv, jsonl)
+        #[arg(long, default_value = "csv")]
+        output_format: BatchFormat,
+        /// File to write the verdicts to instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Algorithm to use. An unknown name lists the algorithms available
+        #[arg(short, long, default_value_t = Algorithm::BruteforceOptimise)]
+        algorithm: Algorithm,
+    },
+    /// Report statistics (coverage, duplicates, waste) of a sequence
+    Analyze {
+        /// File containing the sequence, or "-" for stdin
+        input: PathBuf,
+        /// Amount of unique tokens. Defaults to the largest token in the sequence
+        #[arg(short, long)]
+        n: Option<usize>,
+        /// Format the sequence is written in (digits, delimited, binary, community, share)
+        #[arg(short, long, default_value = "digits")]
+        format: Format,
+        /// Write the statistics as CSV
+        #[arg(long)]
+        csv: bool,
+        /// Write a Markdown or HTML report (stats, bounds, coverage) instead of the summary
+        #[arg(long, value_enum)]
+        report: Option<ReportFormat>,
+    },
+    /// Create superpermutations over a range of n with each algorithm and report on them
+    Sweep {
+        /// Smallest n to create a superpermutation for
+        #[arg(long, default_value_t = 1)]
+        from: usize,
+        /// Largest n to create a superpermutation for
+        #[arg(long, default_value_t = 5)]
+        to: usize,
+        /// Algorithms to run. Defaults to all of them
+        #[arg(short, long)]
+        algorithm: Vec<Algorithm>,
+        /// Write the results as CSV
+        #[arg(long)]
+        csv: bool,
+        /// Cache directory to reuse results from and store new results in
+        #[cfg(feature = "cache")]
+        #[arg(long)]
+        cache: Option<PathBuf>,
+    },
+    /// Serve POST /check and POST /create as an HTTP JSON service
+    #[cfg(feature = "server")]
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: String,
+    },
+    /// Convert a sequence between formats
+    Convert {
+        /// File containing the sequence, or "-" for stdin
+        input: PathBuf,
+        /// Format the input is written in (digits, delimited, binary, community, share)
+        #[arg(long)]
+        from: Format,
+        /// Format to write the output in (digits, delimited, binary, community, share)
+        #[arg(long)]
+        to: Format,
+        /// File to write to instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Check every algorithm against the embedded fixtures of known valid and invalid sequences
+    Selftest,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+/// Width of the coverage strip included in Markdown and HTML reports
+const REPORT_COVERAGE_WIDTH: usize = 64;
+
+impl ReportFormat {
+    fn render(&self, report: &RunReport) -> String {
+        match self {
+            ReportFormat::Markdown => report.render_markdown(Some(REPORT_COVERAGE_WIDTH)),
+            ReportFormat::Html => report.render_html(Some(REPORT_COVERAGE_WIDTH)),
+        }
+    }
+}
+
+/// Reads a sequence from a file, or from stdin if the path is "-"
+fn read_input(input: &PathBuf, format: Format) -> Result<SuperPerm, io::Error> {
+    if input.as_os_str() == "-" {
+        return io::read_superperm(std::io::stdin().lock(), format);
+    }
+    return io::read_file(input, format);
+}
+
+/// Writes a sequence to a file, or to stdout if no path is given
+fn write_output(output: &Option<PathBuf>, superperm: &SuperPerm, format: Format) -> Result<(), io::Error> {
+    match output {
+        Some(path) => io::write_file(path, superperm, format),
+        None => io::write_superperm(std::io::stdout().lock(), superperm, format),
+    }
+}
+
+/// Creates with bruteforce_optimise, stopping at the first Ctrl-C to write
+/// out the sequence so far and a checkpoint instead of losing everything. A
+/// second Ctrl-C exits straight away.
+fn create_interruptible(n: usize, output: &Option<PathBuf>, format: Format, checkpoint: &PathBuf) -> Result<ExitCode, io::Error> {
+    let cancel = Arc::new(AtomicBool::new(false));
+    let handler_cancel = Arc::clone(&cancel);
+    let handler = ctrlc::set_handler(move || {
+        if handler_cancel.swap(true, Ordering::Relaxed) {
+            std::process::exit(130);
+        }
+    });
+    if let Err(err) = handler {
+        eprintln!("warning: Ctrl-C won't save progress: {}", err);
+    }
+
+    let sequence = bruteforce_optimise::Handle.create_superperm_cancellable(n, usize::MAX, &mut |_: &ProgressEvent| {}, &cancel);
+    write_output(output, &SuperPerm::new(sequence.clone(), n), format)?;
+    if cancel.load(Ordering::Relaxed) == false {
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let stats = analysis::SuperPermStats::compute(&sequence, n);
+    eprintln!("interrupted, partial sequence of length {} covers {} permutations", stats.length, stats.distinct_perms);
+    match Checkpoint::from_partial("bruteforce_optimise", n, &sequence).save(checkpoint) {
+        Ok(()) => eprintln!("checkpoint saved to {}", checkpoint.display()),
+        Err(err) => eprintln!("error: couldn't save checkpoint: {}", err),
+    }
+    return Ok(ExitCode::from(130));
+}
+
+fn run(cli: Cli) -> Result<ExitCode, io::Error> {
+    match cli.command {
+        Command::Create {
+            n,
+            algorithm,
+            format,
+            output,
+            json,
+            report,
+            memory_limit,
+            checkpoint,
+            #[cfg(feature = "websocket")]
+            progress_addr,
+        } => {
+            if let Err(err) = check_create_n(algorithm.handle().as_ref(), n) {
+                eprintln!("error: {}", err);
+                return Ok(ExitCode::from(2));
+            }
+            if let Some(limit) = memory_limit {
+                let budget = MemoryBudget::new(limit.saturating_mul(1 << 20));
+                if let Err(err) = budget.check(budget::create_estimate(algorithm, n)) {
+                    eprintln!("error: n={} with {}: {}", n, algorithm.name(), err);
+                    return Ok(ExitCode::from(2));
+                }
+            }
+            #[cfg(feature = "websocket")]
+            if let Some(addr) = progress_addr {
+                let mut server = ProgressServer::bind(addr)?;
+                eprintln!("streaming progress on ws://{}", server.local_addr());
+                let sequence = bruteforce_optimise::Handle.create_superperm_with_progress(n, 1000, &mut server);
+                write_output(&output, &SuperPerm::new(sequence, n), format)?;
+                return Ok(ExitCode::SUCCESS);
+            }
+            if let Some(report_format) = report {
+                let report = RunReport::run(algorithm.name(), algorithm.handle().as_ref(), n);
+                match output {
+                    Some(path) => std::fs::write(path, report_format.render(&report))?,
+                    None => print!("{}", report_format.render(&report)),
+                }
+                return Ok(ExitCode::SUCCESS);
+            }
+            if json {
+                let report = RunReport::run(algorithm.name(), algorithm.handle().as_ref(), n);
+                match output {
+                    Some(path) => std::fs::write(path, report.to_json() + "\n")?,
+                    None => println!("{}", report.to_json()),
+                }
+                return Ok(ExitCode::SUCCESS);
+            }
+            if let Algorithm::BruteforceOptimise = algorithm {
+                return create_interruptible(n, &output, format, &checkpoint);
+            }
+            let sequence = algorithm.handle().create_superperm(n);
+            write_output(&output, &SuperPerm::new(sequence, n), format)?;
+        }
+        Command::Check { input, n, algorithm, format, strict } => {
+            let superperm = read_input(&input, format)?;
+            let n = n.unwrap_or(superperm.n_tokens);
+            if let Err(err) = check_n_supported(n) {
+                eprintln!("error: {}", err);
+                return Ok(ExitCode::from(2));
+            }
+            let mode = if strict { CheckMode::Strict } else { CheckMode::Lenient };
+            let valid = match algorithm.handle().check_superperm_with(&superperm.sequence, n, mode) {
+                Ok(valid) => valid,
+                Err(err) => {
+                    println!("not a superpermutation for n={}: {}", n, err);
+                    return Ok(ExitCode::FAILURE);
+                }
+            };
+            if valid {
+                println!("valid superpermutation of length {} for n={}", superperm.sequence.len(), n);
+            } else {
+                println!("not a superpermutation for n={}", n);
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Command::CheckBatch { input, input_format, output_format, output, algorithm } => {
+            let input_format = input_format.unwrap_or(
+                if input.extension().is_some_and(|ext| ext == "jsonl") { BatchFormat::Jsonl } else { BatchFormat::Csv }
+            );
+            let candidates = if input.as_os_str() == "-" {
+                batch::read_candidates(std::io::stdin().lock(), input_format)
+            } else {
+                batch::read_candidates(BufReader::new(io::open_file(&input)?), input_format)
+            };
+            let candidates = match candidates {
+                Ok(candidates) => candidates,
+                Err(err) => {
+                    eprintln!("error: {}", err);
+                    return Ok(ExitCode::from(2));
+                }
+            };
+            let handle = algorithm.handle();
+            let verdicts = batch::check_batch(&candidates, handle.as_ref());
+            match output {
+                Some(path) => batch::write_verdicts(BufWriter::new(File::create(path)?), &verdicts, output_format)?,
+                None => batch::write_verdicts(std::io::stdout().lock(), &verdicts, output_format)?,
+            }
+            let valid = verdicts.iter().filter(|v| v.valid).count();
+            eprintln!("{}/{} candidates are valid superpermutations", valid, verdicts.len());
+        }
+        Command::Analyze { input, n, format, csv, report: report_format } => {
+            let superperm = read_input(&input, format)?;
+            let n = n.unwrap_or(superperm.n_tokens);
+            if let Err(err) = check_n_supported(n) {
+                eprintln!("error: {}", err);
+                return Ok(ExitCode::from(2));
+            }
+            let handle = Algorithm::BruteforceOptimise.handle();
+            let report = RunReport::analyze("input", handle.as_ref(), superperm.sequence, n);
+            if let Some(report_format) = report_format {
+                print!("{}", report_format.render(&report));
+            } else if csv {
+                Stats::write_csv(std::io::stdout(), &[report])?;
+            } else {
+                println!("{}", report);
+                if let Some(bounds) = analysis::bounds_report(n, report.length) {
+                    println!("lower bound:      {}", bounds.lower_bound);
+                    println!("best known:       {}", bounds.best_known);
+                    println!("recursive length: {}", bounds.recursive_length);
+                    println!("length class:     {:?}", bounds.class);
+                    if report.valid == false {
+                        let explanation = analysis::explain(&report.sequence, n, bounds.best_known);
+                        println!("{}", explanation);
+                    }
+                }
+            }
+        }
+        Command::Sweep {
+            from,
+            to,
+            algorithm,
+            csv,
+            #[cfg(feature = "cache")]
+            cache,
+        } => {
+            let algorithms = if algorithm.is_empty() {
+                Algorithm::ALL.to_vec()
+            } else {
+                algorithm
+            };
+            // The largest n decides whether every run is supported
+            for algorithm in &algorithms {
+                if let Err(err) = check_create_n(algorithm.handle().as_ref(), to) {
+                    eprintln!("error: {}: {}", algorithm.name(), err);
+                    return Ok(ExitCode::from(2));
+                }
+            }
+            #[cfg(feature = "cache")]
+            let cache = match cache.map(ResultCache::open).transpose() {
+                Ok(cache) => cache,
+                Err(err) => {
+                    eprintln!("error: {}", err);
+                    return Ok(ExitCode::from(2));
+                }
+            };
+            let mut reports = Vec::new();
+            for n in from..=to {
+                for algorithm in &algorithms {
+                    let run = || RunReport::run(algorithm.name(), algorithm.handle().as_ref(), n);
+                    #[cfg(feature = "cache")]
+                    let report = match &cache {
+                        Some(cache) => match cache.get_or_run(&CacheKey::new(algorithm.name(), n, 0, 0), run) {
+                            Ok(report) => report,
+                            Err(err) => {
+                                eprintln!("error: {}", err);
+                                return Ok(ExitCode::from(2));
+                            }
+                        },
+                        None => run(),
+                    };
+                    #[cfg(not(feature = "cache"))]
+                    let report = run();
+                    if !csv {
+                        println!(
+                            "{:<20} n={:<3} length={:<8} valid={:<5} {:.6}s",
+                            report.algorithm, report.n, report.length, report.valid,
+                            report.timings.creation_secs,
+                        );
+                    }
+                    reports.push(report);
+                }
+            }
+            if csv {
+                Stats::write_csv(std::io::stdout(), &reports)?;
+            }
+        }
+        #[cfg(feature = "server")]
+        Command::Serve { addr } => {
+            eprintln!("listening on {}", addr);
+            server::serve(addr)?;
+        }
+        Command::Convert { input, from, to, output } => {
+            let superperm = read_input(&input, from)?;
+            write_output(&output, &superperm, to)?;
+        }
+        Command::Selftest => {
+            let total = fixtures::fixtures().len();
+            let mut passed = true;
+            for algorithm in Algorithm::ALL {
+                let failures = fixtures::verify_against_fixtures(algorithm.handle().as_ref());
+                println!("{:<20} {}/{} fixtures passed", algorithm.name(), total - failures.len(), total);
+                for fixture in &failures {
+                    println!("  failed {} (expected valid={})", fixture.name, fixture.valid);
+                }
+                passed &= failures.is_empty();
+            }
+            if passed == false {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+    }
+    return Ok(ExitCode::SUCCESS);
+}
+
+/// Prints the throughput counters to stderr, if anything was counted
+fn print_metrics() {
+    let metrics = Metrics::snapshot();
+    if metrics.is_empty() {
+        return;
+    }
+    eprintln!(
+        "metrics: {:.3}s, {} windows checked ({:.0}/s), {} perms covered ({:.0}/s), {} nodes expanded ({:.0}/s)",
+        metrics.elapsed_secs,
+        metrics.windows_checked, metrics.windows_per_sec(),
+        metrics.perms_covered, metrics.perms_per_sec(),
+        metrics.nodes_expanded, metrics.nodes_per_sec(),
+    );
+}
+
+fn main() -> ExitCode {
+    Metrics::start();
+    let code = match run(Cli::parse()) {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::from(2)
+        }
+    };
+    print_metrics();
+    return code;
+}
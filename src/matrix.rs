@@ -0,0 +1,139 @@
+// Export of the costs of moving between permutations, for analysing the
+// structure of the problem in external numerical tools. Permutations are
+// indexed by their lexicographic rank throughout.
+//
+// The cost of visiting b straight after a is permutation::metrics::overlap,
+// the amount of tokens that need appending after a for b to appear.
+use std::io::{self, Write};
+
+use crate::analysis::first_visits;
+use crate::permutation::{checked_factorial, lex_rank, lex_unrank, metrics};
+
+/// Dense n!×n! matrix of overlap costs in row-major order, where element
+/// (i, j) is the cost of visiting the permutation of rank j straight after
+/// the one of rank i. Costs are at most n so are stored as bytes, but the
+/// matrix still has (n!)² elements, so this is only practical up to n=7.
+///
+/// Panics if n is above 255 or n! doesn't fit in a usize.
+pub fn overlap_cost_matrix(n: usize) -> Vec<u8> {
+    assert!(n <= u8::MAX as usize, "costs don't fit in a byte");
+    let tokens: Vec<usize> = (1..n + 1).collect();
+    let size = total_perms(n);
+    let perms: Vec<Vec<usize>> = (0..size).map(|rank| lex_unrank(rank, &tokens).unwrap()).collect();
+    let mut matrix = Vec::with_capacity(size * size);
+    for a in &perms {
+        for b in &perms {
+            matrix.push(metrics::overlap(a, b) as u8);
+        }
+    }
+    return matrix;
+}
+
+/// Sparse form of overlap_cost_matrix holding only the (from, to, cost)
+/// entries with a cost from 1 up to max_cost. Each permutation has at most
+/// k! successors of cost k, so for small max_cost this stays small for any n.
+///
+/// E.g with max_cost 1 each permutation has the one entry to its rotation
+pub fn sparse_overlap_costs(n: usize, max_cost: usize) -> Vec<(usize, usize, usize)> {
+    let tokens: Vec<usize> = (1..n + 1).collect();
+    let mut entries = Vec::new();
+    for from in 0..total_perms(n) {
+        let a = lex_unrank(from, &tokens).unwrap();
+        // Successors of cost k keep the last n-k tokens and rearrange the rest
+        let mut successors = Vec::new();
+        for cost in 1..max_cost.min(n) + 1 {
+            for arrangement in 0..total_perms(cost) {
+                let mut b = a[cost..].to_vec();
+                b.extend(lex_unrank(arrangement, &a[..cost]).unwrap());
+                if metrics::overlap(&a, &b) == cost {
+                    successors.push((from, lex_rank(&b).unwrap(), cost));
+                }
+            }
+        }
+        successors.sort_unstable();
+        entries.extend(successors);
+    }
+    return entries;
+}
+
+/// Visit-order adjacency of a sequence: a (from, to, cost) entry for each
+/// pair of consecutive newly visited permutations, where cost is the amount of
+/// tokens the sequence actually spends between them. The cost is above the
+/// overlap cost wherever the sequence wastes tokens.
+pub fn visit_adjacency(sequence: &[usize], n: usize) -> Vec<(usize, usize, usize)> {
+    let starts = first_visits(sequence, n);
+    let rank = |start: usize| lex_rank(&sequence[start..start + n]).unwrap();
+    return starts.windows(2).map(|pair| (rank(pair[0]), rank(pair[1]), pair[1] - pair[0])).collect();
+}
+
+/// Amount of permutations of n tokens. Panics on overflow.
+fn total_perms(n: usize) -> usize {
+    return checked_factorial(n).expect("n! overflows a usize");
+}
+
+/// Writes a square matrix in row-major order as CSV, one row per line
+pub fn write_dense_csv<W: Write>(mut w: W, matrix: &[u8], size: usize) -> io::Result<()> {
+    for row in matrix.chunks(size.max(1)) {
+        let cells: Vec<String> = row.iter().map(|cost| cost.to_string()).collect();
+        writeln!(w, "{}", cells.join(","))?;
+    }
+    return Ok(());
+}
+
+/// Writes (from, to, cost) entries as CSV with a `from,to,cost` header
+pub fn write_sparse_csv<W: Write>(mut w: W, entries: &[(usize, usize, usize)]) -> io::Result<()> {
+    writeln!(w, "from,to,cost")?;
+    for (from, to, cost) in entries {
+        writeln!(w, "{},{},{}", from, to, cost)?;
+    }
+    return Ok(());
+}
+
+/// Writes a square matrix of bytes in row-major order in NumPy's .npy format
+/// (version 1.0), readable with numpy.load
+pub fn write_npy<W: Write>(mut w: W, matrix: &[u8], size: usize) -> io::Result<()> {
+    let mut header = format!("{{'descr': '|u1', 'fortran_order': False, 'shape': ({}, {}), }}", size, size);
+    // The magic string, version and header length take 10 bytes and the whole
+    // preamble is padded with spaces to a multiple of 64, ending in a newline
+    let padding = 63 - (10 + header.len()) % 64;
+    header.push_str(&" ".repeat(padding));
+    header.push('\n');
+    w.write_all(b"\x93NUMPY\x01\x00")?;
+    w.write_all(&(header.len() as u16).to_le_bytes())?;
+    w.write_all(header.as_bytes())?;
+    w.write_all(matrix)?;
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cost_matrices() {
+        let matrix = overlap_cost_matrix(3);
+        // Row of 123: 123, 132, 213, 231, 312, 321
+        assert_eq!(&matrix[..6], &[0,3,3,1,2,2]);
+        for n in 1..6 {
+            let size = total_perms(n);
+            let matrix = overlap_cost_matrix(n);
+            let mut dense = Vec::new();
+            for (i, cost) in matrix.iter().enumerate() {
+                if *cost >= 1 && *cost <= 2 {
+                    dense.push((i / size, i % size, *cost as usize));
+                }
+            }
+            assert_eq!(sparse_overlap_costs(n, 2), dense);
+        }
+        assert_eq!(visit_adjacency(&[1,2,3,1,2,1,3,2,1], 3), vec![(0,3,1), (3,4,1), (4,2,2), (2,1,1), (1,5,1)]);
+
+        let mut csv = Vec::new();
+        write_dense_csv(&mut csv, &overlap_cost_matrix(2), 2).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap(), "0,1\n1,0\n");
+        let mut npy = Vec::new();
+        write_npy(&mut npy, &matrix, 6).unwrap();
+        assert!(npy.starts_with(b"\x93NUMPY\x01\x00"));
+        assert_eq!((npy.len() - matrix.len()) % 64, 0);
+        assert_eq!(npy[npy.len() - matrix.len() - 1], b'\n');
+    }
+}
@@ -0,0 +1,107 @@
+// Process wide throughput counters. Solvers and checkers add to them once
+// per call rather than per step, so keeping them costs a few relaxed atomic
+// adds. A Metrics snapshot turns the totals into rates.
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::progress::Stopwatch;
+
+static WINDOWS_CHECKED: AtomicU64 = AtomicU64::new(0);
+static PERMS_COVERED: AtomicU64 = AtomicU64::new(0);
+static NODES_EXPANDED: AtomicU64 = AtomicU64::new(0);
+static STARTED: OnceLock<Stopwatch> = OnceLock::new();
+
+fn stopwatch() -> &'static Stopwatch {
+    return STARTED.get_or_init(Stopwatch::start);
+}
+
+/// Windows of a candidate sequence looked at by a checker
+pub(crate) fn add_windows_checked(count: usize) {
+    stopwatch();
+    WINDOWS_CHECKED.fetch_add(count as u64, Ordering::Relaxed);
+}
+
+/// Permutations found by a checker or placed by a solver
+pub(crate) fn add_perms_covered(count: usize) {
+    stopwatch();
+    PERMS_COVERED.fetch_add(count as u64, Ordering::Relaxed);
+}
+
+/// Candidate permutations a solver considered appending
+pub(crate) fn add_nodes_expanded(count: usize) {
+    stopwatch();
+    NODES_EXPANDED.fetch_add(count as u64, Ordering::Relaxed);
+}
+
+/// Counter totals, with the time since the clock was started (by
+/// Metrics::start or else when something was first counted)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Metrics {
+    pub elapsed_secs: f64,
+    pub windows_checked: u64,
+    pub perms_covered: u64,
+    pub nodes_expanded: u64,
+}
+
+impl Metrics {
+    /// Starts the clock rates are measured against, if it isn't running yet
+    pub fn start() {
+        stopwatch();
+    }
+
+    /// Reads the current totals
+    pub fn snapshot() -> Metrics {
+        return Metrics {
+            elapsed_secs: STARTED.get().map_or(0.0, |start| start.elapsed_secs()),
+            windows_checked: WINDOWS_CHECKED.load(Ordering::Relaxed),
+            perms_covered: PERMS_COVERED.load(Ordering::Relaxed),
+            nodes_expanded: NODES_EXPANDED.load(Ordering::Relaxed),
+        };
+    }
+
+    fn rate(&self, count: u64) -> f64 {
+        if self.elapsed_secs > 0.0 {
+            return count as f64 / self.elapsed_secs;
+        }
+        return 0.0;
+    }
+
+    pub fn windows_per_sec(&self) -> f64 {
+        return self.rate(self.windows_checked);
+    }
+
+    pub fn perms_per_sec(&self) -> f64 {
+        return self.rate(self.perms_covered);
+    }
+
+    pub fn nodes_per_sec(&self) -> f64 {
+        return self.rate(self.nodes_expanded);
+    }
+
+    /// True when nothing has been counted yet
+    pub fn is_empty(&self) -> bool {
+        return self.windows_checked == 0 && self.perms_covered == 0 && self.nodes_expanded == 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::SuperPermHandling;
+    use crate::bruteforce_optimise;
+
+    #[test]
+    fn counters_grow() {
+        // Other tests add to the same counters so only lower bounds are known
+        let before = Metrics::snapshot();
+        let sequence = bruteforce_optimise::Handle.create_superperm(3);
+        assert!(bruteforce_optimise::Handle.check_superperm(&sequence, 3));
+        let after = Metrics::snapshot();
+        assert!(after.windows_checked >= before.windows_checked + 7);
+        assert!(after.perms_covered >= before.perms_covered + 12);
+        assert!(after.nodes_expanded > before.nodes_expanded);
+        assert!(after.is_empty() == false);
+    }
+}
@@ -0,0 +1,651 @@
+// Mixed radix number systems, where each position of a number can have its
+// own base. Useful for ranking/unranking combinatorial objects, e.g the
+// permutations handled by bruteforce_optimise.
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::FusedIterator;
+
+#[cfg(feature = "bigint")]
+use num_bigint::BigUint;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+/// Class to encode any value into a different base where
+/// each "position" can be of a different base
+///
+/// ```
+/// use super_permutations_attempt::MixedRadix;
+///
+/// // Positions of base 5, 4 and 3, least significant first
+/// let system = MixedRadix::new(vec![5, 4, 3]);
+/// assert_eq!(system.max_value, 60);
+/// assert_eq!(system.encode_value(&54), vec![4, 2, 2]);
+/// assert_eq!(system.decode_representation(&vec![4, 2, 2]), 54);
+///
+/// // Iterating yields the representation of every value from 0 to max_value-1
+/// assert_eq!(system.into_iter().nth(54), Some(vec![4, 2, 2]));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MixedRadix {
+    /// The base of each position for this system
+    pub bases: Vec<usize>,
+    /// The first integer above 0 that is unrepresentable with the bases given
+    pub max_value: usize,
+}
+
+/// Errors that can arise when creating or using a MixedRadix system
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MixedRadixError {
+    /// The product of the bases doesn't fit in a usize
+    Overflow,
+    /// A base of 0 was given, which has no digits
+    ZeroBase { position: usize },
+    /// A digit of a representation isn't below the base of its position
+    DigitOutOfRange { position: usize, digit: usize, base: usize },
+    /// A representation has a different number of positions to the system
+    LengthMismatch { expected: usize, found: usize },
+    /// A value is too large to be represented by the system
+    ValueOutOfRange { value: usize, max_value: usize },
+}
+
+impl fmt::Display for MixedRadixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MixedRadixError::Overflow => write!(f, "mixed radix system is too large for a usize"),
+            MixedRadixError::ZeroBase { position } => write!(f, "base at position {} is 0", position),
+            MixedRadixError::DigitOutOfRange { position, digit, base } => {
+                write!(f, "digit {} at position {} is not below its base {}", digit, position, base)
+            }
+            MixedRadixError::LengthMismatch { expected, found } => {
+                write!(f, "representation has {} positions but the system has {}", found, expected)
+            }
+            MixedRadixError::ValueOutOfRange { value, max_value } => {
+                write!(f, "value {} is not below the system's max value {}", value, max_value)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MixedRadixError {}
+
+/// Short for "Mixed Radix Representation".
+/// Data type for a value represented in a mixed radix system
+pub type MixedRadixRepr = Vec<usize>;
+
+impl MixedRadix {
+    /// bases parameter details:
+    ///  - Vec passed in expected to be the base for each position,
+    ///  - Values in the front of vec refer to least significant positions.
+    ///  - It also indirectly sets the number of positions available.
+    ///
+    /// Panics if any base is 0 or max_value doesn't fit in a usize. See try_new
+    /// for a non-panicking version and BigMixedRadix for systems that large.
+    pub fn new(bases: Vec<usize>) -> MixedRadix{
+        match MixedRadix::try_new(bases) {
+            Ok(system) => system,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Same as new but returns an error instead of panicking if the system
+    /// can't be represented
+    pub fn try_new(bases: Vec<usize>) -> Result<MixedRadix, MixedRadixError> {
+        // Calculate maximum representable value with the bases passed in
+        let mut max: usize = 1;
+        for (position, base) in bases.iter().enumerate() {
+            if *base == 0 {
+                return Err(MixedRadixError::ZeroBase { position });
+            }
+            max = max.checked_mul(*base).ok_or(MixedRadixError::Overflow)?;
+        }
+        return Ok(MixedRadix {
+            bases,
+            max_value: max,
+        });
+    }
+
+    /// Interprets a value into a representation of the bases specified at instantiation
+    /// 
+    /// E.g The value 54 encoded to the bases (5, 4, 3) will be represented as (4, 2, 2)
+    pub fn encode_value(&self, val: &usize) -> MixedRadixRepr {
+        let mut representation: MixedRadixRepr = vec![0; self.bases.len()];
+        let mut carry_over = *val;
+        for (i, base) in self.bases.iter().enumerate() {
+            representation[i] = carry_over % base;
+            carry_over /= base;
+        }
+        return representation;
+    }
+    /// Same as encode_value but errors for values that can't be represented
+    /// instead of silently dropping the excess
+    pub fn try_encode_value(&self, val: &usize) -> Result<MixedRadixRepr, MixedRadixError> {
+        if *val >= self.max_value {
+            return Err(MixedRadixError::ValueOutOfRange { value: *val, max_value: self.max_value });
+        }
+        return Ok(self.encode_value(val));
+    }
+    /// Checks a representation has a digit for each position and that every
+    /// digit is below the base of its position
+    pub fn validate(&self, repr: &MixedRadixRepr) -> Result<(), MixedRadixError> {
+        if repr.len() != self.bases.len() {
+            return Err(MixedRadixError::LengthMismatch { expected: self.bases.len(), found: repr.len() });
+        }
+        for (position, (digit, base)) in repr.iter().zip(&self.bases).enumerate() {
+            if digit >= base {
+                return Err(MixedRadixError::DigitOutOfRange { position, digit: *digit, base: *base });
+            }
+        }
+        return Ok(());
+    }
+    /// Adds one to a representation in place, carrying into more significant
+    /// positions as needed. Amortised O(1), unlike re-encoding a value.
+    ///
+    /// Returns false if the representation was the largest representable and
+    /// has wrapped around to all zeros.
+    pub fn increment(&self, repr: &mut MixedRadixRepr) -> bool {
+        for (digit, base) in repr.iter_mut().zip(&self.bases) {
+            *digit += 1;
+            if *digit < *base {
+                return true;
+            }
+            *digit = 0;
+        }
+        return false;
+    }
+    /// Subtracts one from a representation in place, borrowing from more
+    /// significant positions as needed. Inverse of increment.
+    ///
+    /// Returns false if the representation was all zeros and has wrapped
+    /// around to the largest representable.
+    pub fn decrement(&self, repr: &mut MixedRadixRepr) -> bool {
+        for (digit, base) in repr.iter_mut().zip(&self.bases) {
+            if *digit > 0 {
+                *digit -= 1;
+                return true;
+            }
+            *digit = base - 1;
+        }
+        return false;
+    }
+    /// Adds two representations digit by digit, carrying into more significant
+    /// positions against each position's base.
+    ///
+    /// Returns None if the sum isn't representable (i.e it would be >= max_value)
+    /// or either representation has a different number of positions to the system.
+    pub fn checked_add(&self, a: &MixedRadixRepr, b: &MixedRadixRepr) -> Option<MixedRadixRepr> {
+        if a.len() != self.bases.len() || b.len() != self.bases.len() {
+            return None;
+        }
+        let mut sum: MixedRadixRepr = Vec::with_capacity(self.bases.len());
+        let mut carry = 0;
+        for (pos, base) in self.bases.iter().enumerate() {
+            let digit = a[pos] + b[pos] + carry;
+            sum.push(digit % base);
+            carry = digit / base;
+        }
+        if carry != 0 {
+            return None;
+        }
+        return Some(sum);
+    }
+    /// Subtracts b from a digit by digit, borrowing from more significant
+    /// positions against each position's base.
+    ///
+    /// Returns None if b is larger than a or either representation has a
+    /// different number of positions to the system.
+    pub fn checked_sub(&self, a: &MixedRadixRepr, b: &MixedRadixRepr) -> Option<MixedRadixRepr> {
+        if a.len() != self.bases.len() || b.len() != self.bases.len() {
+            return None;
+        }
+        let mut difference: MixedRadixRepr = Vec::with_capacity(self.bases.len());
+        let mut borrow = 0;
+        for (pos, base) in self.bases.iter().enumerate() {
+            let subtrahend = b[pos] + borrow;
+            if a[pos] >= subtrahend {
+                difference.push(a[pos] - subtrahend);
+                borrow = 0;
+            } else {
+                difference.push(a[pos] + base - subtrahend);
+                borrow = 1;
+            }
+        }
+        if borrow != 0 {
+            return None;
+        }
+        return Some(difference);
+    }
+    /// Compares the values of two representations without decoding them, by
+    /// comparing from the most significant position down.
+    ///
+    /// Both representations must have a position per base, which is only
+    /// checked in debug builds.
+    pub fn compare(&self, a: &MixedRadixRepr, b: &MixedRadixRepr) -> Ordering {
+        debug_assert!(a.len() == self.bases.len() && b.len() == self.bases.len(), "representation length mismatch");
+        for pos in (0..self.bases.len()).rev() {
+            match a[pos].cmp(&b[pos]) {
+                Ordering::Equal => continue,
+                unequal => return unequal,
+            }
+        }
+        return Ordering::Equal;
+    }
+    /// Same as decode_representation but validates the representation first
+    /// instead of trusting its digits
+    pub fn try_decode_representation(&self, repr: &MixedRadixRepr) -> Result<usize, MixedRadixError> {
+        self.validate(repr)?;
+        return Ok(self.decode_representation(repr));
+    }
+    /// Interprets a representation into a value. Inverse of encode_value
+    ///
+    /// The representation is trusted to be valid for the system. See
+    /// try_decode_representation for untrusted input.
+    pub fn decode_representation(&self, repr: &MixedRadixRepr) -> usize {
+        let mut sum: usize = 0;
+        let mut position_mult: usize = 1;
+        for (pos, base) in self.bases.iter().enumerate() {
+            sum += repr[pos] * position_mult;
+            position_mult *= base;
+        }
+        return sum;
+    }
+
+    /// Iterates over every representation in reflected Gray order, where each
+    /// representation differs from the one before it by a single digit moving
+    /// up or down by 1. The least significant digit changes most often.
+    ///
+    /// E.g the bases (2, 3) give (0,0), (1,0), (1,1), (0,1), (0,2), (1,2)
+    pub fn gray_iter(&self) -> GrayIter<'_> {
+        return GrayIter {
+            system: self,
+            current: vec![0; self.bases.len()],
+            directions: vec![1; self.bases.len()],
+            remaining: self.max_value,
+            started: false,
+        };
+    }
+}
+
+// implementing iteration over MixedRadix
+// Used: https://stackoverflow.com/questions/68606470/how-to-return-a-reference-when-implementing-an-iterator
+/// Iterator over the representations of every value in a MixedRadix system,
+/// in increasing order of value. Created by iterating over `&MixedRadix`.
+pub struct MixedRadixIter<'a> {
+    system: &'a MixedRadix,
+    /// Value of the next representation to yield from the front
+    i: usize,
+    /// Representation of i, kept up to date by incrementing
+    front: MixedRadixRepr,
+    /// One past the value of the next representation to yield from the back
+    end: usize,
+}
+impl<'a> Iterator for MixedRadixIter<'a> {
+    type Item = MixedRadixRepr;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.i >= self.end {
+            return None;
+        } else {
+            let output = self.front.clone();
+            self.system.increment(&mut self.front);
+            self.i += 1;
+            return Some(output);
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.end.saturating_sub(self.i);
+        return (remaining, Some(remaining));
+    }
+
+    /// Skips straight to the nth representation without encoding the ones
+    /// skipped over
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        if n > 0 {
+            self.i = self.i.saturating_add(n).min(self.end);
+            self.front = self.system.encode_value(&self.i);
+        }
+        return self.next();
+    }
+}
+impl<'a> DoubleEndedIterator for MixedRadixIter<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.end <= self.i {
+            return None;
+        } else {
+            self.end -= 1;
+            return Some(self.system.encode_value(&self.end));
+        }
+    }
+
+    fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+        self.end = self.end.saturating_sub(n).max(self.i);
+        return self.next_back();
+    }
+}
+impl<'a> ExactSizeIterator for MixedRadixIter<'a> {}
+impl<'a> FusedIterator for MixedRadixIter<'a> {}
+impl<'a> IntoIterator for &'a MixedRadix {
+    type Item = MixedRadixRepr;
+    type IntoIter = MixedRadixIter<'a>;
+    fn into_iter(self) -> Self::IntoIter {
+        MixedRadixIter {
+            system: self,
+            i: 0,
+            front: vec![0; self.bases.len()],
+            end: self.max_value,
+        }
+    }
+}
+
+/// The change between consecutive representations of a Gray order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GrayDelta {
+    /// Position of the digit that changed
+    pub position: usize,
+    /// +1 if the digit went up, -1 if it went down
+    pub delta: isize,
+}
+
+/// Iterator over the representations of a MixedRadix system in reflected Gray
+/// order. Each representation comes with the change from the one before it,
+/// which is None for the first. Created by MixedRadix::gray_iter.
+pub struct GrayIter<'a> {
+    system: &'a MixedRadix,
+    /// Representation most recently yielded
+    current: MixedRadixRepr,
+    /// Direction each digit is currently moving in
+    directions: Vec<isize>,
+    /// Amount of representations left to yield
+    remaining: usize,
+    started: bool,
+}
+impl<'a> Iterator for GrayIter<'a> {
+    type Item = (MixedRadixRepr, Option<GrayDelta>);
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        if !self.started {
+            self.started = true;
+            return Some((self.current.clone(), None));
+        }
+        // Move the least significant digit that isn't at the end of its
+        // range, reversing the direction of the digits that are
+        for (position, base) in self.system.bases.iter().enumerate() {
+            let next = self.current[position] as isize + self.directions[position];
+            if next >= 0 && next < *base as isize {
+                self.current[position] = next as usize;
+                let change = GrayDelta { position, delta: self.directions[position] };
+                return Some((self.current.clone(), Some(change)));
+            }
+            self.directions[position] = -self.directions[position];
+        }
+        return None;
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        return (self.remaining, Some(self.remaining));
+    }
+}
+impl<'a> ExactSizeIterator for GrayIter<'a> {}
+impl<'a> FusedIterator for GrayIter<'a> {}
+
+#[cfg(feature = "rayon")]
+impl MixedRadix {
+    /// Parallel version of iterating over the system. The range of values is
+    /// split across rayon's threads and each representation is encoded from
+    /// its value, so the output is in the same order as the serial iterator.
+    ///
+    /// Requires the `rayon` feature.
+    pub fn par_iter(&self) -> impl IndexedParallelIterator<Item = MixedRadixRepr> + '_ {
+        return (0..self.max_value).into_par_iter().map(move |value| self.encode_value(&value));
+    }
+}
+
+/// Same as MixedRadix except values are arbitrary precision integers, so any
+/// system (e.g the permutations of 30 tokens) can be represented.
+///
+/// Requires the `bigint` feature.
+#[cfg(feature = "bigint")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BigMixedRadix {
+    /// The base of each position for this system
+    pub bases: Vec<usize>,
+    /// The first integer above 0 that is unrepresentable with the bases given
+    pub max_value: BigUint,
+}
+
+#[cfg(feature = "bigint")]
+impl BigMixedRadix {
+    /// See MixedRadix::new for the meaning of bases.
+    ///
+    /// Panics if any base is 0. See try_new for a non-panicking version.
+    pub fn new(bases: Vec<usize>) -> BigMixedRadix {
+        match BigMixedRadix::try_new(bases) {
+            Ok(system) => system,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Same as new but returns an error instead of panicking if a base is 0.
+    /// The system can't overflow, so that's the only error
+    pub fn try_new(bases: Vec<usize>) -> Result<BigMixedRadix, MixedRadixError> {
+        if let Some(position) = bases.iter().position(|base| *base == 0) {
+            return Err(MixedRadixError::ZeroBase { position });
+        }
+        let max_value = bases.iter().map(|base| BigUint::from(*base)).product();
+        return Ok(BigMixedRadix {
+            bases,
+            max_value,
+        });
+    }
+
+    /// Interprets a value into a representation of the bases specified at instantiation
+    pub fn encode_value(&self, val: &BigUint) -> MixedRadixRepr {
+        let mut representation: MixedRadixRepr = vec![0; self.bases.len()];
+        let mut carry_over = val.clone();
+        for (i, base) in self.bases.iter().enumerate() {
+            let digit = &carry_over % *base;
+            // The digit is below base so always fits in a usize
+            representation[i] = digit.try_into().expect("digit exceeds its base");
+            carry_over /= *base;
+        }
+        return representation;
+    }
+
+    /// Interprets a representation into a value. Inverse of encode_value
+    pub fn decode_representation(&self, repr: &MixedRadixRepr) -> BigUint {
+        let mut sum = BigUint::from(0u32);
+        for (pos, base) in self.bases.iter().enumerate().rev() {
+            sum = sum * *base + repr[pos];
+        }
+        return sum;
+    }
+
+    /// Converts to a MixedRadix if the system fits within a usize
+    pub fn to_mixed_radix(&self) -> Result<MixedRadix, MixedRadixError> {
+        return MixedRadix::try_new(self.bases.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mixedradix_iteration() {
+        let system = MixedRadix::new(vec![3,2,2]);
+        let expected = vec![
+            vec![0,0,0],
+            vec![1,0,0],
+            vec![2,0,0],
+            vec![0,1,0],
+            vec![1,1,0],
+            vec![2,1,0],
+            vec![0,0,1],
+            vec![1,0,1],
+            vec![2,0,1],
+            vec![0,1,1],
+            vec![1,1,1],
+            vec![2,1,1],
+        ];
+        // check if iterator can be used multiple times
+        for _ in 0..2 {
+            for (i, repr) in system.into_iter().enumerate() {
+                assert_eq!(repr, expected[i]);
+            }
+        }
+    }
+
+    #[test]
+    fn mixedradix_edgecase() {
+        let system = MixedRadix::new(vec![1,2]);
+        let expected = [
+            vec![0,0],
+            vec![0,1],
+        ];
+        for (i, repr) in system.into_iter().enumerate() {
+            assert_eq!(repr, expected[i]);
+        }
+    }
+
+    #[test]
+    fn mixedradix_increment_and_decrement() {
+        let system = MixedRadix::new(vec![3,2,2]);
+        let mut repr = vec![0,0,0];
+        for value in 1..system.max_value {
+            assert!(system.increment(&mut repr));
+            assert_eq!(repr, system.encode_value(&value));
+        }
+        assert!(!system.increment(&mut repr));
+        assert_eq!(repr, vec![0,0,0]);
+        assert!(!system.decrement(&mut repr));
+        assert_eq!(repr, vec![2,1,1]);
+        assert!(system.decrement(&mut repr));
+        assert_eq!(repr, vec![1,1,1]);
+    }
+
+    #[test]
+    fn mixedradix_arithmetic() {
+        let system = MixedRadix::new(vec![5,4,3]);
+        for a in 0..system.max_value {
+            for b in 0..system.max_value {
+                let (ra, rb) = (system.encode_value(&a), system.encode_value(&b));
+                let sum = system.checked_add(&ra, &rb);
+                let difference = system.checked_sub(&ra, &rb);
+                if a + b < system.max_value {
+                    assert_eq!(sum, Some(system.encode_value(&(a + b))));
+                } else {
+                    assert_eq!(sum, None);
+                }
+                if a >= b {
+                    assert_eq!(difference, Some(system.encode_value(&(a - b))));
+                } else {
+                    assert_eq!(difference, None);
+                }
+                assert_eq!(system.compare(&ra, &rb), a.cmp(&b));
+            }
+        }
+        // Representations of the wrong length are rejected rather than indexed past
+        assert_eq!(system.checked_add(&vec![1,2], &vec![1,2,0]), None);
+        assert_eq!(system.checked_sub(&vec![1,2,0], &vec![1,2,0,0]), None);
+    }
+
+    #[test]
+    fn mixedradix_overflow() {
+        let factorial_bases: Vec<usize> = (1..=21).rev().collect();
+        assert_eq!(MixedRadix::try_new(factorial_bases), Err(MixedRadixError::Overflow));
+        assert!(MixedRadix::try_new((1..=20).rev().collect()).is_ok());
+    }
+
+    #[test]
+    fn mixedradix_validation() {
+        assert_eq!(MixedRadix::try_new(vec![3,0,2]), Err(MixedRadixError::ZeroBase { position: 1 }));
+        let system = MixedRadix::new(vec![3,2,2]);
+        assert_eq!(system.try_decode_representation(&vec![2,1,1]), Ok(11));
+        assert_eq!(
+            system.try_decode_representation(&vec![1,2,0]),
+            Err(MixedRadixError::DigitOutOfRange { position: 1, digit: 2, base: 2 })
+        );
+        assert_eq!(
+            system.try_decode_representation(&vec![1,1]),
+            Err(MixedRadixError::LengthMismatch { expected: 3, found: 2 })
+        );
+        assert_eq!(system.try_encode_value(&11), Ok(vec![2,1,1]));
+        assert_eq!(
+            system.try_encode_value(&12),
+            Err(MixedRadixError::ValueOutOfRange { value: 12, max_value: 12 })
+        );
+    }
+
+    #[test]
+    fn gray_order() {
+        let system = MixedRadix::new(vec![2,3]);
+        let reprs: Vec<MixedRadixRepr> = system.gray_iter().map(|(repr, _)| repr).collect();
+        assert_eq!(reprs, vec![vec![0,0], vec![1,0], vec![1,1], vec![0,1], vec![0,2], vec![1,2]]);
+
+        let system = MixedRadix::new(vec![3,1,4,2]);
+        let mut seen = vec![false; system.max_value];
+        let mut previous: Option<MixedRadixRepr> = None;
+        for (repr, change) in system.gray_iter() {
+            let value = system.decode_representation(&repr);
+            assert!(!seen[value]);
+            seen[value] = true;
+            if let Some(previous) = previous {
+                let change = change.unwrap();
+                for position in 0..repr.len() {
+                    let expected = previous[position] as isize + if position == change.position { change.delta } else { 0 };
+                    assert_eq!(repr[position] as isize, expected);
+                }
+            } else {
+                assert_eq!(change, None);
+            }
+            previous = Some(repr);
+        }
+        assert!(seen.iter().all(|s| *s));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_iteration() {
+        let system = MixedRadix::new(vec![5,4,3,2]);
+        let serial: Vec<MixedRadixRepr> = system.into_iter().collect();
+        let parallel: Vec<MixedRadixRepr> = system.par_iter().collect();
+        assert_eq!(serial, parallel);
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn big_mixedradix() {
+        let system = BigMixedRadix::new((1..=30).rev().collect());
+        let max = &system.max_value - 1u32;
+        let repr = system.encode_value(&max);
+        assert_eq!(repr, (0..30).rev().collect::<Vec<usize>>());
+        assert_eq!(system.decode_representation(&repr), max);
+        assert!(system.to_mixed_radix().is_err());
+
+        let small = BigMixedRadix::new(vec![5,4,3]).to_mixed_radix().unwrap();
+        assert_eq!(small.encode_value(&54), BigMixedRadix::new(vec![5,4,3]).encode_value(&BigUint::from(54u32)));
+        assert_eq!(BigMixedRadix::try_new(vec![5,0,3]), Err(MixedRadixError::ZeroBase { position: 1 }));
+    }
+
+    #[test]
+    fn mixedradix_iterator_adapters() {
+        let system = MixedRadix::new(vec![3,2,2]);
+        let forward: Vec<MixedRadixRepr> = system.into_iter().collect();
+        let mut backward: Vec<MixedRadixRepr> = system.into_iter().rev().collect();
+        backward.reverse();
+        assert_eq!(forward, backward);
+
+        let mut iter = system.into_iter();
+        assert_eq!(iter.len(), 12);
+        assert_eq!(iter.nth(4), Some(vec![1,1,0]));
+        assert_eq!(iter.nth_back(2), Some(vec![0,1,1]));
+        assert_eq!(iter.len(), 4);
+        assert_eq!(iter.nth(10), None);
+        assert_eq!(iter.next_back(), None);
+
+        let stepped: Vec<MixedRadixRepr> = system.into_iter().skip(1).step_by(5).collect();
+        assert_eq!(stepped, vec![vec![1,0,0], vec![0,0,1], vec![2,1,1]]);
+    }
+}
@@ -0,0 +1,203 @@
+// Permutations of multisets, i.e sequences of tokens that may repeat, and
+// super-sequences containing every distinct arrangement of a multiset.
+//
+// The insertion shift scheme of PermutationMapper relies on every token being
+// distinct, so multisets are ranked in lexicographic order instead. E.g the
+// multiset [1,1,2] has the 3 arrangements [1,1,2], [1,2,1] and [2,1,1].
+use std::collections::BTreeMap;
+
+/// Class to help with the handling of the distinct arrangements of a multiset
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultisetMapper {
+    /// Tokens of the multiset in ascending order. This is the "0th" arrangement
+    pub core_sequence: Vec<usize>,
+    /// Amount of distinct arrangements of the multiset
+    pub max_value: usize,
+}
+
+impl MultisetMapper {
+    /// Tokens passed in can be in any order and repeat.
+    ///
+    /// E.g passing in [2,1,1] will focus on the arrangements [1,1,2], [1,2,1] and [2,1,1].
+    /// Panics if the amount of arrangements doesn't fit in a usize.
+    pub fn new(mut tokens: Vec<usize>) -> MultisetMapper {
+        tokens.sort_unstable();
+        let max_value = arrangement_count(&token_counts(&tokens))
+            .and_then(|count| usize::try_from(count).ok())
+            .expect("amount of arrangements overflows a usize");
+        return MultisetMapper { core_sequence: tokens, max_value };
+    }
+
+    /// Reads a value and maps it to a distinct arrangement in lexicographic order.
+    /// Panics if the value is not below max_value.
+    pub fn value_to_perm(&self, value: &usize) -> Vec<usize> {
+        assert!(*value < self.max_value, "value out of range");
+        let mut counts = token_counts(&self.core_sequence);
+        let mut remaining = self.core_sequence.len() as u128;
+        let mut arrangements = self.max_value as u128;
+        let mut value = *value as u128;
+        let mut perm = Vec::with_capacity(self.core_sequence.len());
+        for _ in 0..self.core_sequence.len() {
+            // Each candidate token starts a block of arrangements, skip blocks
+            // until the one containing the value
+            for (token, count) in counts.iter_mut() {
+                if *count == 0 {
+                    continue;
+                }
+                let block = arrangements * (*count as u128) / remaining;
+                if value < block {
+                    perm.push(*token);
+                    *count -= 1;
+                    arrangements = block;
+                    break;
+                }
+                value -= block;
+            }
+            remaining -= 1;
+        }
+        return perm;
+    }
+
+    /// Reads an arrangement and maps it to its value. Inverse of value_to_perm.
+    ///
+    /// A return of None indicates that the sequence passed in is not an
+    /// arrangement of the multiset.
+    pub fn perm_to_value(&self, permutation: &[usize]) -> Option<usize> {
+        if permutation.len() != self.core_sequence.len() {
+            return None;
+        }
+        let mut counts = token_counts(&self.core_sequence);
+        let mut remaining = self.core_sequence.len() as u128;
+        let mut arrangements = self.max_value as u128;
+        let mut value: u128 = 0;
+        for token in permutation {
+            // Every arrangement starting with a smaller token comes first
+            for (_, count) in counts.range(..token) {
+                value += arrangements * (*count as u128) / remaining;
+            }
+            let count = counts.get_mut(token).filter(|count| **count > 0)?;
+            arrangements = arrangements * (*count as u128) / remaining;
+            *count -= 1;
+            remaining -= 1;
+        }
+        return Some(value as usize);
+    }
+
+    /// Iterates over every distinct arrangement in lexicographic order
+    pub fn iter(&self) -> impl Iterator<Item = Vec<usize>> + '_ {
+        return (0..self.max_value).scan(self.core_sequence.clone(), |perm, _| {
+            let output = perm.clone();
+            crate::permutation::next_permutation(perm);
+            return Some(output);
+        });
+    }
+}
+
+/// Amount of each distinct token in a sequence
+fn token_counts(tokens: &[usize]) -> BTreeMap<usize, usize> {
+    let mut counts = BTreeMap::new();
+    for token in tokens {
+        *counts.entry(*token).or_insert(0) += 1;
+    }
+    return counts;
+}
+
+/// Multinomial coefficient of the counts. None on overflow.
+fn arrangement_count(counts: &BTreeMap<usize, usize>) -> Option<u128> {
+    // Choose the positions of each distinct token in turn
+    let mut total: u128 = 1;
+    let mut placed: u128 = 0;
+    for count in counts.values() {
+        for i in 1..*count as u128 + 1 {
+            placed += 1;
+            total = total.checked_mul(placed)? / i;
+        }
+    }
+    return Some(total);
+}
+
+/// Checks whether every distinct arrangement of the multiset of tokens appears
+/// as a contiguous run in the sequence.
+pub fn check_superseq(sequence: &[usize], tokens: &[usize]) -> bool {
+    let mapper = MultisetMapper::new(tokens.to_vec());
+    if tokens.is_empty() {
+        // The empty arrangement is in every sequence
+        return true;
+    }
+    if sequence.len() < tokens.len() {
+        return false;
+    }
+    let mut perm_checklist = vec![false; mapper.max_value];
+    for window in sequence.windows(tokens.len()) {
+        if let Some(value) = mapper.perm_to_value(window) {
+            perm_checklist[value] = true;
+        }
+    }
+    return perm_checklist.iter().all(|checked| *checked);
+}
+
+/// Greedily builds a sequence containing every distinct arrangement of the
+/// multiset of tokens, using the same approach as bruteforce_optimise: append
+/// the unseen arrangement that overlaps most with the end of the sequence.
+pub fn create_superseq(tokens: &[usize]) -> Vec<usize> {
+    let mapper = MultisetMapper::new(tokens.to_vec());
+    let n = tokens.len();
+    let mut superseq = mapper.core_sequence.clone();
+    let mut perm_checklist = vec![false; mapper.max_value];
+    perm_checklist[0] = true;
+
+    for _ in 1..mapper.max_value {
+        let mut appended = false;
+        // Try the longest overlaps first
+        for overlap in (0..n).rev() {
+            let trailing = &superseq[superseq.len() - overlap..];
+            let next = mapper
+                .iter()
+                .enumerate()
+                .find(|(value, perm)| perm_checklist[*value] == false && perm.starts_with(trailing));
+            if let Some((value, perm)) = next {
+                perm_checklist[value] = true;
+                superseq.extend_from_slice(&perm[overlap..]);
+                appended = true;
+                break;
+            }
+        }
+        if !appended {
+            break;
+        }
+    }
+    return superseq;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multiset_ranking() {
+        let mapper = MultisetMapper::new(vec![2,1,1,3]);
+        assert_eq!(mapper.max_value, 12);
+        let perms: Vec<Vec<usize>> = mapper.iter().collect();
+        assert_eq!(perms.len(), 12);
+        assert_eq!(perms[1], vec![1,1,3,2]);
+        for (value, perm) in perms.iter().enumerate() {
+            assert_eq!(mapper.value_to_perm(&value), *perm);
+            assert_eq!(mapper.perm_to_value(perm), Some(value));
+        }
+        assert_eq!(mapper.perm_to_value(&[1,2,2,3]), None);
+        assert_eq!(mapper.perm_to_value(&[1,1,2]), None);
+        // Without repeats the ranking is the usual lexicographic one
+        let mapper = MultisetMapper::new(vec![1,2,3,4]);
+        assert_eq!(mapper.max_value, 24);
+        assert_eq!(mapper.value_to_perm(&23), vec![4,3,2,1]);
+    }
+
+    #[test]
+    fn multiset_superseqs() {
+        assert!(check_superseq(&[1,1,2,1,1], &[1,1,2]));
+        assert!(!check_superseq(&[1,1,2,1], &[1,1,2]));
+        for tokens in [vec![1,1,2], vec![1,1,2,2], vec![1,2,2,3], vec![1,2,3,4]] {
+            assert!(check_superseq(&create_superseq(&tokens), &tokens));
+        }
+    }
+}
@@ -0,0 +1,1396 @@
+// Utilities for working with permutations of distinct tokens.
+//
+// Permutations can be ranked in two orders. The standard lexicographic order
+// via Lehmer codes gives ranks that agree with other permutation libraries
+// (e.g Python's itertools). PermutationMapper defaults to the crate's original
+// insertion shift order, where rank 1 is [2,1,3,...] rather than [1,2,...,n,n-1].
+use rand::Rng;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::mixed_radix::{MixedRadix, MixedRadixRepr};
+
+pub mod metrics;
+
+/// A number in the factorial number system (factoradic).
+///
+/// The digits are stored most significant first, with the digit at index i
+/// being below n-i. When the digits are the Lehmer code of a permutation, the
+/// value of the number is the lexicographic rank of the permutation.
+///
+/// E.g for n=3 the digits [2,1,0] have the value 2*2! + 1*1! + 0*0! = 5.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FactorialNumber {
+    pub digits: Vec<usize>,
+}
+
+impl FactorialNumber {
+    /// Creates the n digit factorial number with the value given. None if the
+    /// value is n! or more.
+    pub fn from_rank(rank: usize, n: usize) -> Option<FactorialNumber> {
+        let mut digits = vec![0; n];
+        let mut carry_over = rank;
+        // Least significant digit has base 1, the next base 2, and so on
+        for base in 1..n + 1 {
+            digits[n - base] = carry_over % base;
+            carry_over /= base;
+        }
+        if carry_over != 0 {
+            return None;
+        }
+        return Some(FactorialNumber { digits });
+    }
+
+    /// Value of the number. Panics if the value doesn't fit in a usize, which
+    /// is only possible for more than 20 digits.
+    pub fn rank(&self) -> usize {
+        let n = self.digits.len();
+        let mut rank: usize = 0;
+        for (i, digit) in self.digits.iter().enumerate() {
+            rank = rank
+                .checked_mul(n - i)
+                .and_then(|r| r.checked_add(*digit))
+                .expect("factorial number rank overflows a usize");
+        }
+        return rank;
+    }
+
+    /// Whether every digit is below the base of its position
+    pub fn is_valid(&self) -> bool {
+        let n = self.digits.len();
+        return self.digits.iter().enumerate().all(|(i, digit)| *digit < n - i);
+    }
+
+    /// The mixed radix system matching factorial numbers of n digits, in the
+    /// least significant first convention of MixedRadix (bases 1,2,...,n)
+    pub fn system(n: usize) -> MixedRadix {
+        return MixedRadix::new((1..n + 1).collect());
+    }
+
+    /// Converts to a representation of the system given by FactorialNumber::system
+    pub fn to_repr(&self) -> MixedRadixRepr {
+        return self.digits.iter().rev().copied().collect();
+    }
+
+    /// Converts from a representation of the system given by FactorialNumber::system
+    pub fn from_repr(repr: &MixedRadixRepr) -> FactorialNumber {
+        return FactorialNumber { digits: repr.iter().rev().copied().collect() };
+    }
+}
+
+/// Calculates the Lehmer code of a permutation: for each position, the count
+/// of later tokens that are smaller than the token at that position.
+///
+/// E.g [3,1,2] has the Lehmer code [2,0,0]. None if any token is repeated.
+pub fn lehmer_code(perm: &[usize]) -> Option<FactorialNumber> {
+    let mut digits = Vec::with_capacity(perm.len());
+    for (i, token) in perm.iter().enumerate() {
+        let mut smaller = 0;
+        for later in &perm[i + 1..] {
+            if later == token {
+                return None;
+            }
+            if later < token {
+                smaller += 1;
+            }
+        }
+        digits.push(smaller);
+    }
+    return Some(FactorialNumber { digits });
+}
+
+/// Builds the permutation of tokens with the Lehmer code given. The order the
+/// tokens are passed in doesn't matter. Inverse of lehmer_code.
+///
+/// None if the code isn't valid or its length doesn't match the tokens.
+pub fn perm_from_lehmer_code(code: &FactorialNumber, tokens: &[usize]) -> Option<Vec<usize>> {
+    if code.digits.len() != tokens.len() || !code.is_valid() {
+        return None;
+    }
+    let mut remaining = tokens.to_vec();
+    remaining.sort_unstable();
+    let mut perm = Vec::with_capacity(tokens.len());
+    for digit in &code.digits {
+        perm.push(remaining.remove(*digit));
+    }
+    return Some(perm);
+}
+
+/// Factorial of n, the amount of permutations of n tokens. None if it
+/// overflows a usize, i.e for n > 20 on 64 bit targets
+pub fn checked_factorial(n: usize) -> Option<usize> {
+    return (1..n + 1).try_fold(1usize, |acc, x| acc.checked_mul(x));
+}
+
+/// Lexicographic rank of a permutation amongst all permutations of its tokens.
+/// E.g [1,2,3] has rank 0 and [3,2,1] has rank 5. None if any token is repeated.
+pub fn lex_rank(perm: &[usize]) -> Option<usize> {
+    return Some(lehmer_code(perm)?.rank());
+}
+
+/// The permutation of tokens with the lexicographic rank given. Inverse of
+/// lex_rank. None if the rank is not below n!.
+pub fn lex_unrank(rank: usize, tokens: &[usize]) -> Option<Vec<usize>> {
+    let code = FactorialNumber::from_rank(rank, tokens.len())?;
+    return perm_from_lehmer_code(&code, tokens);
+}
+
+/// Amount of arrangements of k distinct tokens out of n, i.e n!/(n-k)!.
+/// None on overflow or if k is above n.
+pub fn k_perm_count(n: usize, k: usize) -> Option<usize> {
+    if k > n {
+        return None;
+    }
+    return (n - k + 1..n + 1).try_fold(1usize, |acc, x| acc.checked_mul(x));
+}
+
+/// Lexicographic rank of an arrangement of distinct tokens out of 1..=n,
+/// where k is the length of the arrangement. The digits of the rank are in
+/// the mixed radix system with bases (n, n-1, ..., n-k+1).
+///
+/// E.g for n=3 the arrangements of 2 tokens are 12, 13, 21, 23, 31, 32 in
+/// order, so [2,3] has rank 3. None if a token is repeated or outside 1..=n.
+pub fn k_perm_rank(perm: &[usize], n: usize) -> Option<usize> {
+    let mut used = vec![false; n];
+    let mut rank = 0;
+    for (i, token) in perm.iter().enumerate() {
+        if *token == 0 || *token > n || used[token - 1] {
+            return None;
+        }
+        // Tokens not yet used that are smaller each start an earlier block
+        let smaller = used[..token - 1].iter().filter(|x| **x == false).count();
+        used[token - 1] = true;
+        rank = rank * (n - i) + smaller;
+    }
+    return Some(rank);
+}
+
+/// The arrangement of k distinct tokens out of 1..=n with the rank given.
+/// Inverse of k_perm_rank. None if the rank is not below n!/(n-k)!.
+pub fn k_perm_unrank(rank: usize, n: usize, k: usize) -> Option<Vec<usize>> {
+    if rank >= k_perm_count(n, k)? {
+        return None;
+    }
+    // Read off the digits least significant first
+    let mut digits = vec![0; k];
+    let mut value = rank;
+    for i in (0..k).rev() {
+        digits[i] = value % (n - i);
+        value /= n - i;
+    }
+    let mut remaining: Vec<usize> = (1..n + 1).collect();
+    return Some(digits.iter().map(|digit| remaining.remove(*digit)).collect());
+}
+
+/// Whether the sequence contains each of the tokens 1..=n exactly once, where
+/// n is its length
+pub fn is_permutation(perm: &[usize]) -> bool {
+    let mut seen = vec![false; perm.len()];
+    for token in perm {
+        if *token == 0 || *token > perm.len() || seen[token - 1] {
+            return false;
+        }
+        seen[token - 1] = true;
+    }
+    return true;
+}
+
+/// Inverse of a permutation of 1..=n, i.e the permutation that undoes it.
+/// E.g [2,3,1] has the inverse [3,1,2]. None if perm is not a permutation of 1..=n.
+pub fn invert(perm: &[usize]) -> Option<Vec<usize>> {
+    if !is_permutation(perm) {
+        return None;
+    }
+    let mut inverse = vec![0; perm.len()];
+    for (i, token) in perm.iter().enumerate() {
+        inverse[token - 1] = i + 1;
+    }
+    return Some(inverse);
+}
+
+/// Composition of two permutations of 1..=n, applying b first then a, i.e
+/// the result maps i to a[b[i]]. E.g composing [2,3,1] with [2,1,3] gives [3,2,1].
+///
+/// None if either isn't a permutation of 1..=n or their lengths differ.
+pub fn compose(a: &[usize], b: &[usize]) -> Option<Vec<usize>> {
+    if a.len() != b.len() || !is_permutation(a) || !is_permutation(b) {
+        return None;
+    }
+    return Some(b.iter().map(|token| a[token - 1]).collect());
+}
+
+/// invert for the permutation of 1..=n with the lexicographic rank given
+pub fn invert_rank(rank: usize, n: usize) -> Option<usize> {
+    let tokens: Vec<usize> = (1..n + 1).collect();
+    return lex_rank(&invert(&lex_unrank(rank, &tokens)?)?);
+}
+
+/// compose for the permutations of 1..=n with the lexicographic ranks given
+pub fn compose_ranks(a: usize, b: usize, n: usize) -> Option<usize> {
+    let tokens: Vec<usize> = (1..n + 1).collect();
+    return lex_rank(&compose(&lex_unrank(a, &tokens)?, &lex_unrank(b, &tokens)?)?);
+}
+
+/// Disjoint cycle decomposition of a permutation of 1..=n, where each token i
+/// is sent to perm[i]. Each cycle starts with its smallest token, cycles are
+/// ordered by their first token and fixed points are included as cycles of 1.
+///
+/// E.g [2,3,1,5,4,6] has the cycles [[1,2,3], [4,5], [6]]. None if perm is
+/// not a permutation of 1..=n.
+pub fn cycles(perm: &[usize]) -> Option<Vec<Vec<usize>>> {
+    if !is_permutation(perm) {
+        return None;
+    }
+    let mut visited = vec![false; perm.len()];
+    let mut output = Vec::new();
+    for start in 1..perm.len() + 1 {
+        if visited[start - 1] {
+            continue;
+        }
+        let mut cycle = Vec::new();
+        let mut token = start;
+        while visited[token - 1] == false {
+            visited[token - 1] = true;
+            cycle.push(token);
+            token = perm[token - 1];
+        }
+        output.push(cycle);
+    }
+    return Some(output);
+}
+
+/// Lengths of the cycles of a permutation of 1..=n in descending order, which
+/// is a partition of n. E.g [2,3,1,5,4,6] has the cycle type [3,2,1].
+pub fn cycle_type(perm: &[usize]) -> Option<Vec<usize>> {
+    let mut lengths: Vec<usize> = cycles(perm)?.iter().map(|cycle| cycle.len()).collect();
+    lengths.sort_unstable_by(|a, b| b.cmp(a));
+    return Some(lengths);
+}
+
+/// Whether a permutation is made from an even or odd amount of swaps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Parity {
+    Even,
+    Odd,
+}
+
+impl Parity {
+    /// +1 for even permutations and -1 for odd ones
+    pub fn sign(&self) -> i32 {
+        match self {
+            Parity::Even => 1,
+            Parity::Odd => -1,
+        }
+    }
+}
+
+/// Parity of a permutation of distinct tokens, relative to the tokens in
+/// ascending order. The sum of the Lehmer code is the amount of inversions,
+/// whose parity is the parity of the permutation.
+///
+/// E.g [1,2,3] and [2,3,1] are even, [2,1,3] is odd. None if any token is repeated.
+pub fn parity(perm: &[usize]) -> Option<Parity> {
+    let inversions: usize = lehmer_code(perm)?.digits.iter().sum();
+    if inversions.is_multiple_of(2) {
+        return Some(Parity::Even);
+    }
+    return Some(Parity::Odd);
+}
+
+/// Order in which PermutationMapper ranks the permutations of its sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RankOrder {
+    /// The crate's original order, built from the amount each token is shifted
+    /// along when inserted. E.g for [1,2,3], rank 1 is [2,1,3]
+    #[default]
+    InsertionShift,
+    /// Standard lexicographic order by position in the sequence given. E.g for
+    /// [1,2,3], rank 1 is [1,3,2]
+    Lexicographic,
+}
+
+/// Class to help with the handling of permutations for an arbitrary sequence.
+/// Tokens of the sequence must be distinct, see multiset::MultisetMapper otherwise.
+///
+/// Tokens can be of any type that can be compared for equality, e.g chars or
+/// strings, though the superpermutation algorithms use usize tokens.
+#[derive(Debug, Clone)]
+pub struct PermutationMapper<T = usize> {
+    pub core_sequence: Vec<T>,
+    pub mixed_radix_sys: MixedRadix,
+    pub order: RankOrder,
+    /// Display name of each token of the core sequence, if given. See builder
+    pub names: Option<Vec<String>>,
+}
+impl<T: Clone + PartialEq> PermutationMapper<T> {
+    // Why use MixedRadix for permutations?
+    // Imagine the sequence [1, 2, 3] and all its permutations.
+    // When creating a permutation, we have 3 positions to fill up.
+    // We insert "1" in one of the three positions. Two positions are left (e.g [_, 1, _])
+    // We insert "2" in one of the two positions. One position are left (e.g [2, 1, _])
+    // We insert "3" in the final position. Permutation is complete (e.g [2, 1, 3])
+    // With this logic, each permutation can be represented as a number in a mixed radix system.
+    // [1, 2, 3] be represented as (0, 0, 0) or 0
+    // [2, 1, 3] be represented as (1, 0, 0) or 1
+    // [3, 2, 1] be represented as (2, 1, 0) or 5
+
+    /// Vector passed in defines the sequence of tokens that all permutations will be built from.
+    /// 
+    /// This vector will be set as the "0th" permutations.
+    /// 
+    /// E.g passing in the vector [1,2,3] will focus on its permutations (i.e [2,1,3], [3,1,2], etc)
+    pub fn new(sequence: Vec<T>) -> PermutationMapper<T> {
+        return PermutationMapper::with_order(sequence, RankOrder::InsertionShift);
+    }
+
+    /// Same as new but with the order permutations are ranked in given
+    pub fn with_order(sequence: Vec<T>, order: RankOrder) -> PermutationMapper<T> {
+        let bases: Vec<usize> = (1..sequence.len()+1).rev().collect();
+        let obj = PermutationMapper{
+            core_sequence: sequence,
+            mixed_radix_sys: MixedRadix::new(bases),
+            order,
+            names: None,
+        };
+        return obj;
+    }
+
+    /// Starts building a mapper for the sequence, to also set the order and
+    /// display names of the tokens. Unlike new, the tokens are checked to be
+    /// distinct. E.g
+    /// PermutationMapper::builder(vec!['a','b']).names(["apple", "banana"]).build()
+    pub fn builder(sequence: Vec<T>) -> PermutationMapperBuilder<T> {
+        return PermutationMapperBuilder { core_sequence: sequence, order: RankOrder::default(), names: None };
+    }
+
+    /// Display name of a token of the core sequence. None if no names were
+    /// given or the token isn't in the core sequence
+    pub fn name_of(&self, token: &T) -> Option<&str> {
+        let index = self.core_sequence.iter().position(|t| t == token)?;
+        return Some(self.names.as_ref()?[index].as_str());
+    }
+
+    /// Display names of each token of a permutation. See name_of
+    pub fn perm_names(&self, perm: &[T]) -> Option<Vec<&str>> {
+        return perm.iter().map(|token| self.name_of(token)).collect();
+    }
+
+    /// Reads a value and maps it to a distinct permutation.
+    /// Passing in 0 will output the same sequence given at instantiation.
+    pub fn value_to_perm(&self, value: &usize) -> Vec<T> {
+        if self.order == RankOrder::Lexicographic {
+            // Unrank positions into the core sequence so the core sequence is rank 0
+            let positions: Vec<usize> = (0..self.core_sequence.len()).collect();
+            let perm_positions = lex_unrank(*value, &positions).expect("value out of range");
+            return perm_positions.iter().map(|pos| self.core_sequence[*pos].clone()).collect();
+        }
+        // Convert the value to a useful MixedRadix number
+        let repr = self.mixed_radix_sys.encode_value(value);
+        return self.repr_to_perm(&repr);
+    }
+    /// Builds the permutation for an insertion shift representation
+    fn repr_to_perm(&self, repr: &MixedRadixRepr) -> Vec<T> {
+        // pos_is_filled tracks which positions have been filled so no token
+        // value needs to be reserved to mark empty positions
+        let mut output_perm: Vec<Option<T>> = vec![None; self.core_sequence.len()];
+        let mut pos_is_filled: Vec<bool> = vec![false; self.core_sequence.len()];
+        for (pos, token) in self.core_sequence.iter().enumerate() {
+            let shift = repr[pos];
+            let mut ind: usize = 0;
+
+            // Use each "digit" in the mixedradix representation to know how much to shift along
+            // before inserting.
+
+            // Skip to first non-filled position
+            while pos_is_filled[ind] == true {
+                ind += 1;
+            }
+            // For each shift
+            for _ in 0..shift {
+                // Move along 1
+                ind += 1;
+                // Skip to next non-filled position
+                while pos_is_filled[ind] == true {
+                    ind += 1;
+                }
+            }
+            output_perm[ind] = Some(token.clone());
+            pos_is_filled[ind] = true;
+        }
+        return output_perm.into_iter().flatten().collect();
+    }
+    /// Reads a permutation and maps it to a distinct value.
+    /// Can be thought of as the inverse of value_to_perm.
+    /// 
+    /// The value resulting from this method should be able to be passed
+    /// back into value_to_perm to get back the original permutation.
+    /// 
+    /// A return of None indicates that the permutation passed in is not a
+    /// valid permutation of the sequence given at instantiation.
+    pub fn perm_to_value(&self, permutation: &[T]) -> Option<usize> {
+        if self.order == RankOrder::Lexicographic {
+            if permutation.len() != self.core_sequence.len() {
+                return None;
+            }
+            let mut perm_positions = Vec::with_capacity(permutation.len());
+            for token in permutation {
+                perm_positions.push(self.core_sequence.iter().position(|t| t == token)?);
+            }
+            return lex_rank(&perm_positions);
+        }
+        let repr = self.perm_to_repr(permutation)?;
+        return Some(self.mixed_radix_sys.decode_representation(&repr));
+    }
+    /// Same as perm_to_value but gives the mixed radix representation of the
+    /// value in the insertion shift order instead
+    fn perm_to_repr(&self, permutation: &[T]) -> Option<MixedRadixRepr> {
+        // if the lengths of core_sequence and permutation doesn't match then
+        // mapping to a value is obviously impossible
+        if permutation.len() != self.core_sequence.len() {
+            return None;
+        }
+        
+        let mut repr: MixedRadixRepr = Vec::with_capacity(self.core_sequence.len());
+        let mut pos_is_filled: Vec<bool> = vec![false; self.core_sequence.len()];
+        let max_ind = self.core_sequence.len();
+
+        for token in self.core_sequence.iter() {
+            let mut shift = 0;
+            let mut ind = 0;
+            // move index to first unfilled position
+            while ind < max_ind && pos_is_filled[ind] == true {
+                ind += 1;
+            }
+            // keep shifting index
+            while ind < max_ind && *token != permutation[ind] {
+                // keep track of shifts
+                ind += 1;
+                shift += 1;
+                // autoskip over filled positions
+                while ind < max_ind && pos_is_filled[ind] == true {
+                    ind += 1;
+                }
+            }
+            // if the index rolls off the "edge" whilst looking for a token match
+            // then the permutation passed initially passed in is impossible to map to
+            if ind >= max_ind {
+                return None;
+            }
+            // keep track which position has been filled
+            pos_is_filled[ind] = true;
+            // store the number of shifts
+            repr.push(shift);
+        }
+        return Some(repr);
+    }
+
+    /// Returns a vector of values in which if they were passed into value_to_perm,
+    /// the resulting permutation would match the perm_target.
+    /// 
+    /// The permutation target can be shorter than the sequence passed in at instantiation.
+    /// If this is the case, this method will look for permutations whose starting elements matches
+    /// the perm_target.
+    pub fn possible_values_for(&self, perm_target: &Vec<T>) -> Vec<usize> {
+        if self.order == RankOrder::Lexicographic {
+            return self.possible_lex_values_for(perm_target);
+        }
+        let n = perm_target.len();
+        // Check for empty perm_target, this means all permutations "fit" the target
+        if n == 0 {
+            return (1..self.mixed_radix_sys.max_value).collect();
+        }
+        
+        // if no value can be mapped for the bounds then no values are possible
+        let Some((min_perm, max_perm)) = self.prefix_bounds(perm_target) else {
+            return vec![];
+        };
+        let (Some(min_repr), Some(max_repr)) = (self.perm_to_repr(&min_perm), self.perm_to_repr(&max_perm)) else {
+            return vec![];
+        };
+        
+        // Use max and min representations to get range of possible representations
+        let sys = MixedRadix::new(
+            max_repr
+                .iter()
+                .zip(min_repr.clone())
+                .map(|(max, min)| max+1-min)
+                .collect()
+        );
+        // Iterate through all the representations that fit between max and min repr and store
+        // the associated value. (Keep note of the difference between "representation" and "value")
+        let mut values = Vec::with_capacity(sys.max_value);
+        for offset in sys.into_iter() {
+            // Each digit of the offset is at most max-min so no carries occur
+            let Some(cur_repr) = self.mixed_radix_sys.checked_add(&min_repr, &offset) else {
+                break;
+            };
+            values.push(self.mixed_radix_sys.decode_representation(&cur_repr));
+        }
+
+        return values;
+    }
+
+    /// Iterates over every permutation in order of rank, starting from the
+    /// core sequence. Each permutation is built by updating the previous one
+    /// rather than from its value.
+    pub fn iter(&self) -> PermutationIter<'_, T> {
+        return PermutationIter {
+            mapper: self,
+            rank: 0,
+            repr: vec![0; self.core_sequence.len()],
+            perm: self.core_sequence.clone(),
+            positions: (0..self.core_sequence.len()).collect(),
+        };
+    }
+
+    /// Picks a permutation uniformly at random, returning its value along with
+    /// it. The same seeded rng always gives the same permutation.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> (usize, Vec<T>) {
+        let value = rng.gen_range(0..self.mixed_radix_sys.max_value);
+        return (value, self.value_to_perm(&value));
+    }
+
+    /// The first and last permutations, in either order, that start with the
+    /// prefix given. None if no permutation starts with the prefix.
+    fn prefix_bounds(&self, prefix: &Vec<T>) -> Option<(Vec<T>, Vec<T>)> {
+        if prefix.len() > self.core_sequence.len() {
+            return None;
+        }
+        let mut core_leftover = self.core_sequence.clone();
+        core_leftover.retain(|x| !prefix.contains(x));
+
+        // Leftover tokens in their original order give the "minimum" permutation
+        // and in reverse the "maximum" permutation
+        let mut min_perm = prefix.clone();
+        min_perm.extend(core_leftover.iter().cloned());
+        let mut max_perm = prefix.clone();
+        max_perm.extend(core_leftover.iter().rev().cloned());
+        return Some((min_perm, max_perm));
+    }
+
+    /// possible_values_for in lexicographic order. Permutations sharing the
+    /// same starting elements are neighbours in lexicographic order so the
+    /// values form a single range.
+    fn possible_lex_values_for(&self, perm_target: &Vec<T>) -> Vec<usize> {
+        let Some((min_perm, max_perm)) = self.prefix_bounds(perm_target) else {
+            return vec![];
+        };
+        let (Some(min_value), Some(max_value)) = (self.perm_to_value(&min_perm), self.perm_to_value(&max_perm)) else {
+            return vec![];
+        };
+        return (min_value..max_value + 1).collect();
+    }
+
+    /// Lazily iterates over the permutations starting with the prefix given,
+    /// along with their values, in increasing order of value. Unlike
+    /// possible_values_for nothing is computed for permutations not reached.
+    ///
+    /// E.g the prefix [2] for the sequence [1,2,3] yields (1, [2,1,3]) then (2, [2,3,1])
+    pub fn perms_with_prefix(&self, prefix: &Vec<T>) -> PrefixPermIter<'_, T> {
+        let mut iter = PrefixPermIter {
+            mapper: self,
+            remaining: 0,
+            min_repr: Vec::new(),
+            offsets: MixedRadix::new(vec![]),
+            offset: Vec::new(),
+            rank: 0,
+            positions: Vec::new(),
+        };
+        let Some((min_perm, max_perm)) = self.prefix_bounds(prefix) else {
+            return iter;
+        };
+        match self.order {
+            RankOrder::InsertionShift => {
+                let (Some(min_repr), Some(max_repr)) = (self.perm_to_repr(&min_perm), self.perm_to_repr(&max_perm)) else {
+                    return iter;
+                };
+                iter.offsets = MixedRadix::new(max_repr.iter().zip(&min_repr).map(|(max, min)| max+1-min).collect());
+                iter.offset = vec![0; min_repr.len()];
+                iter.remaining = iter.offsets.max_value;
+                iter.min_repr = min_repr;
+            }
+            RankOrder::Lexicographic => {
+                let (Some(min_value), Some(max_value)) = (self.perm_to_value(&min_perm), self.perm_to_value(&max_perm)) else {
+                    return iter;
+                };
+                iter.positions = min_perm
+                    .iter()
+                    .map(|token| self.core_sequence.iter().position(|t| t == token).unwrap())
+                    .collect();
+                iter.rank = min_value;
+                iter.remaining = max_value + 1 - min_value;
+            }
+        }
+        return iter;
+    }
+
+    /// The permutations ending with the suffix given, along with their values,
+    /// in increasing order of value. Empty if no permutation ends with the
+    /// suffix. The counterpart of perms_with_prefix for growing a sequence
+    /// leftwards, though everything is computed up front.
+    ///
+    /// E.g the suffix [2,1] for the sequence [1,2,3] gives just [3,2,1]
+    pub fn perms_with_suffix(&self, suffix: &[T]) -> Vec<(usize, Vec<T>)> {
+        let mut remaining: Vec<T> = self.core_sequence.clone();
+        for token in suffix {
+            let Some(pos) = remaining.iter().position(|t| t == token) else {
+                return Vec::new();
+            };
+            remaining.remove(pos);
+        }
+        let mut perms: Vec<(usize, Vec<T>)> = PermutationMapper::new(remaining)
+            .iter()
+            .map(|mut perm| {
+                perm.extend_from_slice(suffix);
+                (self.perm_to_value(&perm).unwrap(), perm)
+            })
+            .collect();
+        perms.sort_by_key(|(value, _)| *value);
+        return perms;
+    }
+
+    /// Ranks many permutations at once, replacing the contents of out with
+    /// their values in the same order.
+    ///
+    /// Errors with the index of the first sequence that isn't a permutation
+    /// of the core sequence, leaving out with the values before it.
+    pub fn ranks_of<P: AsRef<[T]>>(&self, perms: &[P], out: &mut Vec<usize>) -> Result<(), usize> {
+        out.clear();
+        out.reserve(perms.len());
+        for (i, perm) in perms.iter().enumerate() {
+            out.push(self.perm_to_value(perm.as_ref()).ok_or(i)?);
+        }
+        return Ok(());
+    }
+
+    /// Inverse of ranks_of. Replaces the contents of out with the permutation
+    /// of each value one after the other, so permutation i is
+    /// out[i*len..(i+1)*len] where len is the length of the core sequence.
+    pub fn perms_of(&self, values: &[usize], out: &mut Vec<T>) {
+        out.clear();
+        out.reserve(values.len() * self.core_sequence.len());
+        for value in values {
+            out.extend(self.value_to_perm(value));
+        }
+    }
+}
+
+/// Errors that can arise when building a PermutationMapper
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MapperError {
+    /// The token at index is equal to an earlier token
+    RepeatedToken { index: usize },
+    /// The amount of display names doesn't match the amount of tokens
+    NameCount { tokens: usize, names: usize },
+}
+
+impl std::fmt::Display for MapperError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MapperError::RepeatedToken { index } => write!(f, "token at index {} is repeated", index),
+            MapperError::NameCount { tokens, names } => {
+                write!(f, "{} display names given for {} tokens", names, tokens)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MapperError {}
+
+/// Configures a PermutationMapper before creating it. Created by
+/// PermutationMapper::builder
+#[derive(Debug, Clone)]
+pub struct PermutationMapperBuilder<T = usize> {
+    core_sequence: Vec<T>,
+    order: RankOrder,
+    names: Option<Vec<String>>,
+}
+
+impl<T: Clone + PartialEq> PermutationMapperBuilder<T> {
+    /// Order to rank the permutations in, InsertionShift by default
+    pub fn order(mut self, order: RankOrder) -> PermutationMapperBuilder<T> {
+        self.order = order;
+        return self;
+    }
+
+    /// Display name of each token, in the order of the core sequence
+    pub fn names<I: IntoIterator<Item = S>, S: Into<String>>(mut self, names: I) -> PermutationMapperBuilder<T> {
+        self.names = Some(names.into_iter().map(Into::into).collect());
+        return self;
+    }
+
+    /// Creates the mapper, failing if a token is repeated or the amount of
+    /// names doesn't match the amount of tokens
+    pub fn build(self) -> Result<PermutationMapper<T>, MapperError> {
+        for index in 1..self.core_sequence.len() {
+            if self.core_sequence[..index].contains(&self.core_sequence[index]) {
+                return Err(MapperError::RepeatedToken { index });
+            }
+        }
+        if let Some(names) = &self.names {
+            if names.len() != self.core_sequence.len() {
+                return Err(MapperError::NameCount { tokens: self.core_sequence.len(), names: names.len() });
+            }
+        }
+        let mut mapper = PermutationMapper::with_order(self.core_sequence, self.order);
+        mapper.names = self.names;
+        return Ok(mapper);
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T: Clone + PartialEq + Send + Sync> PermutationMapper<T> {
+    /// Parallel version of iter. The range of ranks is split across rayon's
+    /// threads and each permutation is built from its value, so the output is
+    /// in the same order as iter.
+    ///
+    /// Requires the `rayon` feature.
+    pub fn par_iter(&self) -> impl IndexedParallelIterator<Item = Vec<T>> + '_ {
+        return (0..self.mixed_radix_sys.max_value).into_par_iter().map(move |value| self.value_to_perm(&value));
+    }
+
+    /// Parallel version of ranks_of
+    pub fn par_ranks_of<P: AsRef<[T]> + Sync>(&self, perms: &[P], out: &mut Vec<usize>) -> Result<(), usize> {
+        out.clear();
+        // No permutation has the value usize::MAX as there are at most usize::MAX of them
+        out.par_extend(perms.par_iter().map(|perm| self.perm_to_value(perm.as_ref()).unwrap_or(usize::MAX)));
+        if let Some(invalid) = out.iter().position(|value| *value == usize::MAX) {
+            out.truncate(invalid);
+            return Err(invalid);
+        }
+        return Ok(());
+    }
+
+    /// Parallel version of perms_of
+    pub fn par_perms_of(&self, values: &[usize], out: &mut Vec<T>) {
+        out.clear();
+        out.par_extend(values.par_iter().flat_map_iter(|value| self.value_to_perm(value)));
+    }
+}
+
+/// Iterator over the permutations of a PermutationMapper's sequence in order
+/// of rank. Created by PermutationMapper::iter.
+pub struct PermutationIter<'a, T> {
+    mapper: &'a PermutationMapper<T>,
+    /// Rank of the next permutation to yield
+    rank: usize,
+    /// Insertion shift representation of the next permutation
+    repr: MixedRadixRepr,
+    /// The next permutation in insertion shift order
+    perm: Vec<T>,
+    /// Positions into the core sequence of the next permutation, in lexicographic order
+    positions: Vec<usize>,
+}
+impl<'a, T: Clone + PartialEq> PermutationIter<'a, T> {
+    /// Steps perm along to the next insertion shift representation in place.
+    ///
+    /// The digits below the first that doesn't carry are at their largest,
+    /// so their tokens fill the end of the permutation in reverse, and after
+    /// wrapping to 0 they fill the start in order. The rest of the tokens keep
+    /// their relative places apart from the token of the incremented digit,
+    /// which moves along one place.
+    fn step_insertion_shift(&mut self) {
+        let bases = &self.mapper.mixed_radix_sys.bases;
+        let carried = self.repr.iter().zip(bases).take_while(|(digit, base)| **digit + 1 == **base).count();
+        if carried < self.repr.len() {
+            let n = self.perm.len();
+            let shift = self.repr[carried];
+            self.perm[n - carried..].reverse();
+            self.perm.rotate_right(carried);
+            self.perm.swap(carried + shift, carried + shift + 1);
+        }
+        self.mapper.mixed_radix_sys.increment(&mut self.repr);
+    }
+}
+impl<'a, T: Clone + PartialEq> Iterator for PermutationIter<'a, T> {
+    type Item = Vec<T>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rank >= self.mapper.mixed_radix_sys.max_value {
+            return None;
+        }
+        self.rank += 1;
+        match self.mapper.order {
+            RankOrder::InsertionShift => {
+                let output = self.perm.clone();
+                self.step_insertion_shift();
+                return Some(output);
+            }
+            RankOrder::Lexicographic => {
+                let output = self.positions.iter().map(|pos| self.mapper.core_sequence[*pos].clone()).collect();
+                next_permutation(&mut self.positions);
+                return Some(output);
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.mapper.mixed_radix_sys.max_value - self.rank;
+        return (remaining, Some(remaining));
+    }
+
+    /// Jumps straight to the permutation n ranks on rather than stepping
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        let max_value = self.mapper.mixed_radix_sys.max_value;
+        self.rank = self.rank.saturating_add(n).min(max_value);
+        if self.rank < max_value {
+            match self.mapper.order {
+                RankOrder::InsertionShift => {
+                    self.repr = self.mapper.mixed_radix_sys.encode_value(&self.rank);
+                    self.perm = self.mapper.repr_to_perm(&self.repr);
+                }
+                RankOrder::Lexicographic => {
+                    let positions: Vec<usize> = (0..self.positions.len()).collect();
+                    self.positions = lex_unrank(self.rank, &positions).expect("rank out of range");
+                }
+            }
+        }
+        return self.next();
+    }
+}
+impl<'a, T: Clone + PartialEq> ExactSizeIterator for PermutationIter<'a, T> {}
+impl<'a, T: Clone + PartialEq> std::iter::FusedIterator for PermutationIter<'a, T> {}
+impl<'a, T: Clone + PartialEq> IntoIterator for &'a PermutationMapper<T> {
+    type Item = Vec<T>;
+    type IntoIter = PermutationIter<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        return self.iter();
+    }
+}
+
+/// Iterator over the permutations starting with a prefix, along with their
+/// values. Created by PermutationMapper::perms_with_prefix.
+pub struct PrefixPermIter<'a, T> {
+    mapper: &'a PermutationMapper<T>,
+    /// Amount of permutations left to yield
+    remaining: usize,
+    /// Insertion shift representation of the first permutation with the prefix
+    min_repr: MixedRadixRepr,
+    /// System of the digit ranges between the first and last permutation
+    offsets: MixedRadix,
+    /// Offset from min_repr of the next permutation
+    offset: MixedRadixRepr,
+    /// Lexicographic rank of the next permutation
+    rank: usize,
+    /// Positions into the core sequence of the next permutation, in lexicographic order
+    positions: Vec<usize>,
+}
+impl<'a, T: Clone + PartialEq> Iterator for PrefixPermIter<'a, T> {
+    type Item = (usize, Vec<T>);
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        match self.mapper.order {
+            RankOrder::InsertionShift => {
+                // Each digit of the offset is at most max-min so no carries occur
+                let system = &self.mapper.mixed_radix_sys;
+                let repr = system.checked_add(&self.min_repr, &self.offset)?;
+                self.offsets.increment(&mut self.offset);
+                return Some((system.decode_representation(&repr), self.mapper.repr_to_perm(&repr)));
+            }
+            RankOrder::Lexicographic => {
+                let output = self.positions.iter().map(|pos| self.mapper.core_sequence[*pos].clone()).collect();
+                next_permutation(&mut self.positions);
+                self.rank += 1;
+                return Some((self.rank - 1, output));
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        return (self.remaining, Some(self.remaining));
+    }
+}
+impl<'a, T: Clone + PartialEq> ExactSizeIterator for PrefixPermIter<'a, T> {}
+impl<'a, T: Clone + PartialEq> std::iter::FusedIterator for PrefixPermIter<'a, T> {}
+
+/// Iterates over every permutation of the tokens given in Steinhaus-Johnson-Trotter
+/// order, where each permutation is the one before it with two neighbouring
+/// tokens swapped. Tokens must be distinct.
+///
+/// E.g [1,2,3] gives [1,2,3], [1,3,2], [3,1,2], [3,2,1], [2,3,1], [2,1,3]
+pub fn sjt_iter(tokens: &[usize]) -> SjtIter {
+    let total = (1..tokens.len() + 1).product();
+    return SjtIter {
+        tokens: tokens.to_vec(),
+        order: (0..tokens.len()).collect(),
+        directions: vec![-1; tokens.len()],
+        remaining: total,
+        started: false,
+    };
+}
+
+/// Iterator over permutations in Steinhaus-Johnson-Trotter order. Each
+/// permutation comes with the position i where the tokens at i and i+1 were
+/// swapped, which is None for the first. Created by sjt_iter.
+pub struct SjtIter {
+    tokens: Vec<usize>,
+    /// Positions into tokens of the permutation most recently yielded. Swaps
+    /// are decided by comparing these rather than the tokens themselves
+    order: Vec<usize>,
+    /// Direction each element of order is moving in, indexed by element
+    directions: Vec<isize>,
+    /// Amount of permutations left to yield
+    remaining: usize,
+    started: bool,
+}
+impl Iterator for SjtIter {
+    type Item = (Vec<usize>, Option<usize>);
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        if self.started {
+            // Move the largest element whose neighbour in its direction is smaller
+            let n = self.order.len() as isize;
+            let mut mobile: Option<usize> = None;
+            for (pos, element) in self.order.iter().enumerate() {
+                let neighbour = pos as isize + self.directions[*element];
+                if neighbour < 0 || neighbour >= n || self.order[neighbour as usize] > *element {
+                    continue;
+                }
+                if mobile.is_none_or(|m| *element > self.order[m]) {
+                    mobile = Some(pos);
+                }
+            }
+            let pos = mobile?;
+            let element = self.order[pos];
+            let neighbour = (pos as isize + self.directions[element]) as usize;
+            self.order.swap(pos, neighbour);
+            // Larger elements turn around
+            for larger in element + 1..self.order.len() {
+                self.directions[larger] = -self.directions[larger];
+            }
+            let perm = self.order.iter().map(|i| self.tokens[*i]).collect();
+            return Some((perm, Some(pos.min(neighbour))));
+        }
+        self.started = true;
+        return Some((self.tokens.clone(), None));
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        return (self.remaining, Some(self.remaining));
+    }
+}
+impl ExactSizeIterator for SjtIter {}
+impl std::iter::FusedIterator for SjtIter {}
+
+/// Generates every permutation of a sequence with Heap's algorithm, where each
+/// permutation is the one before it with a single pair of tokens swapped.
+/// Permutations are written into one reusable buffer rather than allocated
+/// separately, so they are lent out by next_perm instead of iterated over.
+///
+/// E.g [1,2,3] gives [1,2,3], [2,1,3], [3,1,2], [1,3,2], [2,3,1], [3,2,1]
+pub struct HeapPermutations {
+    perm: Vec<usize>,
+    /// Per position loop counters of the iterative form of the algorithm
+    counters: Vec<usize>,
+    /// Position currently being worked on
+    i: usize,
+    started: bool,
+}
+impl HeapPermutations {
+    /// The tokens given are the first permutation
+    pub fn new(tokens: Vec<usize>) -> HeapPermutations {
+        return HeapPermutations {
+            counters: vec![0; tokens.len()],
+            perm: tokens,
+            i: 1,
+            started: false,
+        };
+    }
+
+    /// Moves on to the next permutation and returns it. None once every
+    /// permutation has been given.
+    pub fn next_perm(&mut self) -> Option<&[usize]> {
+        if !self.started {
+            self.started = true;
+            return Some(&self.perm);
+        }
+        while self.i < self.perm.len() {
+            let i = self.i;
+            if self.counters[i] < i {
+                if i.is_multiple_of(2) {
+                    self.perm.swap(0, i);
+                } else {
+                    self.perm.swap(self.counters[i], i);
+                }
+                self.counters[i] += 1;
+                self.i = 1;
+                return Some(&self.perm);
+            }
+            self.counters[i] = 0;
+            self.i += 1;
+        }
+        return None;
+    }
+}
+
+/// Rearranges the tokens into the next permutation in lexicographic order, in
+/// the same way as C++'s std::next_permutation. If the tokens are already the
+/// last permutation (descending order) they wrap around to the first
+/// (ascending order) and false is returned. Repeated tokens are fine.
+///
+/// E.g [1,3,2] becomes [2,1,3] and [3,2,1] becomes [1,2,3]
+pub fn next_permutation(tokens: &mut [usize]) -> bool {
+    // Find the last ascent, everything after it is in descending order
+    let Some(pivot) = (1..tokens.len()).rev().find(|i| tokens[i - 1] < tokens[*i]).map(|i| i - 1) else {
+        tokens.reverse();
+        return false;
+    };
+    // Swap the pivot with the smallest larger token after it, then put the
+    // tail back into ascending order
+    let successor = (pivot + 1..tokens.len()).rev().find(|i| tokens[*i] > tokens[pivot]).unwrap();
+    tokens.swap(pivot, successor);
+    tokens[pivot + 1..].reverse();
+    return true;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+
+    #[test]
+    fn lexicographic_ranking_matches_itertools() {
+        // itertools yields permutations of sorted input in lexicographic order
+        for (rank, perm) in (1..6).permutations(5).enumerate() {
+            assert_eq!(lex_rank(&perm), Some(rank));
+            assert_eq!(lex_unrank(rank, &[5,3,1,2,4]), Some(perm));
+        }
+        assert_eq!(lex_unrank(120, &[1,2,3,4,5]), None);
+        assert_eq!(lex_rank(&[1,2,1]), None);
+    }
+
+    #[test]
+    fn k_perm_ranking_matches_itertools() {
+        for (rank, perm) in (1..6).permutations(3).enumerate() {
+            assert_eq!(k_perm_rank(&perm, 5), Some(rank));
+            assert_eq!(k_perm_unrank(rank, 5, 3), Some(perm));
+        }
+        assert_eq!(k_perm_count(5, 3), Some(60));
+        assert_eq!(k_perm_unrank(60, 5, 3), None);
+        assert_eq!(k_perm_rank(&[2,3], 3), Some(3));
+        assert_eq!(k_perm_rank(&[1,4], 3), None);
+        assert_eq!(k_perm_rank(&[3,2,1], 3), lex_rank(&[3,2,1]));
+    }
+
+    #[test]
+    fn built_mappers() {
+        let mapper = PermutationMapper::builder(vec!['a','b','c'])
+            .order(RankOrder::Lexicographic)
+            .names(["apple", "banana", "cherry"])
+            .build()
+            .unwrap();
+        assert_eq!(mapper.value_to_perm(&1), vec!['a','c','b']);
+        assert_eq!(mapper.perm_names(&['c','a']), Some(vec!["cherry", "apple"]));
+        assert_eq!(mapper.name_of(&'d'), None);
+        assert_eq!(PermutationMapper::new(vec![1,2]).name_of(&1), None);
+
+        assert_eq!(PermutationMapper::builder(vec![1,2,1]).build().unwrap_err(), MapperError::RepeatedToken { index: 2 });
+        assert_eq!(
+            PermutationMapper::builder(vec![1,2]).names(["one"]).build().unwrap_err(),
+            MapperError::NameCount { tokens: 2, names: 1 }
+        );
+    }
+
+    #[test]
+    fn factorial_numbers() {
+        let number = FactorialNumber::from_rank(5, 3).unwrap();
+        assert_eq!(number.digits, vec![2,1,0]);
+        assert_eq!(number.rank(), 5);
+        assert_eq!(lehmer_code(&[3,2,1]), Some(number.clone()));
+        assert_eq!(checked_factorial(0), Some(1));
+        assert_eq!(checked_factorial(5), Some(120));
+        assert_eq!(checked_factorial(100), None);
+
+        let system = FactorialNumber::system(3);
+        assert_eq!(system.decode_representation(&number.to_repr()), 5);
+        assert_eq!(FactorialNumber::from_repr(&system.encode_value(&5)), number);
+        assert!(!FactorialNumber { digits: vec![0,2,0] }.is_valid());
+    }
+
+    #[test]
+    fn permutation_algebra() {
+        assert_eq!(invert(&[2,3,1]), Some(vec![3,1,2]));
+        assert_eq!(compose(&[2,3,1], &[2,1,3]), Some(vec![3,2,1]));
+        assert_eq!(invert(&[1,3,3]), None);
+        assert_eq!(compose(&[1,2], &[1,2,3]), None);
+        for perm in (1..5).permutations(4) {
+            let inverse = invert(&perm).unwrap();
+            assert_eq!(compose(&perm, &inverse), Some(vec![1,2,3,4]));
+            assert_eq!(compose(&inverse, &perm), Some(vec![1,2,3,4]));
+        }
+        for rank in 0..24 {
+            let inverse = invert_rank(rank, 4).unwrap();
+            assert_eq!(compose_ranks(rank, inverse, 4), Some(0));
+        }
+        assert_eq!(invert_rank(24, 4), None);
+    }
+
+    #[test]
+    fn permutation_parity() {
+        assert_eq!(parity(&[1,2,3]), Some(Parity::Even));
+        assert_eq!(parity(&[2,3,1]), Some(Parity::Even));
+        assert_eq!(parity(&[2,1,3]).map(|p| p.sign()), Some(-1));
+        assert_eq!(parity(&[1,1]), None);
+        // Half of the permutations of n > 1 tokens are even, and composing
+        // permutations multiplies their signs
+        let perms: Vec<Vec<usize>> = (1..5).permutations(4).collect();
+        assert_eq!(perms.iter().filter(|p| parity(p) == Some(Parity::Even)).count(), 12);
+        for a in &perms {
+            for b in &perms {
+                let composed = parity(&compose(a, b).unwrap()).unwrap();
+                assert_eq!(composed.sign(), parity(a).unwrap().sign() * parity(b).unwrap().sign());
+            }
+        }
+    }
+
+    #[test]
+    fn cycle_decomposition() {
+        assert_eq!(cycles(&[2,3,1,5,4,6]), Some(vec![vec![1,2,3], vec![4,5], vec![6]]));
+        assert_eq!(cycle_type(&[2,3,1,5,4,6]), Some(vec![3,2,1]));
+        assert_eq!(cycles(&[]), Some(vec![]));
+        assert_eq!(cycles(&[2,2]), None);
+        // A permutation is even exactly when n minus its cycle count is even
+        for perm in (1..6).permutations(5) {
+            let cycle_count = cycles(&perm).unwrap().len();
+            let even = (5 - cycle_count).is_multiple_of(2);
+            assert_eq!(parity(&perm) == Some(Parity::Even), even);
+        }
+    }
+
+    #[test]
+    fn sjt_order() {
+        let perms: Vec<Vec<usize>> = sjt_iter(&[1,2,3]).map(|(perm, _)| perm).collect();
+        assert_eq!(perms, vec![vec![1,2,3], vec![1,3,2], vec![3,1,2], vec![3,2,1], vec![2,3,1], vec![2,1,3]]);
+
+        let mut seen = std::collections::HashSet::new();
+        let mut previous: Option<Vec<usize>> = None;
+        for (perm, swap) in sjt_iter(&[4,1,3,2,5]) {
+            assert!(seen.insert(perm.clone()));
+            match previous {
+                Some(mut previous) => {
+                    previous.swap(swap.unwrap(), swap.unwrap() + 1);
+                    assert_eq!(previous, perm);
+                }
+                None => assert_eq!(swap, None),
+            }
+            previous = Some(perm);
+        }
+        assert_eq!(seen.len(), 120);
+    }
+
+    #[test]
+    fn heap_order() {
+        let mut heap = HeapPermutations::new(vec![1,2,3]);
+        let mut perms = Vec::new();
+        while let Some(perm) = heap.next_perm() {
+            perms.push(perm.to_vec());
+        }
+        assert_eq!(perms, vec![vec![1,2,3], vec![2,1,3], vec![3,1,2], vec![1,3,2], vec![2,3,1], vec![3,2,1]]);
+
+        let mut heap = HeapPermutations::new(vec![5,3,1,2,4,6]);
+        let mut seen = std::collections::HashSet::new();
+        let mut previous: Option<Vec<usize>> = None;
+        while let Some(perm) = heap.next_perm() {
+            if let Some(previous) = previous {
+                assert_eq!(previous.iter().zip(perm).filter(|(a, b)| a != b).count(), 2);
+            }
+            assert!(seen.insert(perm.to_vec()));
+            previous = Some(perm.to_vec());
+        }
+        assert_eq!(seen.len(), 720);
+        assert_eq!(heap.next_perm(), None);
+    }
+
+    #[test]
+    fn next_permutation_in_place() {
+        let mut perm = vec![1,3,2];
+        assert!(next_permutation(&mut perm));
+        assert_eq!(perm, vec![2,1,3]);
+        let mut perm = vec![3,2,1];
+        assert!(!next_permutation(&mut perm));
+        assert_eq!(perm, vec![1,2,3]);
+        let mut perm = vec![1,1,2];
+        let mut count = 1;
+        while next_permutation(&mut perm) {
+            count += 1;
+        }
+        assert_eq!(count, 3);
+        assert!(!next_permutation(&mut []));
+    }
+
+    #[test]
+    fn encode_and_decode_permutations() {
+        let helper = PermutationMapper::new((1..6).collect());
+
+        for i in 1..helper.mixed_radix_sys.max_value {
+            assert_eq!(
+                helper.perm_to_value(&helper.value_to_perm(&i)),
+                Some(i)
+            );
+        }
+        // Iterating gives the same permutations as mapping each value
+        for order in [RankOrder::InsertionShift, RankOrder::Lexicographic] {
+            let helper = PermutationMapper::with_order((1..6).collect(), order);
+            let perms = helper.iter();
+            assert_eq!(perms.len(), 120);
+            for (i, perm) in perms.enumerate() {
+                assert_eq!(perm, helper.value_to_perm(&i));
+            }
+            // Jumping ahead then stepping on matches too
+            let mut perms = helper.iter();
+            assert_eq!(perms.nth(37), Some(helper.value_to_perm(&37)));
+            assert_eq!(perms.next(), Some(helper.value_to_perm(&38)));
+            assert_eq!(perms.len(), 81);
+            assert_eq!(perms.nth(81), None);
+        }
+    }
+
+    #[test]
+    fn impossible_permutations() {
+        let helper = PermutationMapper::new((1..6).collect());
+        let impossible = vec![
+            vec![1,2,3,4,4],
+            vec![0,2,3,4,5],
+            vec![1,2,3,2,5],
+            vec![1,2,3,5],
+            vec![1,2,3,4,5,6],
+        ];
+        for imp in impossible {
+            println!("{:?}", imp);
+            assert_eq!(helper.perm_to_value(&imp), None);
+        }
+    }
+
+    #[test]
+    fn arbitrary_token_values() {
+        for order in [RankOrder::InsertionShift, RankOrder::Lexicographic] {
+            let mapper = PermutationMapper::with_order(vec![0,7,3,100], order);
+            for (value, perm) in mapper.iter().enumerate() {
+                let mut sorted = perm.clone();
+                sorted.sort();
+                assert_eq!(sorted, vec![0,3,7,100]);
+                assert_eq!(mapper.perm_to_value(&perm), Some(value));
+            }
+        }
+    }
+
+    #[test]
+    fn non_numeric_tokens() {
+        let mapper = PermutationMapper::with_order(vec!['a','b','c'], RankOrder::Lexicographic);
+        assert_eq!(mapper.value_to_perm(&3), vec!['b','c','a']);
+        assert_eq!(mapper.perm_to_value(&['c','b','a']), Some(5));
+        let words = PermutationMapper::new(vec![String::from("x"), String::from("y")]);
+        let perms: Vec<_> = words.perms_with_prefix(&vec![String::from("y")]).collect();
+        assert_eq!(perms, [(1, vec![String::from("y"), String::from("x")])]);
+    }
+
+    #[test]
+    fn mapper_orders() {
+        let shift = PermutationMapper::new(vec![1,2,3]);
+        let lex = PermutationMapper::with_order(vec![1,2,3], RankOrder::Lexicographic);
+        assert_eq!(shift.value_to_perm(&1), vec![2,1,3]);
+        assert_eq!(lex.value_to_perm(&1), vec![1,3,2]);
+        assert_eq!(lex.perm_to_value(&[3,2,1]), Some(5));
+        assert_eq!(lex.perm_to_value(&[1,2,2]), None);
+        assert_eq!(lex.possible_values_for(&vec![2]), vec![2,3]);
+
+        // The core sequence is rank 0 whatever order its tokens are in
+        let lex = PermutationMapper::with_order(vec![3,1,2], RankOrder::Lexicographic);
+        for value in 0..6 {
+            assert_eq!(lex.perm_to_value(&lex.value_to_perm(&value)), Some(value));
+        }
+        assert_eq!(lex.value_to_perm(&0), vec![3,1,2]);
+    }
+
+    #[test]
+    fn prefix_iteration_matches_possible_values() {
+        for order in [RankOrder::InsertionShift, RankOrder::Lexicographic] {
+            let mapper = PermutationMapper::with_order((1..6).collect(), order);
+            for prefix in [vec![3], vec![2,5], vec![5,4,3,2,1], vec![1,1], vec![6]] {
+                let (values, perms): (Vec<usize>, Vec<Vec<usize>>) = mapper.perms_with_prefix(&prefix).unzip();
+                assert_eq!(values, mapper.possible_values_for(&prefix));
+                for (value, perm) in values.iter().zip(perms) {
+                    assert!(perm.starts_with(&prefix));
+                    assert_eq!(mapper.value_to_perm(value), perm);
+                }
+            }
+        }
+        let mapper = PermutationMapper::new(vec![1,2,3]);
+        let perms: Vec<_> = mapper.perms_with_prefix(&vec![2]).collect();
+        assert_eq!(perms, [(1, vec![2,1,3]), (2, vec![2,3,1])]);
+    }
+
+    #[test]
+    fn suffix_matching() {
+        for order in [RankOrder::InsertionShift, RankOrder::Lexicographic] {
+            let mapper = PermutationMapper::with_order((1..6).collect(), order);
+            for suffix in [vec![3], vec![2,5], vec![5,4,3,2,1], vec![]] {
+                let expected: Vec<(usize, Vec<usize>)> = (0..120)
+                    .map(|value| (value, mapper.value_to_perm(&value)))
+                    .filter(|(_, perm)| perm.ends_with(&suffix))
+                    .collect();
+                assert_eq!(mapper.perms_with_suffix(&suffix), expected);
+            }
+            assert_eq!(mapper.perms_with_suffix(&[1,1]), vec![]);
+            assert_eq!(mapper.perms_with_suffix(&[6]), vec![]);
+        }
+        let mapper = PermutationMapper::new(vec![1,2,3]);
+        assert_eq!(mapper.perms_with_suffix(&[2,1]), vec![(5, vec![3,2,1])]);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_iteration() {
+        for order in [RankOrder::InsertionShift, RankOrder::Lexicographic] {
+            let mapper = PermutationMapper::with_order((1..6).collect(), order);
+            let serial: Vec<Vec<usize>> = mapper.iter().collect();
+            let parallel: Vec<Vec<usize>> = mapper.par_iter().collect();
+            assert_eq!(serial, parallel);
+
+            let (mut ranks, mut par_ranks) = (Vec::new(), Vec::new());
+            assert_eq!(mapper.ranks_of(&serial, &mut ranks), Ok(()));
+            assert_eq!(mapper.par_ranks_of(&serial, &mut par_ranks), Ok(()));
+            assert_eq!(ranks, par_ranks);
+            let (mut perms, mut par_perms) = (Vec::new(), Vec::new());
+            mapper.perms_of(&ranks, &mut perms);
+            mapper.par_perms_of(&ranks, &mut par_perms);
+            assert_eq!(perms, par_perms);
+            assert_eq!(mapper.par_ranks_of(&[vec![1,2,3,4,5], vec![1,1,1,1,1]], &mut par_ranks), Err(1));
+        }
+    }
+
+    #[test]
+    fn batch_ranking() {
+        let mapper = PermutationMapper::new((1..5).collect());
+        let values: Vec<usize> = vec![3, 0, 23, 7];
+        let mut perms = Vec::new();
+        mapper.perms_of(&values, &mut perms);
+        assert_eq!(perms.len(), 16);
+        let windows: Vec<&[usize]> = perms.chunks(4).collect();
+        let mut ranks = vec![99];
+        assert_eq!(mapper.ranks_of(&windows, &mut ranks), Ok(()));
+        assert_eq!(ranks, values);
+        assert_eq!(mapper.ranks_of(&[[1,2,3,4], [1,2,3,3]], &mut ranks), Err(1));
+        assert_eq!(ranks, vec![0]);
+    }
+
+    #[test]
+    fn seeded_sampling() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let mapper = PermutationMapper::new((1..6).collect());
+        let mut rng = StdRng::seed_from_u64(42);
+        let samples: Vec<_> = (0..50).map(|_| mapper.sample(&mut rng)).collect();
+        let mut rng = StdRng::seed_from_u64(42);
+        for (value, perm) in samples {
+            assert_eq!(mapper.perm_to_value(&perm), Some(value));
+            assert_eq!(mapper.sample(&mut rng), (value, perm));
+        }
+    }
+}
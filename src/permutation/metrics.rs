@@ -0,0 +1,73 @@
+// Distances between two permutations of the same distinct tokens.
+use crate::permutation::{cycles, is_permutation, lehmer_code};
+
+/// Where each token of a sits in b, as a permutation of 1..=n. None if a and b
+/// aren't permutations of the same distinct tokens.
+fn relative_perm(a: &[usize], b: &[usize]) -> Option<Vec<usize>> {
+    if a.len() != b.len() {
+        return None;
+    }
+    let mut positions = Vec::with_capacity(a.len());
+    for token in a {
+        positions.push(b.iter().position(|t| t == token)? + 1);
+    }
+    if !is_permutation(&positions) {
+        return None;
+    }
+    return Some(positions);
+}
+
+/// Kendall tau distance: the amount of pairs of tokens in a different order in
+/// a than in b, which is the least amount of swaps of neighbouring tokens
+/// turning a into b. E.g [1,2,3] and [3,1,2] are 2 apart.
+pub fn kendall_tau(a: &[usize], b: &[usize]) -> Option<usize> {
+    let relative = relative_perm(a, b)?;
+    return Some(lehmer_code(&relative)?.digits.iter().sum());
+}
+
+/// Cayley distance: the least amount of swaps of any two tokens turning a into
+/// b, which is n minus the amount of cycles of the permutation between them.
+/// E.g [1,2,3] and [3,2,1] are 1 apart.
+pub fn cayley(a: &[usize], b: &[usize]) -> Option<usize> {
+    let relative = relative_perm(a, b)?;
+    return Some(a.len() - cycles(&relative)?.len());
+}
+
+/// Overlap distance: the amount of tokens that need appending after a for b to
+/// appear, i.e n minus the longest suffix of a that is also a prefix of b.
+/// This is the cost of visiting b straight after a in a superpermutation.
+///
+/// E.g [1,2,3] to [2,3,1] is 1 and [1,2,3] to [3,2,1] is 2. Unlike the other
+/// distances it isn't symmetric.
+pub fn overlap(a: &[usize], b: &[usize]) -> usize {
+    let n = a.len().min(b.len());
+    let shared = (0..n + 1).rev().find(|len| a[a.len() - len..] == b[..*len]).unwrap_or(0);
+    return b.len() - shared;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+
+    #[test]
+    fn distances() {
+        assert_eq!(kendall_tau(&[1,2,3], &[3,1,2]), Some(2));
+        assert_eq!(kendall_tau(&[1,2,3], &[3,2,1]), Some(3));
+        assert_eq!(cayley(&[1,2,3], &[3,2,1]), Some(1));
+        assert_eq!(cayley(&[1,2,3], &[2,3,1]), Some(2));
+        assert_eq!(cayley(&[1,2,3], &[1,2,4]), None);
+        assert_eq!(overlap(&[1,2,3], &[2,3,1]), 1);
+        assert_eq!(overlap(&[1,2,3], &[3,2,1]), 2);
+        assert_eq!(overlap(&[1,2,3], &[1,2,3]), 0);
+
+        for a in (1..5).permutations(4) {
+            for b in (1..5).permutations(4) {
+                let tau = kendall_tau(&a, &b).unwrap();
+                assert_eq!(Some(tau), kendall_tau(&b, &a));
+                assert!(cayley(&a, &b).unwrap() <= tau);
+                assert_eq!(tau == 0, a == b);
+            }
+        }
+    }
+}
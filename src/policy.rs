@@ -0,0 +1,126 @@
+// Pluggable guidance for constructive solvers. At each step the solver
+// describes the sequence so far and the ways it could be extended, and a
+// Policy scores the extensions. The solver then takes the highest scoring
+// one. Handcrafted rules, learned models or anything else can be plugged in
+// without the solver changing. WeightedGreedy is greedy_with_policy with its
+// weights as the policy.
+//
+// Permutations are identified by their lexicographic rank, as in local_search.
+use crate::local_search::{order_to_sequence, Costs};
+use crate::permutation::checked_factorial;
+
+/// What a policy sees of the sequence being built
+#[derive(Debug, Clone, Copy)]
+pub struct State<'a> {
+    pub n: usize,
+    /// Permutations visited so far in order
+    pub order: &'a [usize],
+    /// Whether each permutation has been visited, indexed by rank
+    pub visited: &'a [bool],
+    /// Length of the sequence so far
+    pub length: usize,
+}
+
+/// Features of one way to extend the sequence: visiting a permutation next
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Extension {
+    pub rank: usize,
+    /// Tokens appended to visit it
+    pub cost: usize,
+    /// Unvisited permutations that could follow it at a cost of 1. Only its
+    /// rotation can, so this is 0 or 1
+    pub onward: usize,
+}
+
+/// Scores extensions of a sequence, higher being better
+pub trait Policy {
+    /// One score per extension, in the same order
+    fn scores(&self, state: &State<'_>, extensions: &[Extension]) -> Vec<f64>;
+}
+
+/// Any closure taking a state and extensions can be used as a Policy
+impl<F: Fn(&State<'_>, &[Extension]) -> Vec<f64>> Policy for F {
+    fn scores(&self, state: &State<'_>, extensions: &[Extension]) -> Vec<f64> {
+        return self(state, extensions);
+    }
+}
+
+/// The default handcrafted policy: cheapest extension first, preferring
+/// those leaving more cheap moves open afterwards
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CheapestFirst;
+
+impl Policy for CheapestFirst {
+    fn scores(&self, state: &State<'_>, extensions: &[Extension]) -> Vec<f64> {
+        // onward is below n, so it only ever breaks ties in cost
+        let n = state.n.max(1) as f64;
+        return extensions.iter().map(|extension| extension.onward as f64 / n - extension.cost as f64).collect();
+    }
+}
+
+/// Builds a superpermutation of (1,2,3, ...,n) from (1,2,3, ...,n) onwards,
+/// visiting whichever unvisited permutation the policy scores highest each
+/// step, ties going to the lowest rank. Only practical up to n=6, see
+/// local_search.
+pub fn greedy_with_policy<P: Policy + ?Sized>(n: usize, policy: &P) -> Vec<usize> {
+    if n == 0 {
+        return Vec::new();
+    }
+    let costs = Costs::new(n);
+    let size = checked_factorial(n).expect("n! overflows");
+    // The one permutation each can be followed by for a single token
+    let rotations: Vec<Option<usize>> =
+        (0..size).map(|rank| (0..size).find(|next| *next != rank && costs.cost(rank, *next) == 1)).collect();
+    let mut visited = vec![false; size];
+    visited[0] = true;
+    let mut order = vec![0];
+    let mut length = n;
+    let mut extensions = Vec::with_capacity(size);
+    while order.len() < size {
+        let current = *order.last().unwrap();
+        extensions.clear();
+        extensions.extend((0..size).filter(|rank| visited[*rank] == false).map(|rank| Extension {
+            rank,
+            cost: costs.cost(current, rank),
+            onward: rotations[rank].filter(|next| visited[*next] == false).map_or(0, |_| 1),
+        }));
+        let state = State { n, order: &order, visited: &visited, length };
+        let scores = policy.scores(&state, &extensions);
+        assert_eq!(scores.len(), extensions.len(), "policy gave the wrong amount of scores");
+        let mut chosen = 0;
+        for (i, score) in scores.iter().enumerate() {
+            if *score > scores[chosen] {
+                chosen = i;
+            }
+        }
+        let extension = extensions[chosen];
+        visited[extension.rank] = true;
+        order.push(extension.rank);
+        length += extension.cost;
+    }
+    return order_to_sequence(&order, n);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::SuperPermHandling;
+    use crate::bruteforce_optimise;
+
+    #[test]
+    fn policies() {
+        for n in 1..6 {
+            let sequence = greedy_with_policy(n, &CheapestFirst);
+            assert!(bruteforce_optimise::Handle.check_superperm(&sequence, n), "n={}", n);
+        }
+        assert_eq!(greedy_with_policy(4, &CheapestFirst).len(), 33);
+
+        // A closure that always picks the most expensive extension offered
+        let contrary = |_: &State<'_>, extensions: &[Extension]| {
+            extensions.iter().map(|extension| extension.cost as f64).collect::<Vec<f64>>()
+        };
+        let sequence = greedy_with_policy(4, &contrary);
+        assert!(bruteforce_optimise::Handle.check_superperm(&sequence, 4));
+        assert!(sequence.len() > 33);
+    }
+}
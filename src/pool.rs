@@ -0,0 +1,111 @@
+// Pool of the distinct solutions found over repeated runs of randomised
+// solvers, so they can report how many different sequences they reach and
+// how long they are rather than only their single best output.
+//
+// Sequences that are the same up to relabeling the tokens or reading them
+// backwards are counted as one solution. Each is stored in a canonical form:
+// the tokens are relabeled 1,2,3,... in order of first appearance, and of the
+// sequence and its reversal the lexicographically smaller result is kept.
+use std::collections::BTreeMap;
+
+/// Relabels the tokens 1,2,3,... in order of first appearance. E.g 2321 gives 1213
+fn relabel_by_appearance<'a, I: Iterator<Item = &'a usize>>(tokens: I) -> Vec<usize> {
+    let mut labels: Vec<(usize, usize)> = Vec::new();
+    let mut relabeled = Vec::new();
+    for token in tokens {
+        let label = match labels.iter().find(|(original, _)| original == token) {
+            Some((_, label)) => *label,
+            None => {
+                labels.push((*token, labels.len() + 1));
+                labels.len()
+            }
+        };
+        relabeled.push(label);
+    }
+    return relabeled;
+}
+
+/// The canonical form of a sequence, shared by every relabeling and the
+/// reversal of it. E.g 321323123 and 123121321 both give 123121321
+pub fn canonical_form(sequence: &[usize]) -> Vec<usize> {
+    let forwards = relabel_by_appearance(sequence.iter());
+    let backwards = relabel_by_appearance(sequence.iter().rev());
+    return forwards.min(backwards);
+}
+
+/// Distinct solutions gathered from any number of runs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SolutionPool {
+    /// Times each canonical form has been inserted
+    solutions: BTreeMap<Vec<usize>, usize>,
+    runs: usize,
+}
+
+impl SolutionPool {
+    pub fn new() -> SolutionPool {
+        return SolutionPool::default();
+    }
+
+    /// Adds the output of a run. Returns whether it's a new solution, i.e
+    /// not a relabeling or reversal of one already in the pool. Sequences
+    /// aren't checked, so check them first if runs can fail.
+    pub fn insert(&mut self, sequence: &[usize]) -> bool {
+        self.runs += 1;
+        let count = self.solutions.entry(canonical_form(sequence)).or_insert(0);
+        *count += 1;
+        return *count == 1;
+    }
+
+    /// Amount of sequences inserted
+    pub fn runs(&self) -> usize {
+        return self.runs;
+    }
+
+    /// Amount of distinct solutions
+    pub fn distinct(&self) -> usize {
+        return self.solutions.len();
+    }
+
+    /// Amount of distinct solutions of each length, in order of length
+    pub fn length_distribution(&self) -> BTreeMap<usize, usize> {
+        let mut distribution = BTreeMap::new();
+        for solution in self.solutions.keys() {
+            *distribution.entry(solution.len()).or_insert(0) += 1;
+        }
+        return distribution;
+    }
+
+    /// Shortest solution in canonical form, ties going to the
+    /// lexicographically smallest. None if the pool is empty
+    pub fn best(&self) -> Option<&Vec<usize>> {
+        return self.solutions.keys().min_by_key(|solution| solution.len());
+    }
+
+    /// Each distinct solution in canonical form along with the amount of
+    /// times it was found, in lexicographic order
+    pub fn solutions(&self) -> impl Iterator<Item = (&Vec<usize>, usize)> {
+        return self.solutions.iter().map(|(solution, count)| (solution, *count));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deduplicated_solutions() {
+        assert_eq!(canonical_form(&[3,2,1,3,2,3,1,2,3]), vec![1,2,3,1,2,1,3,2,1]);
+        assert_eq!(canonical_form(&[2,1,2]), vec![1,2,1]);
+
+        let mut pool = SolutionPool::new();
+        assert!(pool.insert(&[1,2,3,1,2,1,3,2,1]));
+        // Relabeled and reversed copies of it
+        assert!(!pool.insert(&[3,2,1,3,2,3,1,2,3]));
+        assert!(!pool.insert(&[1,2,3,1,2,1,3,2,1].iter().rev().copied().collect::<Vec<usize>>()));
+        assert!(pool.insert(&[1,2,3,1,2,1,3,2,1,1]));
+        assert_eq!((pool.runs(), pool.distinct()), (4, 2));
+        assert_eq!(pool.length_distribution(), BTreeMap::from([(9, 1), (10, 1)]));
+        assert_eq!(pool.best(), Some(&vec![1,2,3,1,2,1,3,2,1]));
+        assert_eq!(pool.solutions().map(|(_, count)| count).collect::<Vec<usize>>(), vec![1, 3]);
+    }
+}
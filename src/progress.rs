@@ -0,0 +1,69 @@
+// Progress notifications from long running creation, so headless runs can be
+// watched. Solvers report ProgressEvents to a ProgressSink, which can be any
+// closure or, with the `websocket` feature, a server streaming them as JSON.
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "websocket")]
+pub mod websocket;
+
+/// Snapshot of how far a solver has got
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProgressEvent {
+    /// Seconds since the solver started
+    pub elapsed_secs: f64,
+    /// Length of the best (for greedy solvers the current) sequence so far
+    pub best_length: usize,
+    /// Amount of distinct permutations covered so far
+    pub covered: usize,
+    /// Amount of permutations to cover
+    pub total: usize,
+    /// Fraction of the permutations covered, from 0 to 1
+    pub coverage: f64,
+    /// Nodes (placed permutations for greedy solvers) processed per second
+    pub nodes_per_sec: f64,
+}
+
+impl ProgressEvent {
+    /// Fills in the derived coverage and rate from the raw counts
+    pub fn new(elapsed_secs: f64, best_length: usize, covered: usize, total: usize, nodes: usize) -> ProgressEvent {
+        let coverage = if total == 0 { 1.0 } else { covered as f64 / total as f64 };
+        let nodes_per_sec = if elapsed_secs > 0.0 { nodes as f64 / elapsed_secs } else { 0.0 };
+        return ProgressEvent { elapsed_secs, best_length, covered, total, coverage, nodes_per_sec };
+    }
+}
+
+/// Wall clock timer for timings and progress. std's Instant panics on
+/// wasm32-unknown-unknown, where elapsed times read as 0 instead.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Stopwatch {
+    #[cfg(not(target_arch = "wasm32"))]
+    start: std::time::Instant,
+}
+
+impl Stopwatch {
+    pub(crate) fn start() -> Stopwatch {
+        return Stopwatch {
+            #[cfg(not(target_arch = "wasm32"))]
+            start: std::time::Instant::now(),
+        };
+    }
+
+    pub(crate) fn elapsed_secs(&self) -> f64 {
+        #[cfg(not(target_arch = "wasm32"))]
+        return self.start.elapsed().as_secs_f64();
+        #[cfg(target_arch = "wasm32")]
+        return 0.0;
+    }
+}
+
+/// Receives the progress notifications of a solver
+pub trait ProgressSink {
+    fn report(&mut self, event: &ProgressEvent);
+}
+
+/// Any closure taking an event can be used as a ProgressSink
+impl<F: FnMut(&ProgressEvent)> ProgressSink for F {
+    fn report(&mut self, event: &ProgressEvent) {
+        self(event);
+    }
+}
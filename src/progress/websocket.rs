@@ -0,0 +1,179 @@
+// Streams ProgressEvents as JSON text frames to any number of WebSocket
+// subscribers, e.g dashboards watching a headless run. Only the server side
+// of RFC 6455 needed for pushing frames is implemented: the opening handshake
+// and unmasked text frames. Anything subscribers send is ignored.
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use sha1::{Digest, Sha1};
+
+use crate::progress::{ProgressEvent, ProgressSink};
+
+/// Fixed GUID the handshake key is combined with, from RFC 6455
+const HANDSHAKE_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// How long a client gets to send its opening handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The Sec-WebSocket-Accept value answering a Sec-WebSocket-Key
+fn accept_key(key: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(key.as_bytes());
+    hasher.update(HANDSHAKE_GUID.as_bytes());
+    return STANDARD.encode(hasher.finalize());
+}
+
+/// Encodes a text frame holding the whole message
+fn text_frame(message: &str) -> Vec<u8> {
+    let payload = message.as_bytes();
+    // FIN bit set with the text opcode
+    let mut frame = vec![0x81];
+    if payload.len() < 126 {
+        frame.push(payload.len() as u8);
+    } else if payload.len() <= u16::MAX as usize {
+        frame.push(126);
+        frame.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+    } else {
+        frame.push(127);
+        frame.extend_from_slice(&(payload.len() as u64).to_be_bytes());
+    }
+    frame.extend_from_slice(payload);
+    return frame;
+}
+
+/// Reads the client's opening handshake, giving the Sec-WebSocket-Key to
+/// answer. Clients that don't send one get a 400.
+fn read_handshake(stream: &mut TcpStream) -> io::Result<String> {
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut key = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("sec-websocket-key") {
+                key = Some(value.trim().to_string());
+            }
+        }
+    }
+    let Some(key) = key else {
+        stream.write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n")?;
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "missing Sec-WebSocket-Key header"));
+    };
+    return Ok(key);
+}
+
+/// Answers a handshake giving the key, switching the connection over
+fn answer_handshake(stream: &mut TcpStream, key: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(key)
+    )?;
+    return stream.flush();
+}
+
+/// WebSocket server broadcasting each reported event to every subscriber
+pub struct ProgressServer {
+    addr: SocketAddr,
+    subscribers: Arc<Mutex<Vec<TcpStream>>>,
+}
+
+impl ProgressServer {
+    /// Starts accepting subscribers on the address given in the background
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<ProgressServer> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let subscribers: Arc<Mutex<Vec<TcpStream>>> = Arc::new(Mutex::new(Vec::new()));
+        let accepted = Arc::clone(&subscribers);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else {
+                    continue;
+                };
+                // Handshakes are read on their own thread without the lock,
+                // so a slow client holds up neither other clients nor
+                // broadcasting
+                let accepted = Arc::clone(&accepted);
+                thread::spawn(move || {
+                    let Ok(key) = read_handshake(&mut stream) else {
+                        return;
+                    };
+                    let Ok(subscriber) = stream.try_clone() else {
+                        return;
+                    };
+                    // Registered under the same lock the response is written
+                    // in, so no events are missed once a client sees it
+                    let mut subscribers = accepted.lock().unwrap();
+                    if answer_handshake(&mut stream, &key).is_ok() {
+                        subscribers.push(subscriber);
+                    }
+                });
+            }
+        });
+        return Ok(ProgressServer { addr, subscribers });
+    }
+
+    /// The address being listened on, useful after binding to port 0
+    pub fn local_addr(&self) -> SocketAddr {
+        return self.addr;
+    }
+
+    /// Sends the event as a JSON text frame to every subscriber, dropping
+    /// the ones that have gone away
+    pub fn broadcast(&self, event: &ProgressEvent) {
+        let frame = text_frame(&serde_json::to_string(event).expect("event serialisation failed"));
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.retain_mut(|stream| stream.write_all(&frame).is_ok());
+    }
+}
+
+impl ProgressSink for ProgressServer {
+    fn report(&mut self, event: &ProgressEvent) {
+        self.broadcast(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn websocket_streaming() {
+        // Example from RFC 6455
+        assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+        assert_eq!(text_frame("hi"), vec![0x81, 2, b'h', b'i']);
+        assert_eq!(&text_frame(&"a".repeat(300))[..4], &[0x81, 126, 1, 44]);
+
+        let server = ProgressServer::bind("127.0.0.1:0").unwrap();
+        // A client that never sends its handshake holds up nobody else
+        let _idle = TcpStream::connect(server.local_addr()).unwrap();
+        let mut client = TcpStream::connect(server.local_addr()).unwrap();
+        client.write_all(b"GET / HTTP/1.1\r\nUpgrade: websocket\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n").unwrap();
+        let mut reader = BufReader::new(client);
+        let mut response = String::new();
+        while !response.ends_with("\r\n\r\n") {
+            reader.read_line(&mut response).unwrap();
+        }
+        assert!(response.starts_with("HTTP/1.1 101"));
+        assert!(response.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n"));
+
+        let event = ProgressEvent::new(2.0, 9, 6, 6, 6);
+        server.broadcast(&event);
+        let mut header = [0; 2];
+        reader.read_exact(&mut header).unwrap();
+        let mut payload = vec![0; header[1] as usize];
+        reader.read_exact(&mut payload).unwrap();
+        let received: ProgressEvent = serde_json::from_slice(&payload).unwrap();
+        assert_eq!(received, event);
+        assert_eq!(received.nodes_per_sec, 3.0);
+    }
+}
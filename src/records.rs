@@ -0,0 +1,72 @@
+// Known lengths of superpermutations, kept as data the rest of the crate can
+// consult. Sources:
+// - Minimal lengths for n <= 5 are proven (n=5 by Johnston, 2014)
+// - 872 for n=6 (Houston, 2014) and 5906 for n=7 (Egan and Coanda, 2019)
+// - For n >= 8, Egan's construction: n! + (n-1)! + (n-2)! + (n-3)! + n - 3
+// - Lower bound n! + (n-1)! + (n-2)! + n - 3 (anonymous, 2011)
+use crate::permutation::checked_factorial;
+
+/// Proven minimal lengths, indexed by n-1
+pub(crate) const MINIMAL_LENGTHS: [usize; 5] = [1, 3, 9, 33, 153];
+
+/// Sum of the values, None if it overflows
+fn checked_sum(values: &[usize]) -> Option<usize> {
+    return values.iter().try_fold(0usize, |acc, x| acc.checked_add(*x));
+}
+
+/// Length of the shortest possible superpermutation of n tokens, for the n
+/// where this has been proven (n <= 5).
+pub fn minimal_length(n: usize) -> Option<usize> {
+    if n == 0 {
+        return Some(0);
+    }
+    return MINIMAL_LENGTHS.get(n - 1).copied();
+}
+
+/// Length of the shortest superpermutation of n tokens found so far. None if
+/// it doesn't fit in a usize.
+pub fn best_known_length(n: usize) -> Option<usize> {
+    if let Some(length) = minimal_length(n) {
+        return Some(length);
+    }
+    match n {
+        6 => return Some(872),
+        7 => return Some(5906),
+        _ => {}
+    }
+    return Some(checked_sum(&[checked_factorial(n)?, checked_factorial(n - 1)?, checked_factorial(n - 2)?, checked_factorial(n - 3)?, n])? - 3);
+}
+
+/// No superpermutation of n tokens can be shorter than this. None if it
+/// doesn't fit in a usize.
+pub fn lower_bound(n: usize) -> Option<usize> {
+    if n < 2 {
+        return Some(n);
+    }
+    return Some(checked_sum(&[checked_factorial(n)?, checked_factorial(n - 1)?, checked_factorial(n - 2)?, n])? - 3);
+}
+
+/// Length of the superpermutation built by the standard recursive
+/// construction, 1! + 2! + ... + n!. None if it doesn't fit in a usize.
+pub fn recursive_length(n: usize) -> Option<usize> {
+    return (1..n + 1).try_fold(0usize, |acc, k| acc.checked_add(checked_factorial(k)?));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_lengths() {
+        for n in 1..6 {
+            assert_eq!(minimal_length(n), best_known_length(n));
+            assert_eq!(minimal_length(n), recursive_length(n));
+            assert!(lower_bound(n).unwrap() <= minimal_length(n).unwrap());
+        }
+        assert_eq!(minimal_length(6), None);
+        assert_eq!(best_known_length(6), Some(872));
+        assert_eq!(best_known_length(8), Some(46205));
+        assert_eq!(lower_bound(6), Some(867));
+        assert_eq!(recursive_length(30), None);
+    }
+}
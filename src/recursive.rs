@@ -0,0 +1,180 @@
+// The standard recursive construction of superpermutations. Each permutation
+// of n-1 tokens in a superpermutation of n-1 tokens, in order of appearance,
+// is replaced by the permutation, n and the permutation again, overlapping
+// each with the last as much as possible. E.g 121 becomes 12312 + 21321,
+// which overlap to 123121321. The length is 1! + 2! + ... + n!, which is
+// minimal for n <= 5.
+//
+// Written as const fns so the superperm! macro can bake sequences into a
+// binary at compile time without running any algorithm. lift does a single
+// step at runtime from any superpermutation, so shorter ones found for n-1
+// carry over to n.
+use crate::analysis::covered_in_order;
+use crate::permutation::checked_factorial;
+
+/// Length of the recursive construction for n tokens, 1! + 2! + ... + n!.
+/// Panics on overflow, see records::recursive_length for a checked version.
+pub const fn length(n: usize) -> usize {
+    let mut total = 0;
+    let mut factorial = 1;
+    let mut k = 1;
+    while k <= n {
+        factorial *= k;
+        total += factorial;
+        k += 1;
+    }
+    return total;
+}
+
+/// Whether the len tokens from start are each of the tokens 1..=len once
+const fn is_perm_window(sequence: &[usize], start: usize, len: usize) -> bool {
+    let mut seen: u128 = 0;
+    let mut i = 0;
+    while i < len {
+        let token = sequence[start + i];
+        if token == 0 || token > len || seen & (1 << token) != 0 {
+            return false;
+        }
+        seen |= 1 << token;
+        i += 1;
+    }
+    return true;
+}
+
+/// Token i of the permutation from start, then k, then the permutation again
+const fn block_token(sequence: &[usize], start: usize, m: usize, k: usize, i: usize) -> usize {
+    if i < m {
+        return sequence[start + i];
+    } else if i == m {
+        return k;
+    }
+    return sequence[start + i - m - 1];
+}
+
+/// The recursive construction for n tokens, where LEN is length(n). Usable
+/// in constants, e.g `const S: [usize; 9] = construct(3);` gives 123121321.
+/// Panics if LEN isn't length(n), which is a compile error in constants.
+pub const fn construct<const LEN: usize>(n: usize) -> [usize; LEN] {
+    assert!(LEN == length(n), "LEN must be recursive::length(n)");
+    // Both fit as every step is shorter than the last one
+    let mut current = [0usize; LEN];
+    let mut next = [0usize; LEN];
+    if n == 0 {
+        return current;
+    }
+    current[0] = 1;
+    let mut current_len = 1;
+    let mut k = 2;
+    while k <= n {
+        let m = k - 1;
+        let block_len = 2 * m + 1;
+        let mut next_len = 0;
+        let mut start = 0;
+        while start + m <= current_len {
+            if is_perm_window(&current, start, m) {
+                // Largest overlap of the end of next with the start of the block
+                let mut overlap = if next_len < block_len { next_len } else { block_len - 1 };
+                while overlap > 0 {
+                    let mut j = 0;
+                    while j < overlap && next[next_len - overlap + j] == block_token(&current, start, m, k, j) {
+                        j += 1;
+                    }
+                    if j == overlap {
+                        break;
+                    }
+                    overlap -= 1;
+                }
+                let mut i = overlap;
+                while i < block_len {
+                    next[next_len] = block_token(&current, start, m, k, i);
+                    next_len += 1;
+                    i += 1;
+                }
+            }
+            start += 1;
+        }
+        current = next;
+        current_len = next_len;
+        k += 1;
+    }
+    return current;
+}
+
+/// One step of the construction from any superpermutation of (1,2,3, ...,n-1)
+/// to one of (1,2,3, ...,n). Each permutation is taken in the order it first
+/// appears. The result has n! tokens more than the sequence given when the
+/// sequence only steps between permutations one token at a time, and is at
+/// most that long otherwise.
+///
+/// E.g 121 for n=3 gives 123121321.
+/// Returns None if the sequence isn't a superpermutation of (1,2,3, ...,n-1)
+/// or n is 0.
+pub fn lift(superperm: &[usize], n: usize) -> Option<Vec<usize>> {
+    if n <= 1 {
+        return if n == 1 { Some(vec![1]) } else { None };
+    }
+    let m = n - 1;
+    let perms: Vec<&[usize]> = covered_in_order(superperm, m).map(|(_, perm)| perm).collect();
+    if Some(perms.len()) != checked_factorial(m) {
+        return None;
+    }
+    let mut lifted: Vec<usize> = Vec::new();
+    for perm in perms {
+        let block: Vec<usize> = perm.iter().copied().chain(std::iter::once(n)).chain(perm.iter().copied()).collect();
+        // Largest overlap of the end of the sequence with the start of the block
+        let overlap = (0..block.len().min(lifted.len() + 1))
+            .rev()
+            .find(|overlap| lifted[lifted.len() - overlap..] == block[..*overlap])
+            .unwrap_or(0);
+        lifted.extend_from_slice(&block[overlap..]);
+    }
+    return Some(lifted);
+}
+
+/// The superpermutation of the tokens (1,2,3, ...,n) from the recursive
+/// construction as a `&'static [usize]`, generated at compile time. n must be
+/// a constant expression. E.g superperm!(3) is &[1,2,3,1,2,1,3,2,1]
+#[macro_export]
+macro_rules! superperm {
+    ($n:expr) => {{
+        const SUPERPERM: [usize; $crate::recursive::length($n)] = $crate::recursive::construct($n);
+        &SUPERPERM as &'static [usize]
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::SuperPermHandling;
+    use crate::{bruteforce_optimise, fixtures, records};
+
+    #[test]
+    fn compile_time_construction() {
+        let tables: [&[usize]; 5] = [superperm!(1), superperm!(2), superperm!(3), superperm!(4), superperm!(5)];
+        for (i, table) in tables.iter().enumerate() {
+            assert_eq!(table.to_vec(), fixtures::minimal(i + 1).unwrap().sequence);
+        }
+        let six = superperm!(6);
+        assert_eq!(Some(six.len()), records::recursive_length(6));
+        assert!(bruteforce_optimise::Handle.check_superperm(&six.to_vec(), 6));
+        assert_eq!(superperm!(0), &[] as &[usize]);
+        assert_eq!(length(4), 33);
+    }
+
+    #[test]
+    fn lifting() {
+        assert_eq!(lift(&[1,2,1], 3), Some(superperm!(3).to_vec()));
+        for n in 2..7 {
+            let below = fixtures::minimal(n - 1).unwrap().sequence;
+            let lifted = lift(&below, n).unwrap();
+            assert!(bruteforce_optimise::Handle.check_superperm(&lifted, n), "n={}", n);
+            assert_eq!(lifted.len(), below.len() + checked_factorial(n).unwrap(), "n={}", n);
+        }
+        // Any valid superpermutation lifts, not just the recursive ones
+        let greedy = bruteforce_optimise::Handle.create_superperm(4);
+        assert!(bruteforce_optimise::Handle.check_superperm(&lift(&greedy, 5).unwrap(), 5));
+        assert_eq!(lift(&[1,2,3,1,2], 4), None);
+        assert_eq!(lift(&[], 1), Some(vec![1]));
+        assert_eq!(lift(&[1], 0), None);
+    }
+}
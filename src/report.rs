@@ -0,0 +1,423 @@
+// Report types describing the result of a run, serialised to a stable and
+// versioned JSON schema for consumption by external analysis tools.
+//
+// Schema (version 1):
+// {
+//   "schema_version": 1,
+//   "crate_version": "0.1.0",
+//   "algorithm": "bruteforce_optimise",
+//   "n": 3,
+//   "length": 9,
+//   "valid": true,
+//   "sequence": [1, 2, 3, 1, 2, 1, 3, 2, 1],
+//   "timings": { "creation_secs": 0.0001, "check_secs": 0.00002 },
+//   "stats": {
+//     "distinct_perms": 6,
+//     "duplicate_visits": 0,
+//     "waste": 1,
+//     "completion_index": 8
+//   }
+// }
+//
+// Adding new fields is not a breaking change and keeps the schema version.
+// Renaming, removing or changing the meaning of a field bumps SCHEMA_VERSION.
+use std::fmt;
+use std::io::{self, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::{self, SuperPermStats};
+use crate::base::SuperPermHandling;
+use crate::batch::csv_field;
+use crate::progress::Stopwatch;
+
+/// Version of the JSON schema emitted by RunReport
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The result of creating (and verifying) a superpermutation with an algorithm
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunReport {
+    /// Version of the schema this report was written with
+    pub schema_version: u32,
+    /// Version of this crate that produced the report
+    pub crate_version: String,
+    /// Name of the algorithm used to create the sequence
+    pub algorithm: String,
+    /// Amount of unique tokens
+    pub n: usize,
+    /// Length of the sequence created
+    pub length: usize,
+    /// Whether the algorithm's checker accepted the sequence
+    pub valid: bool,
+    pub sequence: Vec<usize>,
+    pub timings: Timings,
+    pub stats: Stats,
+}
+
+/// Wall clock times of each stage of a run, in seconds
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Timings {
+    pub creation_secs: f64,
+    pub check_secs: f64,
+}
+
+/// Structural statistics of a sequence, as embedded in a report. Built from
+/// analysis::SuperPermStats, without the fields the report already holds
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    /// Amount of distinct permutations appearing within the sequence
+    pub distinct_perms: usize,
+    /// Amount of times a permutation appears again after its first appearance
+    pub duplicate_visits: usize,
+    /// Length beyond the minimum possible to visit distinct_perms permutations
+    pub waste: usize,
+    /// Index of the token that completes the coverage of every permutation.
+    /// None if not every permutation is covered.
+    pub completion_index: Option<usize>,
+}
+
+impl Stats {
+    /// Calculates the statistics of a sequence for the tokens (1,2,3, ...,n_tokens).
+    /// See analysis::SuperPermStats
+    pub fn compute(sequence: &[usize], n_tokens: usize) -> Stats {
+        return Stats::from(SuperPermStats::compute(sequence, n_tokens));
+    }
+}
+
+impl From<SuperPermStats> for Stats {
+    fn from(stats: SuperPermStats) -> Stats {
+        return Stats {
+            distinct_perms: stats.distinct_perms,
+            duplicate_visits: stats.duplicate_visits,
+            waste: stats.waste,
+            completion_index: stats.completion_index,
+        };
+    }
+}
+
+impl Stats {
+    /// Columns of the rows written by write_csv
+    pub const CSV_HEADER: &'static str =
+        "algorithm,n,length,valid,distinct_perms,duplicate_visits,waste,completion_index,creation_secs,check_secs";
+
+    /// Writes a CSV header followed by one row per report. An incomplete
+    /// coverage leaves the completion_index column empty, and an algorithm
+    /// name containing a comma or quote is quoted.
+    pub fn write_csv<W: Write>(mut w: W, reports: &[RunReport]) -> io::Result<()> {
+        writeln!(w, "{}", Stats::CSV_HEADER)?;
+        for report in reports {
+            let completion_index = match report.stats.completion_index {
+                Some(index) => index.to_string(),
+                None => String::new(),
+            };
+            writeln!(
+                w,
+                "{},{},{},{},{},{},{},{},{},{}",
+                csv_field(&report.algorithm),
+                report.n,
+                report.length,
+                report.valid,
+                report.stats.distinct_perms,
+                report.stats.duplicate_visits,
+                report.stats.waste,
+                completion_index,
+                report.timings.creation_secs,
+                report.timings.check_secs,
+            )?;
+        }
+        return Ok(());
+    }
+}
+
+/// Errors that can arise when loading a report from JSON
+#[derive(Debug)]
+pub enum ReportError {
+    /// The JSON doesn't match the report schema
+    Json(serde_json::Error),
+    /// The report was written with a schema this version of the crate doesn't understand
+    UnsupportedVersion { found: u32, supported: u32 },
+}
+
+impl fmt::Display for ReportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReportError::Json(err) => write!(f, "{}", err),
+            ReportError::UnsupportedVersion { found, supported } => {
+                write!(f, "report schema version {} is not supported (supported: {})", found, supported)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReportError {}
+
+impl From<serde_json::Error> for ReportError {
+    fn from(err: serde_json::Error) -> ReportError {
+        return ReportError::Json(err);
+    }
+}
+
+impl fmt::Display for RunReport {
+    /// The algorithm, verdict, statistics and timings, one per line
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "algorithm:        {}", self.algorithm)?;
+        writeln!(f, "n:                {}", self.n)?;
+        writeln!(f, "length:           {}", self.length)?;
+        writeln!(f, "valid:            {}", self.valid)?;
+        writeln!(f, "distinct perms:   {}", self.stats.distinct_perms)?;
+        writeln!(f, "duplicate visits: {}", self.stats.duplicate_visits)?;
+        writeln!(f, "waste:            {}", self.stats.waste)?;
+        match self.stats.completion_index {
+            Some(index) => writeln!(f, "completion index: {}", index)?,
+            None => writeln!(f, "completion index: incomplete")?,
+        }
+        writeln!(f, "creation time:    {:.6}s", self.timings.creation_secs)?;
+        write!(f, "check time:       {:.6}s", self.timings.check_secs)
+    }
+}
+
+impl RunReport {
+    /// Creates a superpermutation of n_tokens with the handle given, checks it
+    /// with the same handle and gathers the results into a report.
+    pub fn run(algorithm: &str, handle: &dyn SuperPermHandling, n_tokens: usize) -> RunReport {
+        let start = Stopwatch::start();
+        let sequence = handle.create_superperm(n_tokens);
+        let creation_secs = start.elapsed_secs();
+
+        let start = Stopwatch::start();
+        let valid = handle.check_superperm(&sequence, n_tokens);
+        let check_secs = start.elapsed_secs();
+
+        return RunReport {
+            schema_version: SCHEMA_VERSION,
+            crate_version: String::from(env!("CARGO_PKG_VERSION")),
+            algorithm: String::from(algorithm),
+            n: n_tokens,
+            length: sequence.len(),
+            valid,
+            stats: Stats::compute(&sequence, n_tokens),
+            sequence,
+            timings: Timings { creation_secs, check_secs },
+        };
+    }
+
+    /// Checks an existing sequence with the handle given and gathers the
+    /// results into a report. The creation time is recorded as 0.
+    pub fn analyze(label: &str, handle: &dyn SuperPermHandling, sequence: Vec<usize>, n_tokens: usize) -> RunReport {
+        let start = Stopwatch::start();
+        let valid = handle.check_superperm(&sequence, n_tokens);
+        let check_secs = start.elapsed_secs();
+
+        return RunReport {
+            schema_version: SCHEMA_VERSION,
+            crate_version: String::from(env!("CARGO_PKG_VERSION")),
+            algorithm: String::from(label),
+            n: n_tokens,
+            length: sequence.len(),
+            valid,
+            stats: Stats::compute(&sequence, n_tokens),
+            sequence,
+            timings: Timings { creation_secs: 0.0, check_secs },
+        };
+    }
+
+    pub fn to_json(&self) -> String {
+        // Serialising plain data structures to a string cannot fail
+        return serde_json::to_string_pretty(self).expect("report serialisation failed");
+    }
+
+    /// Loads a report from JSON, rejecting reports from newer schema versions
+    pub fn from_json(json: &str) -> Result<RunReport, ReportError> {
+        let report: RunReport = serde_json::from_str(json)?;
+        if report.schema_version > SCHEMA_VERSION {
+            return Err(ReportError::UnsupportedVersion {
+                found: report.schema_version,
+                supported: SCHEMA_VERSION,
+            });
+        }
+        return Ok(report);
+    }
+}
+
+/// A titled table of a rendered report
+struct Section {
+    title: &'static str,
+    header: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+impl RunReport {
+    /// Gathers the tables shared by render_markdown and render_html
+    fn sections(&self) -> Vec<Section> {
+        let completion_index = match self.stats.completion_index {
+            Some(index) => index.to_string(),
+            None => String::from("incomplete"),
+        };
+        let summary = vec![
+            vec![String::from("Length"), self.length.to_string()],
+            vec![String::from("Valid"), self.valid.to_string()],
+            vec![String::from("Distinct perms"), self.stats.distinct_perms.to_string()],
+            vec![String::from("Duplicate visits"), self.stats.duplicate_visits.to_string()],
+            vec![String::from("Waste"), self.stats.waste.to_string()],
+            vec![String::from("Completion index"), completion_index],
+            vec![String::from("Creation time"), format!("{:.6}s", self.timings.creation_secs)],
+            vec![String::from("Check time"), format!("{:.6}s", self.timings.check_secs)],
+        ];
+        let mut sections = vec![Section { title: "Summary", header: vec!["Statistic", "Value"], rows: summary }];
+
+        if let Some(bounds) = analysis::bounds_report(self.n, self.length) {
+            sections.push(Section {
+                title: "Bounds",
+                header: vec!["Lower bound", "Best known", "Recursive length", "Class"],
+                rows: vec![vec![
+                    bounds.lower_bound.to_string(),
+                    bounds.best_known.to_string(),
+                    bounds.recursive_length.to_string(),
+                    format!("{:?}", bounds.class),
+                ]],
+            });
+        }
+
+        let histogram = analysis::overlap_histogram(&self.sequence, self.n);
+        sections.push(Section {
+            title: "Overlap histogram",
+            header: vec!["Overlap", "Transitions"],
+            rows: histogram.iter().enumerate().map(|(overlap, count)| vec![overlap.to_string(), count.to_string()]).collect(),
+        });
+        return sections;
+    }
+
+    /// Renders the report as a Markdown document of tables: the summary
+    /// statistics, a comparison against the known bounds and the overlap
+    /// histogram. If a width is given the ASCII coverage strip
+    /// (analysis::render_coverage_ascii) is included at that width.
+    pub fn render_markdown(&self, coverage_width: Option<usize>) -> String {
+        let mut document = format!("# Superpermutation report: {}, n={}\n", self.algorithm, self.n);
+        for section in self.sections() {
+            document.push_str(&format!("\n## {}\n\n", section.title));
+            document.push_str(&format!("| {} |\n", section.header.join(" | ")));
+            document.push_str(&format!("|{}\n", "---|".repeat(section.header.len())));
+            for row in section.rows {
+                document.push_str(&format!("| {} |\n", row.join(" | ")));
+            }
+        }
+        if let Some(width) = coverage_width {
+            let strip = analysis::render_coverage_ascii(&self.sequence, self.n, width);
+            document.push_str(&format!("\n## Coverage\n\n```text\n{}\n```\n", strip));
+        }
+        return document;
+    }
+
+    /// Renders the same content as render_markdown as a standalone HTML page
+    pub fn render_html(&self, coverage_width: Option<usize>) -> String {
+        let title = format!("Superpermutation report: {}, n={}", escape_html(&self.algorithm), self.n);
+        let mut document = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n<h1>{}</h1>\n",
+            title, title
+        );
+        for section in self.sections() {
+            document.push_str(&format!("<h2>{}</h2>\n<table>\n<tr>", section.title));
+            for cell in section.header {
+                document.push_str(&format!("<th>{}</th>", cell));
+            }
+            document.push_str("</tr>\n");
+            for row in section.rows {
+                document.push_str("<tr>");
+                for cell in row {
+                    document.push_str(&format!("<td>{}</td>", escape_html(&cell)));
+                }
+                document.push_str("</tr>\n");
+            }
+            document.push_str("</table>\n");
+        }
+        if let Some(width) = coverage_width {
+            let strip = analysis::render_coverage_ascii(&self.sequence, self.n, width);
+            document.push_str(&format!("<h2>Coverage</h2>\n<pre>{}</pre>\n", escape_html(&strip)));
+        }
+        document.push_str("</body>\n</html>\n");
+        return document;
+    }
+}
+
+/// Escapes the characters with special meaning in HTML text
+fn escape_html(text: &str) -> String {
+    return text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bruteforce_optimise;
+
+    #[test]
+    fn stats_of_known_sequences() {
+        let stats = Stats::compute(&[1,2,3,1,2,1,3,2,1], 3);
+        assert_eq!(stats, Stats {
+            distinct_perms: 6,
+            duplicate_visits: 0,
+            waste: 1,
+            completion_index: Some(8),
+        });
+        let stats = Stats::compute(&[1,2,1,2,1,3], 2);
+        assert_eq!(stats.distinct_perms, 2);
+        assert_eq!(stats.duplicate_visits, 2);
+        assert_eq!(stats.completion_index, Some(2));
+    }
+
+    #[test]
+    fn csv_rows() {
+        let report = RunReport::analyze("input", &bruteforce_optimise::Handle, vec![1,2,1], 3);
+        let mut csv = Vec::new();
+        Stats::write_csv(&mut csv, &[report]).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], Stats::CSV_HEADER);
+        assert!(lines[1].starts_with("input,3,3,false,0,0,3,,0,"));
+
+        let report = RunReport::analyze("greedy, seeded", &bruteforce_optimise::Handle, vec![1,2,1], 2);
+        let mut csv = Vec::new();
+        Stats::write_csv(&mut csv, &[report]).unwrap();
+        assert!(String::from_utf8(csv).unwrap().lines().nth(1).unwrap().starts_with("\"greedy, seeded\",2,"));
+    }
+
+    #[test]
+    fn json_schema_is_stable() {
+        let report = RunReport::run("bruteforce_optimise", &bruteforce_optimise::Handle, 3);
+        let value: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        let mut keys: Vec<&String> = value.as_object().unwrap().keys().collect();
+        keys.sort();
+        assert_eq!(keys, [
+            "algorithm", "crate_version", "length", "n", "schema_version",
+            "sequence", "stats", "timings", "valid",
+        ]);
+        assert_eq!(value["schema_version"], SCHEMA_VERSION);
+        assert_eq!(RunReport::from_json(&report.to_json()).unwrap(), report);
+
+        let future = report.to_json().replace("\"schema_version\": 1", "\"schema_version\": 99");
+        assert!(matches!(
+            RunReport::from_json(&future),
+            Err(ReportError::UnsupportedVersion { found: 99, .. })
+        ));
+    }
+
+    #[test]
+    fn rendered_reports() {
+        let report = RunReport::analyze("<input>", &bruteforce_optimise::Handle, vec![1,2,3,1,2,1,3,2,1], 3);
+        let markdown = report.render_markdown(Some(9));
+        assert!(markdown.starts_with("# Superpermutation report: <input>, n=3\n"));
+        assert!(markdown.contains("| Waste | 1 |\n"));
+        assert!(markdown.contains("| 2 | 4 |\n"));
+        assert!(markdown.contains("```text\n|..###.###|\n 0        9\n```"));
+        assert!(!report.render_markdown(None).contains("## Coverage"));
+
+        let html = report.render_html(Some(9));
+        assert!(html.contains("<h1>Superpermutation report: &lt;input&gt;, n=3</h1>"));
+        assert!(html.contains("<tr><td>Distinct perms</td><td>6</td></tr>"));
+        assert!(html.contains("<pre>|..###.###|"));
+
+        let text = report.to_string();
+        assert!(text.starts_with("algorithm:        <input>\nn:                3\n"));
+        assert!(text.contains("\nvalid:            true\n"));
+        assert_eq!(text.lines().count(), 10);
+    }
+}
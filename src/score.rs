@@ -0,0 +1,110 @@
+// Scoring of partial sequences for search based solvers. A solver extending a
+// sequence token by token ranks its candidates through the Score trait, so a
+// new heuristic can be tried by implementing Score rather than changing the
+// solver itself.
+//
+// None of the crate's solvers extend token by token (there's no beam search,
+// MCTS or annealing solver here), so this is for solvers built on the crate.
+// Solvers choosing whole permutations at a time use policy::Policy instead.
+use crate::analysis::{overlap_histogram, Coverage};
+
+/// Scores a partial sequence, higher being better
+pub trait Score {
+    /// Scores the sequence given the coverage of its windows, i.e
+    /// Coverage::from_sequence(sequence, n) kept up to date by the caller.
+    fn score(&self, sequence: &[usize], coverage: &Coverage) -> f64;
+}
+
+/// Any closure taking the sequence and coverage can be used as a Score
+impl<F: Fn(&[usize], &Coverage) -> f64> Score for F {
+    fn score(&self, sequence: &[usize], coverage: &Coverage) -> f64 {
+        return self(sequence, coverage);
+    }
+}
+
+/// Distinct permutations covered per character of the sequence
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CoveragePerChar;
+
+impl Score for CoveragePerChar {
+    fn score(&self, sequence: &[usize], coverage: &Coverage) -> f64 {
+        if sequence.is_empty() {
+            return 0.0;
+        }
+        return coverage.covered() as f64 / sequence.len() as f64;
+    }
+}
+
+/// Average weight of the transitions between newly visited permutations,
+/// weighted by how many tokens each shares with the one before it
+#[derive(Debug, Clone, Default)]
+pub struct OverlapQuality {
+    /// Weight of a transition indexed by its overlap 0..n-1. If left empty an
+    /// overlap of o is weighted o/(n-1), so only single token steps score 1
+    pub weights: Vec<f64>,
+}
+
+impl Score for OverlapQuality {
+    fn score(&self, sequence: &[usize], coverage: &Coverage) -> f64 {
+        let n = coverage.n();
+        let histogram = overlap_histogram(sequence, n);
+        let transitions: usize = histogram.iter().sum();
+        if transitions == 0 {
+            return 0.0;
+        }
+        let mut total = 0.0;
+        for (overlap, count) in histogram.iter().enumerate() {
+            let weight = match self.weights.get(overlap) {
+                Some(weight) => *weight,
+                None if self.weights.is_empty() => overlap as f64 / (n - 1) as f64,
+                None => 0.0,
+            };
+            total += weight * *count as f64;
+        }
+        return total / transitions as f64;
+    }
+}
+
+/// Scores appending each of the tokens (1,2,3, ...,n) onto the sequence and
+/// gives the tokens along with their scores, best first. The sequence and
+/// coverage are left as they were.
+pub fn rank_extensions<S: Score + ?Sized>(sequence: &mut Vec<usize>, coverage: &mut Coverage, score: &S) -> Vec<(usize, f64)> {
+    let n = coverage.n();
+    let mut ranked = Vec::with_capacity(n);
+    for token in 1..n + 1 {
+        sequence.push(token);
+        let window = &sequence[sequence.len().saturating_sub(n)..];
+        coverage.add(window);
+        ranked.push((token, score.score(sequence, coverage)));
+        coverage.remove(&sequence[sequence.len().saturating_sub(n)..]);
+        sequence.pop();
+    }
+    // Stable sort keeps smaller tokens first amongst equal scores
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    return ranked;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scoring() {
+        let sequence = vec![1,2,3,1,2,1,3,2,1];
+        let coverage = Coverage::from_sequence(&sequence, 3);
+        assert_eq!(CoveragePerChar.score(&sequence, &coverage), 6.0 / 9.0);
+        assert_eq!(OverlapQuality::default().score(&sequence, &coverage), 4.5 / 5.0);
+        let custom = OverlapQuality { weights: vec![0.0, 0.0, 1.0] };
+        assert_eq!(custom.score(&sequence, &coverage), 0.8);
+        let length = |sequence: &[usize], _: &Coverage| -(sequence.len() as f64);
+        assert_eq!(length.score(&sequence, &coverage), -9.0);
+
+        // 1231 is best extended by 2 to reach 312
+        let mut sequence = vec![1,2,3,1];
+        let mut coverage = Coverage::from_sequence(&sequence, 3);
+        let ranked = rank_extensions(&mut sequence, &mut coverage, &CoveragePerChar);
+        assert_eq!(ranked[0].0, 2);
+        assert_eq!(sequence, vec![1,2,3,1]);
+        assert_eq!(coverage.covered(), 2);
+    }
+}
@@ -0,0 +1,194 @@
+// A small HTTP/1.1 JSON service for checking and creating superpermutations
+// from non-Rust tooling. Built on std's TcpListener alone, with one thread per
+// connection and one request per connection.
+//
+//   POST /check   {"sequence": "123121321", "n": 3, "algorithm": "bruteforce"}
+//   POST /create  {"n": 3, "algorithm": "bruteforce_optimise"}
+//
+// The sequence can be a digit string or an array of tokens, n defaults to the
+// largest token and the algorithm to bruteforce_optimise. Both respond with a
+// report::RunReport as JSON, or {"error": "..."} with a 4xx status. Creation
+// estimated to need more than CREATE_BUDGET is refused with a 413.
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::thread;
+
+use serde::Deserialize;
+
+use crate::base::{check_create_n, check_n_supported, Algorithm};
+use crate::budget::{create_estimate, MemoryBudget};
+use crate::report::RunReport;
+use crate::superperm::SuperPerm;
+
+/// Largest request body accepted, in bytes
+const MAX_BODY_LEN: usize = 64 * 1024 * 1024;
+
+/// Most memory a /create request is estimated to use, 1GiB. E.g n=11 with
+/// bruteforce_optimise fits, n=12 doesn't
+pub const CREATE_BUDGET: MemoryBudget = MemoryBudget { limit_bytes: 1 << 30 };
+
+/// A sequence given either as a digit string or as an array of tokens
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SequenceInput {
+    Digits(String),
+    Tokens(Vec<usize>),
+}
+
+#[derive(Deserialize)]
+struct CheckRequest {
+    sequence: SequenceInput,
+    n: Option<usize>,
+    algorithm: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CreateRequest {
+    n: usize,
+    algorithm: Option<String>,
+}
+
+/// The algorithm going by the name, defaulting to bruteforce_optimise
+fn parse_algorithm(name: Option<&str>) -> Result<Algorithm, String> {
+    return name.map_or(Ok(Algorithm::BruteforceOptimise), str::parse);
+}
+
+/// JSON body of an error response
+fn error_body(message: &str) -> String {
+    return serde_json::json!({ "error": message }).to_string();
+}
+
+/// Responds to a request, giving the status code and JSON body. Kept separate
+/// from the connection handling so it can be used with any transport.
+pub fn handle_request(method: &str, path: &str, body: &[u8]) -> (u16, String) {
+    if path != "/check" && path != "/create" {
+        return (404, error_body("not found"));
+    }
+    if method != "POST" {
+        return (405, error_body("only POST is supported"));
+    }
+    // Errors are bad requests unless they give their own status
+    let bad_request = |err: String| (400, err);
+    let result = if path == "/check" {
+        serde_json::from_slice::<CheckRequest>(body).map_err(|err| err.to_string()).and_then(|request| {
+            let superperm = match request.sequence {
+                SequenceInput::Digits(digits) => SuperPerm::from_digit_str(&digits).map_err(|err| err.to_string())?,
+                SequenceInput::Tokens(tokens) => {
+                    let n_tokens = tokens.iter().copied().max().unwrap_or(0);
+                    SuperPerm::new(tokens, n_tokens)
+                }
+            };
+            let handle = parse_algorithm(request.algorithm.as_deref())?.handle();
+            let n = request.n.unwrap_or(superperm.n_tokens);
+            check_n_supported(n).map_err(|err| err.to_string())?;
+            return Ok(RunReport::analyze("input", handle.as_ref(), superperm.sequence, n));
+        }).map_err(bad_request)
+    } else {
+        serde_json::from_slice::<CreateRequest>(body).map_err(|err| bad_request(err.to_string())).and_then(|request| {
+            let algorithm = parse_algorithm(request.algorithm.as_deref()).map_err(bad_request)?;
+            let handle = algorithm.handle();
+            check_create_n(handle.as_ref(), request.n).map_err(|err| bad_request(err.to_string()))?;
+            CREATE_BUDGET
+                .check(create_estimate(algorithm, request.n))
+                .map_err(|err| (413, format!("n={} with {}: {}", request.n, algorithm.name(), err)))?;
+            return Ok(RunReport::run(algorithm.name(), handle.as_ref(), request.n));
+        })
+    };
+    match result {
+        Ok(report) => (200, report.to_json()),
+        Err((status, message)) => (status, error_body(&message)),
+    }
+}
+
+/// Reads one request off the connection and writes the response
+fn handle_connection(stream: TcpStream) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("").to_string();
+    let path = parts.next().unwrap_or("").to_string();
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+
+    let (status, body) = if content_length > MAX_BODY_LEN {
+        (413, error_body("request body too large"))
+    } else {
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+        handle_request(&method, &path, &body)
+    };
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Payload Too Large",
+    };
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, reason, body.len(), body
+    )?;
+    return stream.flush();
+}
+
+/// Serves requests on the address given until the listener fails. Each
+/// connection is handled on its own thread.
+pub fn serve(addr: impl ToSocketAddrs) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    for stream in listener.incoming() {
+        let stream = stream?;
+        thread::spawn(move || {
+            if let Err(err) = handle_connection(stream) {
+                eprintln!("error: {}", err);
+            }
+        });
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests() {
+        let (status, body) = handle_request("POST", "/check", br#"{"sequence": "123121321"}"#);
+        assert_eq!(status, 200);
+        assert!(RunReport::from_json(&body).unwrap().valid);
+        let (status, body) = handle_request("POST", "/check", br#"{"sequence": [1,2,1], "n": 3, "algorithm": "bruteforce"}"#);
+        assert_eq!(status, 200);
+        assert!(!RunReport::from_json(&body).unwrap().valid);
+
+        let (status, body) = handle_request("POST", "/create", br#"{"n": 4}"#);
+        assert_eq!(status, 200);
+        let report = RunReport::from_json(&body).unwrap();
+        assert_eq!((report.algorithm.as_str(), report.length, report.valid), ("bruteforce_optimise", 33, true));
+
+        assert_eq!(handle_request("POST", "/create", br#"{"n": 4, "algorithm": "x"}"#).0, 400);
+        assert_eq!(handle_request("POST", "/create", br#"{"n": 100}"#).0, 400);
+        assert_eq!(handle_request("POST", "/create", br#"{"n": 9, "algorithm": "weighted_greedy"}"#).0, 400);
+        // Supported n, but over the memory budget
+        assert_eq!(handle_request("POST", "/create", br#"{"n": 12}"#).0, 413);
+        // Too short to be a superperm, rejected before allocating for 20!
+        let (status, body) = handle_request("POST", "/check", br#"{"sequence": [20]}"#);
+        assert_eq!(status, 200);
+        assert!(!RunReport::from_json(&body).unwrap().valid);
+        assert_eq!(handle_request("POST", "/check", b"not json").0, 400);
+        assert_eq!(handle_request("GET", "/check", b"").0, 405);
+        assert_eq!(handle_request("POST", "/other", b"").0, 404);
+    }
+}
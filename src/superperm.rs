@@ -0,0 +1,327 @@
+// Wrapper type for a superpermutation sequence along with conversions to and
+// from the text formats commonly used to share them.
+use std::fmt;
+
+use crate::permutation::{is_permutation, lex_rank};
+
+/// A sequence of tokens (1,2,3, ...,n_tokens) that is intended to be a
+/// superpermutation. Nothing is checked upon construction, use a
+/// SuperPermHandling implementation to verify the sequence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SuperPerm {
+    /// The tokens making up the sequence
+    pub sequence: Vec<usize>,
+    /// The amount of unique tokens the sequence is built from
+    pub n_tokens: usize,
+}
+
+/// Errors that can arise when converting a SuperPerm to or from text or bytes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatError {
+    /// A character that doesn't represent a token was found whilst parsing
+    InvalidCharacter { index: usize, character: char },
+    /// A token is unable to be written as a single digit (i.e not within 1..=9)
+    TokenNotDigit { index: usize, token: usize },
+    /// A delimited token that isn't a positive integer was found whilst parsing
+    InvalidToken { index: usize, token: String },
+    /// The `n=<n_tokens>` header line couldn't be understood
+    InvalidHeader { line: String },
+    /// A token is too large to be packed into the binary format
+    TokenTooLarge { index: usize, token: usize },
+    /// Data in the binary format is malformed
+    InvalidBinary { reason: &'static str },
+    /// A share string is malformed
+    InvalidShareString { reason: &'static str },
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::InvalidCharacter { index, character } => {
+                write!(f, "invalid character {:?} at index {}", character, index)
+            }
+            FormatError::TokenNotDigit { index, token } => {
+                write!(f, "token {} at index {} cannot be written as a single digit", token, index)
+            }
+            FormatError::InvalidToken { index, token } => {
+                write!(f, "invalid token {:?} at token index {}", token, index)
+            }
+            FormatError::InvalidHeader { line } => {
+                write!(f, "invalid header {:?}, expected the form n=<n_tokens>", line)
+            }
+            FormatError::TokenTooLarge { index, token } => {
+                write!(f, "token {} at index {} is too large for the binary format", token, index)
+            }
+            FormatError::InvalidBinary { reason } => {
+                write!(f, "invalid binary data: {}", reason)
+            }
+            FormatError::InvalidShareString { reason } => {
+                write!(f, "invalid share string: {}", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+impl SuperPerm {
+    pub fn new(sequence: Vec<usize>, n_tokens: usize) -> SuperPerm {
+        return SuperPerm {
+            sequence,
+            n_tokens,
+        };
+    }
+
+    /// Lexicographic rank (see permutation::lex_rank) of each window of
+    /// n_tokens tokens in order, None for windows that aren't a permutation of
+    /// (1,2,3, ...,n_tokens). E.g 1213 for 2 tokens gives Some(0), Some(1),
+    /// None. Gives nothing for n_tokens = 0.
+    pub fn perm_windows(&self) -> impl Iterator<Item = Option<usize>> + '_ {
+        let windows = match self.n_tokens {
+            0 => self.sequence[..0].windows(1),
+            n => self.sequence.windows(n),
+        };
+        return windows.map(|window| if is_permutation(window) { lex_rank(window) } else { None });
+    }
+
+    /// Parses the conventional compact format where each token is written as a
+    /// single digit. E.g "123121321" is the sequence [1,2,3,1,2,1,3,2,1].
+    ///
+    /// Surrounding whitespace is ignored. n_tokens is taken to be the largest
+    /// token found in the string.
+    pub fn from_digit_str(digits: &str) -> Result<SuperPerm, FormatError> {
+        let trimmed = digits.trim();
+        // Keep track of how much whitespace was trimmed so errors point at
+        // the index within the original string
+        let offset = digits.len() - digits.trim_start().len();
+        let mut sequence: Vec<usize> = Vec::with_capacity(trimmed.len());
+        for (i, character) in trimmed.char_indices() {
+            match character.to_digit(10) {
+                Some(token) if token != 0 => sequence.push(token as usize),
+                _ => {
+                    return Err(FormatError::InvalidCharacter { index: offset + i, character });
+                }
+            }
+        }
+        let n_tokens = sequence.iter().copied().max().unwrap_or(0);
+        return Ok(SuperPerm::new(sequence, n_tokens));
+    }
+
+    /// Writes the sequence in the conventional compact digit format.
+    /// E.g [1,2,3,1,2,1,3,2,1] is written as "123121321".
+    ///
+    /// Fails if any token is outside of 1..=9 as it wouldn't be a single digit.
+    pub fn to_digit_str(&self) -> Result<String, FormatError> {
+        let mut digits = String::with_capacity(self.sequence.len());
+        for (index, token) in self.sequence.iter().enumerate() {
+            // Checked on the usize, as converting to u32 first could truncate
+            // a large token into a digit
+            match *token {
+                1..=9 => digits.push(char::from_digit(*token as u32, 10).unwrap()),
+                _ => {
+                    return Err(FormatError::TokenNotDigit { index, token: *token });
+                }
+            }
+        }
+        return Ok(digits);
+    }
+
+    /// Parses the delimited format where tokens are separated by commas and/or
+    /// whitespace. E.g "1,2,3,1,2,1,3,2,1" or "10 1 2 ...". Unlike the digit
+    /// format, tokens of any size can be represented.
+    ///
+    /// The first line may optionally be a header of the form `n=12` to set
+    /// n_tokens explicitly, otherwise it's taken to be the largest token found.
+    pub fn from_delimited_str(text: &str) -> Result<SuperPerm, FormatError> {
+        let mut body = text.trim_start();
+        let mut header_n: Option<usize> = None;
+        // Check for a header on the first line
+        let first_line = body.lines().next().unwrap_or("");
+        if let Some(value) = first_line.trim().strip_prefix("n=") {
+            let Ok(n) = value.trim().parse::<usize>() else {
+                return Err(FormatError::InvalidHeader { line: first_line.trim().to_string() });
+            };
+            header_n = Some(n);
+            body = &body[first_line.len()..];
+        }
+
+        let mut sequence: Vec<usize> = Vec::new();
+        let tokens = body
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|token| !token.is_empty());
+        for (index, token) in tokens.enumerate() {
+            match token.parse::<usize>() {
+                Ok(value) if value != 0 => sequence.push(value),
+                _ => {
+                    return Err(FormatError::InvalidToken { index, token: token.to_string() });
+                }
+            }
+        }
+        let n_tokens = header_n.unwrap_or(sequence.iter().copied().max().unwrap_or(0));
+        return Ok(SuperPerm::new(sequence, n_tokens));
+    }
+
+    /// Writes the sequence in the delimited format with a `n=<n_tokens>` header
+    /// line followed by the tokens separated by the delimiter given.
+    ///
+    /// E.g [1,2,1] for 2 tokens with ',' as the delimiter is written as "n=2\n1,2,1"
+    pub fn to_delimited_str(&self, delimiter: char) -> String {
+        let mut text = format!("n={}\n", self.n_tokens);
+        for (i, token) in self.sequence.iter().enumerate() {
+            if i != 0 {
+                text.push(delimiter);
+            }
+            text.push_str(&token.to_string());
+        }
+        return text;
+    }
+}
+
+impl fmt::Display for SuperPerm {
+    /// Digit string for up to 9 tokens, e.g "123121321", otherwise the tokens
+    /// separated by commas, e.g "10,1,2". The alternate form "{:#}" always
+    /// separates the tokens.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.n_tokens <= 9 && f.alternate() == false {
+            if let Ok(digits) = self.to_digit_str() {
+                return write!(f, "{}", digits);
+            }
+        }
+        for (i, token) in self.sequence.iter().enumerate() {
+            if i != 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}", token)?;
+        }
+        return Ok(());
+    }
+}
+
+impl From<Vec<usize>> for SuperPerm {
+    /// n_tokens is taken to be the largest token in the sequence
+    fn from(sequence: Vec<usize>) -> SuperPerm {
+        let n_tokens = sequence.iter().copied().max().unwrap_or(0);
+        return SuperPerm::new(sequence, n_tokens);
+    }
+}
+
+impl TryFrom<&str> for SuperPerm {
+    type Error = FormatError;
+    /// Parses either a digit string or the delimited format. Text with a
+    /// comma, a `n=` header or whitespace between tokens is taken to be
+    /// delimited. E.g "123121321" and "1,2,3,1,2,1,3,2,1" are the same sequence
+    fn try_from(text: &str) -> Result<SuperPerm, FormatError> {
+        let trimmed = text.trim();
+        if trimmed.contains(|c: char| c == ',' || c == '=' || c.is_whitespace()) {
+            return SuperPerm::from_delimited_str(text);
+        }
+        return SuperPerm::from_digit_str(text);
+    }
+}
+
+impl From<SuperPerm> for String {
+    /// Digit string if every token is a single digit, otherwise the delimited
+    /// format with commas. Either way it parses back with SuperPerm::try_from,
+    /// but a digit string doesn't record n_tokens, so it comes back as the
+    /// largest token. E.g 121 with n_tokens 3 parses back with n_tokens 2
+    fn from(superperm: SuperPerm) -> String {
+        return superperm.to_digit_str().unwrap_or_else(|_| superperm.to_delimited_str(','));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digit_str_round_trip() {
+        let superperm = SuperPerm::from_digit_str("123121321").unwrap();
+        assert_eq!(superperm.sequence, vec![1,2,3,1,2,1,3,2,1]);
+        assert_eq!(superperm.n_tokens, 3);
+        assert_eq!(superperm.to_digit_str().unwrap(), "123121321");
+    }
+
+    #[test]
+    fn digit_str_invalid_characters() {
+        assert_eq!(
+            SuperPerm::from_digit_str("12a3"),
+            Err(FormatError::InvalidCharacter { index: 2, character: 'a' })
+        );
+        assert_eq!(
+            SuperPerm::from_digit_str("  1203"),
+            Err(FormatError::InvalidCharacter { index: 4, character: '0' })
+        );
+        assert_eq!(
+            SuperPerm::new(vec![1,10,2], 10).to_digit_str(),
+            Err(FormatError::TokenNotDigit { index: 1, token: 10 })
+        );
+        // 2^32 + 5 isn't 5
+        let large = (1usize << 32) + 5;
+        assert_eq!(
+            SuperPerm::new(vec![1,large], 2).to_digit_str(),
+            Err(FormatError::TokenNotDigit { index: 1, token: large })
+        );
+    }
+
+    #[test]
+    fn delimited_str_round_trip() {
+        let superperm = SuperPerm::new(vec![10,1,2,12,11,1], 12);
+        for delimiter in [',', ' '] {
+            let text = superperm.to_delimited_str(delimiter);
+            assert_eq!(SuperPerm::from_delimited_str(&text).unwrap(), superperm);
+        }
+        // Header is optional and mixed delimiters are accepted
+        let parsed = SuperPerm::from_delimited_str("1, 2,3\n 1 2").unwrap();
+        assert_eq!(parsed, SuperPerm::new(vec![1,2,3,1,2], 3));
+    }
+
+    #[test]
+    fn delimited_str_invalid() {
+        assert_eq!(
+            SuperPerm::from_delimited_str("n=x\n1,2"),
+            Err(FormatError::InvalidHeader { line: String::from("n=x") })
+        );
+        assert_eq!(
+            SuperPerm::from_delimited_str("1,2,-3"),
+            Err(FormatError::InvalidToken { index: 2, token: String::from("-3") })
+        );
+    }
+
+    #[test]
+    fn conversions() {
+        let superperm = SuperPerm::from(vec![1,2,3,1,2,1,3,2,1]);
+        assert_eq!(superperm.n_tokens, 3);
+        assert_eq!(SuperPerm::try_from("123121321"), Ok(superperm.clone()));
+        assert_eq!(SuperPerm::try_from("1,2,3,1,2,1,3,2,1"), Ok(superperm.clone()));
+        assert_eq!(String::from(superperm), "123121321");
+        let partial = String::from(SuperPerm::new(vec![1,2,1], 3));
+        assert_eq!(SuperPerm::try_from(partial.as_str()), Ok(SuperPerm::new(vec![1,2,1], 2)));
+
+        let large = SuperPerm::new(vec![10,1,2,12,11,1], 12);
+        let text: String = large.clone().into();
+        assert_eq!(text, "n=12\n10,1,2,12,11,1");
+        assert_eq!(SuperPerm::try_from(text.as_str()), Ok(large));
+        assert_eq!(
+            SuperPerm::try_from("12x"),
+            Err(FormatError::InvalidCharacter { index: 2, character: 'x' })
+        );
+    }
+
+    #[test]
+    fn display() {
+        let superperm = SuperPerm::new(vec![1,2,3,1,2,1,3,2,1], 3);
+        assert_eq!(superperm.to_string(), "123121321");
+        assert_eq!(format!("{:#}", superperm), "1,2,3,1,2,1,3,2,1");
+        assert_eq!(SuperPerm::new(vec![10,1,2], 10).to_string(), "10,1,2");
+    }
+
+    #[test]
+    fn windows() {
+        let superperm = SuperPerm::new(vec![1,2,1,3], 2);
+        assert_eq!(superperm.perm_windows().collect::<Vec<_>>(), vec![Some(0), Some(1), None]);
+        let superperm = SuperPerm::new(vec![1,2,3,1,2,1,3,2,1], 3);
+        assert_eq!(superperm.perm_windows().flatten().collect::<Vec<_>>(), vec![0, 3, 4, 2, 1, 5]);
+        assert_eq!(SuperPerm::new(vec![1], 3).perm_windows().count(), 0);
+        assert_eq!(SuperPerm::new(vec![1], 0).perm_windows().count(), 0);
+    }
+}
@@ -0,0 +1,141 @@
+// Experimental: universal cycles of k-subsets. A universal cycle is a cyclic
+// sequence where every k-subset of the tokens (1,2,3, ...,n) appears exactly
+// once as a window, in any order within the window. E.g 1234524135 for n=5
+// and k=2 has the windows 12, 23, 34, 45, 52, 24, 41, 13, 35 and 51.
+//
+// They can only exist when k divides C(n-1, k-1), and finding them is done by
+// a plain backtracking search, so only small n are practical.
+
+/// Binomial coefficient C(n, k). None on overflow.
+fn binomial(n: usize, k: usize) -> Option<usize> {
+    if k > n {
+        return Some(0);
+    }
+    let mut total: usize = 1;
+    for i in 0..k.min(n - k) {
+        total = total.checked_mul(n - i)? / (i + 1);
+    }
+    return Some(total);
+}
+
+/// Rank of a k-subset of the tokens (1,2,3, ...,n) in colexicographic order,
+/// given in any order. E.g for k=2 the subsets 12, 13, 23, 14 have the ranks
+/// 0, 1, 2, 3. None if a token is repeated or outside of 1..=n.
+pub fn subset_rank(subset: &[usize], n: usize) -> Option<usize> {
+    let mut sorted = subset.to_vec();
+    sorted.sort_unstable();
+    let mut rank = 0;
+    for (i, token) in sorted.iter().enumerate() {
+        if *token == 0 || *token > n || (i > 0 && sorted[i - 1] == *token) {
+            return None;
+        }
+        rank += binomial(token - 1, i + 1)?;
+    }
+    return Some(rank);
+}
+
+/// Checks whether every k-subset of the tokens (1,2,3, ...,n) appears as a
+/// window of the sequence read cyclically
+pub fn check_subset_cycle(sequence: &[usize], n: usize, k: usize) -> bool {
+    let Some(total) = binomial(n, k) else {
+        return false;
+    };
+    if k == 0 {
+        return true;
+    }
+    if sequence.is_empty() {
+        return total == 0;
+    }
+    let cyclic: Vec<usize> = sequence.iter().copied().cycle().take(sequence.len() + k - 1).collect();
+    let mut subset_checklist = vec![false; total];
+    for window in cyclic.windows(k) {
+        if let Some(rank) = subset_rank(window, n) {
+            subset_checklist[rank] = true;
+        }
+    }
+    return subset_checklist.iter().all(|checked| *checked);
+}
+
+/// Searches for a universal cycle of the k-subsets of the tokens
+/// (1,2,3, ...,n), starting with the tokens 1..=k. None if there isn't one.
+///
+/// The search is exhaustive and takes exponential time, so is only meant for
+/// small cases like n=7, k=2.
+pub fn find_subset_cycle(n: usize, k: usize) -> Option<Vec<usize>> {
+    if k == 0 || k > n {
+        return Some(Vec::new());
+    }
+    let total = binomial(n, k)?;
+    let mut sequence: Vec<usize> = (1..k + 1).collect();
+    let mut seen = vec![false; total];
+    seen[0] = true;
+    if extend_cycle(&mut sequence, &mut seen, n, k, total) {
+        return Some(sequence);
+    }
+    return None;
+}
+
+/// Backtracking step of find_subset_cycle. Returns whether the sequence could
+/// be completed, leaving it completed if so.
+fn extend_cycle(sequence: &mut Vec<usize>, seen: &mut Vec<bool>, n: usize, k: usize, total: usize) -> bool {
+    if sequence.len() == total {
+        // The windows wrapping around to the start must be new subsets as well
+        let mut wrapped = Vec::new();
+        let mut all_new = true;
+        for i in sequence.len() - k + 1..sequence.len() {
+            let window: Vec<usize> = sequence.iter().cycle().skip(i).take(k).copied().collect();
+            match subset_rank(&window, n) {
+                Some(rank) if seen[rank] == false => {
+                    seen[rank] = true;
+                    wrapped.push(rank);
+                }
+                _ => {
+                    all_new = false;
+                    break;
+                }
+            }
+        }
+        for rank in wrapped {
+            seen[rank] = false;
+        }
+        return all_new;
+    }
+    for token in 1..n + 1 {
+        sequence.push(token);
+        if let Some(rank) = subset_rank(&sequence[sequence.len() - k..], n) {
+            if seen[rank] == false {
+                seen[rank] = true;
+                if extend_cycle(sequence, seen, n, k, total) {
+                    return true;
+                }
+                seen[rank] = false;
+            }
+        }
+        sequence.pop();
+    }
+    return false;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subset_cycles() {
+        assert_eq!(subset_rank(&[1,2], 5), Some(0));
+        assert_eq!(subset_rank(&[4,1], 5), Some(3));
+        assert_eq!(subset_rank(&[2,2], 5), None);
+        assert!(check_subset_cycle(&[1,2,3,4,5,2,4,1,3,5], 5, 2));
+        assert!(!check_subset_cycle(&[1,2,3,4,5,2,4,1,3], 5, 2));
+
+        let cycle = find_subset_cycle(5, 2).unwrap();
+        assert_eq!(cycle.len(), 10);
+        assert!(check_subset_cycle(&cycle, 5, 2));
+        let cycle = find_subset_cycle(7, 2).unwrap();
+        assert_eq!(cycle.len(), 21);
+        assert!(check_subset_cycle(&cycle, 7, 2));
+        assert_eq!(find_subset_cycle(4, 3), Some(vec![1,2,3,4]));
+        // 2 doesn't divide C(3,1) so there is no universal cycle
+        assert_eq!(find_subset_cycle(4, 2), None);
+    }
+}
@@ -0,0 +1,140 @@
+// Differential checking of the implementations against each other. Every
+// implementation should accept what any of them creates, and all of them
+// should reach the same verdict on any other sequence, so a disagreement
+// points at a bug in one of them.
+use rand::Rng;
+
+use crate::base::all_handles;
+#[cfg(feature = "exhaustive")]
+use crate::mixed_radix::MixedRadix;
+
+/// Where a sequence given to the checkers came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    /// Created by the implementation named
+    Created(&'static str),
+    /// A random mutation of a sequence created by the implementation named
+    Mutated(&'static str),
+    /// One of every possible sequence up to some length
+    Enumerated,
+}
+
+/// A sequence the checkers didn't all accept (if created) or didn't agree on
+/// (if mutated)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Disagreement {
+    pub origin: Origin,
+    pub n: usize,
+    pub sequence: Vec<usize>,
+    /// Each implementation's name with whether it said the sequence is a superperm
+    pub verdicts: Vec<(&'static str, bool)>,
+}
+
+/// Mutations made of each created sequence
+const MUTATIONS: usize = 16;
+
+/// Deletes, replaces or swaps a random token, or does nothing to an empty sequence
+fn mutate<R: Rng + ?Sized>(sequence: &mut Vec<usize>, n: usize, rng: &mut R) {
+    if sequence.is_empty() {
+        return;
+    }
+    let i = rng.gen_range(0..sequence.len());
+    match rng.gen_range(0..3) {
+        0 => {
+            sequence.remove(i);
+        }
+        1 => sequence[i] = rng.gen_range(1..n + 1),
+        _ => {
+            let j = rng.gen_range(0..sequence.len());
+            sequence.swap(i, j);
+        }
+    }
+}
+
+/// Creates a superperm of n tokens with every implementation and checks each
+/// with every implementation, then does the same for random mutations of
+/// them, giving the sequences the checkers got wrong or disagreed on. Sequences
+/// longer than max_len are skipped to keep large n quick.
+pub fn cross_check<R: Rng + ?Sized>(n: usize, max_len: usize, rng: &mut R) -> Vec<Disagreement> {
+    let handles = all_handles();
+    let verdicts = |sequence: &Vec<usize>| -> Vec<(&'static str, bool)> {
+        return handles.iter().map(|(name, handle)| (*name, handle.check_superperm(sequence, n))).collect();
+    };
+
+    let mut disagreements = Vec::new();
+    for (creator, handle) in &handles {
+        let created = handle.create_superperm(n);
+        if created.len() > max_len {
+            continue;
+        }
+        let created_verdicts = verdicts(&created);
+        if created_verdicts.iter().any(|(_, valid)| *valid == false) {
+            disagreements.push(Disagreement { origin: Origin::Created(creator), n, sequence: created.clone(), verdicts: created_verdicts });
+        }
+        for _ in 0..MUTATIONS {
+            let mut mutated = created.clone();
+            mutate(&mut mutated, n, rng);
+            let mutated_verdicts = verdicts(&mutated);
+            if mutated_verdicts.iter().any(|(_, valid)| *valid != mutated_verdicts[0].1) {
+                disagreements.push(Disagreement { origin: Origin::Mutated(creator), n, sequence: mutated, verdicts: mutated_verdicts });
+            }
+        }
+    }
+    return disagreements;
+}
+
+/// Checks every sequence of the tokens (1,2,3, ...,n) up to max_len long
+/// with every implementation, giving the sequences they don't all agree on.
+/// There are n^max_len sequences of the longest length, so this is only
+/// practical for tiny n: every sequence up to the minimal length is 29523
+/// sequences for n=3 but over 10^19 for n=4.
+///
+/// E.g exhaustive_cross_check(3, 9) is empty if the checkers agree
+#[cfg(feature = "exhaustive")]
+pub fn exhaustive_cross_check(n: usize, max_len: usize) -> Vec<Disagreement> {
+    let handles = all_handles();
+    let mut disagreements = Vec::new();
+    if n == 0 {
+        return disagreements;
+    }
+    for len in 0..max_len + 1 {
+        let system = MixedRadix::new(vec![n; len]);
+        for digits in &system {
+            let sequence: Vec<usize> = digits.iter().map(|digit| digit + 1).collect();
+            let verdicts: Vec<(&'static str, bool)> = handles.iter()
+                .map(|(name, handle)| (*name, handle.check_superperm(&sequence, n)))
+                .collect();
+            if verdicts.iter().any(|(_, valid)| *valid != verdicts[0].1) {
+                disagreements.push(Disagreement { origin: Origin::Enumerated, n, sequence, verdicts });
+            }
+        }
+    }
+    return disagreements;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn implementations_agree() {
+        let mut rng = StdRng::seed_from_u64(682);
+        for n in 0..6 {
+            assert_eq!(cross_check(n, 200, &mut rng), vec![], "n={}", n);
+        }
+        // Too long to be checked at all
+        assert_eq!(cross_check(4, 10, &mut rng), vec![]);
+    }
+
+    #[cfg(feature = "exhaustive")]
+    #[test]
+    fn implementations_agree_exhaustively() {
+        for n in 1..4 {
+            assert_eq!(exhaustive_cross_check(n, crate::records::minimal_length(n).unwrap()), vec![], "n={}", n);
+        }
+        // Every sequence up to the minimal length is out of reach for n=4
+        assert_eq!(exhaustive_cross_check(4, 7), vec![]);
+    }
+}
@@ -0,0 +1,48 @@
+// JavaScript bindings for wasm32 builds, e.g for an interactive web demo.
+// Build with `--target wasm32-unknown-unknown --no-default-features
+// --features wasm-bindgen` and generate the JS glue with wasm-bindgen.
+//
+// Sequences cross the boundary in the digit format, e.g "123121321", so n is
+// limited to 9 here.
+use wasm_bindgen::prelude::*;
+
+use crate::base::{NTooLarge, SuperPermHandling};
+use crate::bruteforce_optimise;
+use crate::report::RunReport;
+use crate::superperm::SuperPerm;
+
+/// Largest n a digit string can hold
+const MAX_N: usize = 9;
+
+/// Rejects n too large for the digit format, before any work is done for it
+fn check_n(n: usize) -> Result<(), NTooLarge> {
+    if n > MAX_N {
+        return Err(NTooLarge { n, max_supported: MAX_N });
+    }
+    return Ok(());
+}
+
+/// Creates a superpermutation of the tokens 1..=n as a digit string
+#[wasm_bindgen(js_name = createSuperperm)]
+pub fn create_superperm(n: usize) -> Result<String, JsError> {
+    check_n(n)?;
+    let sequence = bruteforce_optimise::Handle.create_superperm(n);
+    return Ok(SuperPerm::new(sequence, n).to_digit_str()?);
+}
+
+/// Checks whether a digit string is a superpermutation of the tokens 1..=n
+#[wasm_bindgen(js_name = checkSuperperm)]
+pub fn check_superperm(digits: &str, n: usize) -> Result<bool, JsError> {
+    check_n(n)?;
+    let superperm = SuperPerm::from_digit_str(digits)?;
+    return Ok(bruteforce_optimise::Handle.check_superperm(&superperm.sequence, n));
+}
+
+/// The analysis report (see report::RunReport) of a digit string as JSON
+#[wasm_bindgen(js_name = analyzeSuperperm)]
+pub fn analyze_superperm(digits: &str, n: usize) -> Result<String, JsError> {
+    check_n(n)?;
+    let superperm = SuperPerm::from_digit_str(digits)?;
+    let report = RunReport::analyze("input", &bruteforce_optimise::Handle, superperm.sequence, n);
+    return Ok(report.to_json());
+}
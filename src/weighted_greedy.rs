@@ -0,0 +1,88 @@
+// Greedy creation where the penalty for each size of step is configurable.
+// The plain greedy creators always take the largest overlap available,
+// i.e. the first match wins. Here appending k tokens costs weights[k-1], so
+// e.g making 2 token steps cheaper than their length biases the construction
+// towards moving between 2-cycles early, or dearer to save them for later.
+//
+// The weights are a policy::Policy, so creation is policy::greedy_with_policy.
+// Checking is the same as bruteforce_optimise.
+use crate::base::*;
+use crate::bruteforce_optimise;
+use crate::policy::{greedy_with_policy, Extension, Policy, State};
+
+/// Weighted greedy creation: starting from (1,2,3, ...,n_tokens), the
+/// unvisited permutation with the smallest weight for the amount of tokens
+/// appended to reach it is visited next, ties going to the lowest
+/// lexicographic rank. Only practical up to n=6, see local_search, and
+/// refused above MAX_N where the (n!)² cost matrix would take gigabytes.
+///
+/// The default weighs every step by its length, which is how it's registered
+/// as Algorithm::WeightedGreedy.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct WeightedGreedy {
+    /// Penalty for appending k tokens at index k-1. Steps with no weight
+    /// given cost their amount of tokens, so an empty vector weighs every
+    /// step by its length
+    pub weights: Vec<f64>,
+}
+
+/// Largest n WeightedGreedy creates for, a 25MB cost matrix. n=8 would be 1.6GB
+pub const MAX_N: usize = 7;
+
+impl WeightedGreedy {
+    pub fn new(weights: Vec<f64>) -> WeightedGreedy {
+        return WeightedGreedy { weights };
+    }
+
+    /// Penalty for appending the amount of tokens given
+    pub fn weight(&self, tokens: usize) -> f64 {
+        return self.weights.get(tokens.wrapping_sub(1)).copied().unwrap_or(tokens as f64);
+    }
+}
+
+impl SuperPermHandling for WeightedGreedy {
+    fn check_superperm(&self, sequence: &Vec<usize>, n_tokens: usize) -> bool {
+        return bruteforce_optimise::Handle.check_superperm(sequence, n_tokens);
+    }
+
+    fn max_create_n(&self) -> usize {
+        return MAX_N;
+    }
+
+    /// Panics if n_tokens is above MAX_N, see try_create_superperm
+    fn create_superperm(&self, n_tokens: usize) -> Vec<usize> {
+        assert!(n_tokens <= MAX_N, "weighted greedy creation is limited to n={}", MAX_N);
+        return greedy_with_policy(n_tokens, self);
+    }
+}
+
+/// Scores each extension by minus the weight of its cost, so the lightest
+/// step is taken
+impl Policy for WeightedGreedy {
+    fn scores(&self, _: &State<'_>, extensions: &[Extension]) -> Vec<f64> {
+        return extensions.iter().map(|extension| -self.weight(extension.cost)).collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weighted_creation() {
+        let plain = WeightedGreedy::default();
+        assert_eq!(plain.weight(3), 3.0);
+        for n in 1..6 {
+            assert!(plain.check_superperm(&plain.create_superperm(n), n), "n={}", n);
+        }
+        assert_eq!(plain.create_superperm(4).len(), 33);
+        assert_eq!(plain.try_create_superperm(MAX_N + 1), Err(NTooLarge { n: MAX_N + 1, max_supported: MAX_N }));
+
+        // Preferring to append 2 tokens over 1 makes longer sequences, but
+        // they're still valid
+        let biased = WeightedGreedy::new(vec![2.0, 1.0]);
+        let sequence = biased.create_superperm(4);
+        assert!(biased.check_superperm(&sequence, 4));
+        assert!(sequence.len() > 33);
+    }
+}
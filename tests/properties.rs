@@ -0,0 +1,75 @@
+// Property tests over randomly generated permutations and sequences, covering
+// the invariants the handwritten cases in test_all.rs only spot check
+use proptest::prelude::*;
+use super_permutations_attempt::{bruteforce, bruteforce_optimise};
+use super_permutations_attempt::base::SuperPermHandling;
+use super_permutations_attempt::permutation::{lex_rank, lex_unrank, PermutationMapper, RankOrder};
+
+/// A permutation of 1..=n for n in 1..=8
+fn permutation() -> impl Strategy<Value = Vec<usize>> {
+    return (1usize..9).prop_flat_map(|n| Just((1..n + 1).collect::<Vec<usize>>()).prop_shuffle());
+}
+
+/// n in 1..=4 with a random sequence over 1..=n
+fn sequence(max_len: usize) -> impl Strategy<Value = (usize, Vec<usize>)> {
+    return (1usize..5).prop_flat_map(move |n| (Just(n), prop::collection::vec(1..n + 1, 0..max_len)));
+}
+
+/// n in 1..=4 with a random sequence over 1..=n and a relabeling of 1..=n
+fn labelled_sequence() -> impl Strategy<Value = (usize, Vec<usize>, Vec<usize>)> {
+    return sequence(40).prop_flat_map(|(n, sequence)| {
+        (Just(n), Just(sequence), Just((1..n + 1).collect::<Vec<usize>>()).prop_shuffle())
+    });
+}
+
+/// Applies the relabeling to every token, token t becoming labels[t - 1]
+fn relabel(sequence: &[usize], labels: &[usize]) -> Vec<usize> {
+    return sequence.iter().map(|token| labels[token - 1]).collect();
+}
+
+fn handles() -> Vec<Box<dyn SuperPermHandling>> {
+    return vec![Box::new(bruteforce::Handle), Box::new(bruteforce_optimise::Handle)];
+}
+
+proptest! {
+    #[test]
+    fn rank_round_trip(perm in permutation()) {
+        let tokens: Vec<usize> = (1..perm.len() + 1).collect();
+        for order in [RankOrder::InsertionShift, RankOrder::Lexicographic] {
+            let mapper = PermutationMapper::with_order(tokens.clone(), order);
+            let value = mapper.perm_to_value(&perm).unwrap();
+            prop_assert_eq!(mapper.value_to_perm(&value), perm.clone());
+        }
+        prop_assert_eq!(lex_unrank(lex_rank(&perm).unwrap(), &tokens), Some(perm));
+    }
+
+    #[test]
+    fn value_round_trip(perm in permutation(), value in any::<usize>()) {
+        let mapper = PermutationMapper::new((1..perm.len() + 1).collect());
+        let value = value % mapper.mixed_radix_sys.max_value;
+        prop_assert_eq!(mapper.perm_to_value(&mapper.value_to_perm(&value)), Some(value));
+    }
+
+    #[test]
+    fn checking_survives_concatenation((n, middle) in sequence(40), padding in prop::collection::vec(1usize..5, 0..20)) {
+        let padding: Vec<usize> = padding.into_iter().map(|token| token.min(n)).collect();
+        for handle in handles() {
+            let superperm = handle.create_superperm(n);
+            prop_assert!(handle.check_superperm(&[padding.clone(), superperm, padding.clone()].concat(), n));
+            // Anything valid stays valid with more tokens around it
+            if handle.check_superperm(&middle, n) {
+                prop_assert!(handle.check_superperm(&[padding.clone(), middle.clone(), padding.clone()].concat(), n));
+            }
+        }
+    }
+
+    #[test]
+    fn checking_ignores_labels((n, sequence, labels) in labelled_sequence()) {
+        for handle in handles() {
+            let superperm = handle.create_superperm(n);
+            prop_assert!(handle.check_superperm(&relabel(&superperm, &labels), n));
+            let verdict = handle.check_superperm(&sequence, n);
+            prop_assert_eq!(handle.check_superperm(&relabel(&sequence, &labels), n), verdict);
+        }
+    }
+}
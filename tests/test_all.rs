@@ -1,5 +1,8 @@
 use super_permutations_attempt::{bruteforce, bruteforce_optimise};
-use super_permutations_attempt::base::SuperPermHandling;
+use super_permutations_attempt::base::{
+    long_enough, Algorithm, CheckError, CheckMode, NTooLarge, SuperPermHandling, MAX_CREATE_N, MAX_SUPPORTED_N,
+};
+use super_permutations_attempt::fixtures::verify_against_fixtures;
 use rand::Rng;
 
 
@@ -10,9 +13,35 @@ fn common_checks(superperm_h: impl SuperPermHandling) {
         assert!(superperm_h.check_superperm(&superperm_h.create_superperm(n), n));
     }
 
+    // n = 0 and n = 1
+    assert!(superperm_h.create_superperm(0).is_empty());
+    assert!(superperm_h.check_superperm(&vec![2,1], 0));
+    assert_eq!(superperm_h.create_superperm(1), vec![1]);
+    assert!(superperm_h.check_superperm(&vec![1], 1));
+    assert!(superperm_h.check_superperm(&vec![2,1,2], 1));
+    assert!(!superperm_h.check_superperm(&vec![2], 1));
+
+    // n! too large to count
+    let too_large = NTooLarge { n: MAX_SUPPORTED_N + 1, max_supported: MAX_SUPPORTED_N };
+    assert_eq!(superperm_h.try_create_superperm(MAX_SUPPORTED_N + 1), Err(too_large));
+    assert_eq!(superperm_h.try_check_superperm(&vec![1,2,3], MAX_SUPPORTED_N + 1), Err(too_large));
+    assert_eq!(superperm_h.try_check_superperm(&vec![1,2,1], 2), Ok(true));
+    // Too large to create, and too short to check without allocating n! entries
+    let too_large = NTooLarge { n: MAX_CREATE_N + 1, max_supported: MAX_CREATE_N };
+    assert_eq!(superperm_h.try_create_superperm(MAX_CREATE_N + 1), Err(too_large));
+    assert!(!superperm_h.check_superperm(&vec![1,20], 20));
+    assert_eq!(superperm_h.try_check_superperm(&vec![13], 13), Ok(false));
+    assert!(!long_enough(7, 3) && long_enough(8, 3) && long_enough(0, 0));
+
     // invalid case
     assert!(!superperm_h.check_superperm(&vec![1,2,3,2], 3));
+    // sequences shorter than n
+    for n in 1..6 {
+        assert!(!superperm_h.check_superperm(&(1..n).collect(), n));
+        assert!(!superperm_h.check_superperm(&vec![], n));
+    }
     // valid cases
+    assert!(superperm_h.check_superperm(&vec![], 0));
     assert!(superperm_h.check_superperm(&vec![1,2,3,1,2,1,3,2,1], 3));
     assert!(superperm_h.check_superperm(&vec![1,2,3,4,1,2,3,1,4,2,3,1,2,4,3,1,2,1,3,4,2,1,3,2,4,1,3,2,1,4,3,2,1], 4));
     // fuzz testing valid cases (appending random numbers at beginning/end
@@ -27,6 +56,21 @@ fn common_checks(superperm_h: impl SuperPermHandling) {
         assert!(superperm_h.check_superperm(&vec, 3));
     }
 
+    // foreign tokens and padding are tolerated or rejected depending on the mode
+    let foreign = vec![7,1,2,3,1,2,1,3,2,1,0];
+    assert_eq!(superperm_h.check_superperm_with(&foreign, 3, CheckMode::Lenient), Ok(true));
+    assert_eq!(superperm_h.check_superperm_with(&foreign, 3, CheckMode::RejectForeignTokens), Err(CheckError::ForeignToken { index: 0, token: 7 }));
+    assert_eq!(superperm_h.check_superperm_with(&vec![1,2,4,1], 3, CheckMode::Lenient), Ok(false));
+    assert_eq!(superperm_h.check_superperm_with(&vec![1,2,4,1], 3, CheckMode::Strict), Err(CheckError::ForeignToken { index: 2, token: 4 }));
+    let padded = vec![2,2,1,2,3,1,2,1,3,2,1,1];
+    assert_eq!(superperm_h.check_superperm_with(&padded, 3, CheckMode::RejectForeignTokens), Ok(true));
+    assert_eq!(superperm_h.check_superperm_with(&padded, 3, CheckMode::Strict), Err(CheckError::Padding { leading: 2, trailing: 1 }));
+    assert_eq!(superperm_h.check_superperm_with(&padded[2..11].to_vec(), 3, CheckMode::Strict), Ok(true));
+    assert_eq!(superperm_h.check_superperm_with(&vec![1,1], 3, CheckMode::Strict), Ok(false));
+
+    // embedded known sequences and near misses
+    assert_eq!(verify_against_fixtures(&superperm_h), vec![]);
+
 }
 
 #[test]
@@ -38,3 +82,31 @@ fn bruteforce_test() {
 fn bruteforce_optimise_test() {
     common_checks(bruteforce_optimise::Handle{});
 }
+
+#[test]
+fn pinned_outputs() {
+    // The creation order is part of the API, see the Handle docs
+    let expected: [Vec<usize>; 4] = [
+        vec![1],
+        vec![1,2,1],
+        vec![1,2,3,1,2,1,3,2,1],
+        vec![1,2,3,4,1,2,3,1,4,2,3,1,2,4,3,1,2,1,3,4,2,1,3,2,4,1,3,2,1,4,3,2,1],
+    ];
+    for (i, sequence) in expected.iter().enumerate() {
+        assert_eq!(&bruteforce::Handle.create_superperm(i + 1), sequence);
+        assert_eq!(&bruteforce_optimise::Handle.create_superperm(i + 1), sequence);
+    }
+    // The compile time construction agrees
+    assert_eq!(super_permutations_attempt::superperm!(4), &expected[3][..]);
+}
+
+#[test]
+fn algorithm_registry() {
+    for algorithm in Algorithm::ALL {
+        assert_eq!(algorithm.name().parse::<Algorithm>(), Ok(*algorithm));
+        assert_eq!(algorithm.to_string(), algorithm.name());
+        assert!(algorithm.handle().check_superperm(&algorithm.handle().create_superperm(4), 4));
+    }
+    assert_eq!("bruteforce-optimise".parse::<Algorithm>(), Ok(Algorithm::BruteforceOptimise));
+    assert!("unknown".parse::<Algorithm>().unwrap_err().contains("bruteforce_optimise"));
+}